@@ -0,0 +1,348 @@
+use either::Either;
+use inkwell::attributes::{Attribute, AttributeLoc};
+use inkwell::values::BasicMetadataValueEnum;
+
+use super::*;
+
+// Structs cross function boundaries the way the SysV x86-64 ABI passes them, so calls to
+// and from C functions agree on where each struct lives. Tuples and enums are LLVM structs
+// as well and are passed like a C struct or tagged union. A struct larger than two
+// eightbytes is passed in memory: the caller passes a pointer to a `byval` copy for a
+// param, or a `sret` pointer to return into. Smaller structs are split into eightbytes,
+// each passed in an SSE register if it only holds floats and in an integer register
+// otherwise. A param whose eightbytes don't all fit in the registers that are left is
+// passed in memory instead. Every other type is passed as its LLVM value.
+
+const EIGHTBYTE: u64 = 8;
+
+// The integer and SSE registers SysV x86-64 passes args in
+const INT_REGS: u32 = 6;
+const SSE_REGS: u32 = 8;
+
+// How a value is passed to or returned from a function
+pub(super) enum PassMode<'ctx> {
+    Direct,
+    // As the LLVM types of its eightbytes
+    Cast(Vec<BasicTypeEnum<'ctx>>),
+    // Through a pointer to the LLVM type
+    Indirect(BasicTypeEnum<'ctx>),
+}
+
+impl<'ctx> Codegen<'ctx> {
+    // How the return value and each param of a function are passed. Params are assigned
+    // registers in order, so whether a struct fits depends on the params before it
+    pub(super) fn pass_modes(
+        &self, param_tys: &[Type], ret_ty: &Type,
+    ) -> Result<(PassMode<'ctx>, Vec<PassMode<'ctx>>), String> {
+        let ret_mode = self.pass_mode(ret_ty)?;
+
+        // The `sret` pointer takes the first integer register
+        let (mut int_regs, mut sse_regs) = (INT_REGS, SSE_REGS);
+        if let PassMode::Indirect(_) = ret_mode {
+            int_regs -= 1;
+        }
+
+        let mut param_modes = Vec::with_capacity(param_tys.len());
+        for param_ty in param_tys {
+            let mode = self.pass_mode(param_ty)?;
+            let (int_needed, sse_needed) = match &mode {
+                PassMode::Direct => self.count_registers(&[self.get_llvm_basic_type(param_ty)?]),
+                PassMode::Cast(tys) => self.count_registers(tys),
+                PassMode::Indirect(_) => (0, 0),
+            };
+
+            // A struct is passed in memory as a whole if any of its eightbytes don't fit.
+            // Scalars that don't fit go on the stack without changing their LLVM type.
+            // Either way, the registers are left for the params after it
+            if int_needed <= int_regs && sse_needed <= sse_regs {
+                int_regs -= int_needed;
+                sse_regs -= sse_needed;
+                param_modes.push(mode);
+            } else if let PassMode::Cast(_) = mode {
+                param_modes.push(PassMode::Indirect(self.get_llvm_basic_type(param_ty)?));
+            } else {
+                param_modes.push(mode);
+            }
+        }
+        Ok((ret_mode, param_modes))
+    }
+
+    // The integer and SSE registers that values of `tys` take up
+    fn count_registers(&self, tys: &[BasicTypeEnum<'ctx>]) -> (u32, u32) {
+        let mut scalars = vec![];
+        for ty in tys {
+            self.flatten_scalars(*ty, 0, &mut scalars);
+        }
+        scalars.iter().fold((0, 0), |(int, sse), (_, ty)| match ty {
+            BasicTypeEnum::FloatType(_) | BasicTypeEnum::VectorType(_) => (int, sse + 1),
+            BasicTypeEnum::IntType(int_ty) if int_ty.get_bit_width() > 64 => (int + 2, sse),
+            _ => (int + 1, sse),
+        })
+    }
+
+    fn pass_mode(&self, ty: &Type) -> Result<PassMode<'ctx>, String> {
+        let struct_ty = match ty {
+            Type::Comp(_) | Type::Tuple(_) | Type::Enum(_) => self.get_llvm_basic_type(ty)?,
+            _ => return Ok(PassMode::Direct),
+        };
+
+        let size = self.target_data.get_abi_size(&struct_ty);
+        if size == 0 {
+            return Ok(PassMode::Direct);
+        }
+        if size > 2 * EIGHTBYTE {
+            return Ok(PassMode::Indirect(struct_ty));
+        }
+
+        let mut scalars = vec![];
+        self.flatten_scalars(struct_ty, 0, &mut scalars);
+
+        // A packed struct with a field that's not at its natural alignment goes in memory
+        if scalars.iter().any(|(offset, ty)| offset % self.target_data.get_abi_alignment(ty) as u64 != 0) {
+            return Ok(PassMode::Indirect(struct_ty));
+        }
+
+        let mut eightbytes = vec![];
+        for start in (0..size).step_by(EIGHTBYTE as usize) {
+            let end = size.min(start + EIGHTBYTE);
+            let overlapping = scalars
+                .iter()
+                .filter(|(offset, ty)| *offset < end && offset + self.target_data.get_abi_size(ty) > start)
+                .map(|(_, ty)| *ty)
+                .collect::<Vec<_>>();
+
+            let sse = !overlapping.is_empty() && overlapping.iter().all(|ty| ty.is_float_type());
+            eightbytes.push(match overlapping.as_slice() {
+                [single] if sse => *single,
+                [_, _] if sse && overlapping.iter().all(|ty| self.target_data.get_abi_size(ty) == 4) => {
+                    self.context.f32_type().vec_type(2).as_basic_type_enum()
+                },
+                _ => self.context.custom_width_int_type(((end - start) * 8) as u32).as_basic_type_enum(),
+            });
+        }
+        Ok(PassMode::Cast(eightbytes))
+    }
+
+    // Collect the scalars in `ty` along with their offsets
+    fn flatten_scalars(
+        &self, ty: BasicTypeEnum<'ctx>, offset: u64, scalars: &mut Vec<(u64, BasicTypeEnum<'ctx>)>,
+    ) {
+        match ty {
+            BasicTypeEnum::StructType(struct_ty) => {
+                for (idx, field_ty) in struct_ty.get_field_types().into_iter().enumerate() {
+                    let field_offset =
+                        self.target_data.offset_of_element(&struct_ty, idx as u32).unwrap_or(0);
+                    self.flatten_scalars(field_ty, offset + field_offset, scalars);
+                }
+            },
+            BasicTypeEnum::ArrayType(array_ty) => {
+                let element_ty = array_ty.get_element_type();
+                let element_size = self.target_data.get_abi_size(&element_ty);
+                for idx in 0..array_ty.len() as u64 {
+                    self.flatten_scalars(element_ty, offset + idx * element_size, scalars);
+                }
+            },
+            _ => scalars.push((offset, ty)),
+        }
+    }
+
+    // The single LLVM type a struct's eightbytes are returned as
+    pub(super) fn get_cast_type(&self, eightbytes: &[BasicTypeEnum<'ctx>]) -> BasicTypeEnum<'ctx> {
+        match eightbytes {
+            [single] => *single,
+            _ => self.context.struct_type(eightbytes, false).as_basic_type_enum(),
+        }
+    }
+
+    // The `sret` and `byval` attributes a function and each call to it need
+    pub(super) fn get_abi_attributes(
+        &self, param_tys: &[Type], ret_ty: &Type,
+    ) -> Result<Vec<(AttributeLoc, Attribute)>, String> {
+        let (ret_mode, param_modes) = self.pass_modes(param_tys, ret_ty)?;
+        let mut attrs = vec![];
+        let mut idx = 0;
+        if let PassMode::Indirect(ty) = ret_mode {
+            attrs.push((AttributeLoc::Param(idx), self.get_type_attribute("sret", ty)));
+            idx += 1;
+        }
+        for param_mode in param_modes {
+            idx += match param_mode {
+                PassMode::Direct => 1,
+                PassMode::Cast(tys) => tys.len() as u32,
+                PassMode::Indirect(ty) => {
+                    attrs.push((AttributeLoc::Param(idx), self.get_type_attribute("byval", ty)));
+                    1
+                },
+            };
+        }
+        Ok(attrs)
+    }
+
+    fn get_type_attribute(&self, name: &str, ty: BasicTypeEnum<'ctx>) -> Attribute {
+        self.context.create_type_attribute(Attribute::get_named_enum_kind_id(name), ty.as_any_type_enum())
+    }
+
+    // Name a function's params after the prototype's. A struct split into eightbytes gets
+    // a name for each one
+    pub(super) fn name_params(&self, func: FunctionValue<'ctx>, proto: &Prototype) -> Result<(), String> {
+        let param_tys = proto.params().iter().map(|(_, ty)| ty.clone()).collect::<Vec<_>>();
+        let (ret_mode, param_modes) = self.pass_modes(&param_tys, proto.ret_ty())?;
+        let mut llvm_params = func.get_param_iter();
+        if let PassMode::Indirect(_) = ret_mode {
+            if let Some(param) = llvm_params.next() {
+                param.set_name("sret");
+            }
+        }
+        for ((name, _), param_mode) in proto.params().iter().zip(param_modes) {
+            match param_mode {
+                PassMode::Cast(tys) if tys.len() > 1 => {
+                    for (idx, param) in llvm_params.by_ref().take(tys.len()).enumerate() {
+                        param.set_name(&format!("{}.{}", name, idx));
+                    }
+                },
+                _ => {
+                    if let Some(param) = llvm_params.next() {
+                        param.set_name(name);
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    // Build a call, passing the args and the returned value per `pass_modes()`. Each arg
+    // comes with its type
+    pub(super) fn build_abi_call(
+        &self, callee: CallableValue<'ctx>, args: Vec<(BasicValueEnum<'ctx>, Type)>, ret_ty: &Type,
+        name: &str,
+    ) -> Result<Option<BasicValueEnum<'ctx>>, String> {
+        let param_tys = args.iter().map(|(_, ty)| ty.clone()).collect::<Vec<_>>();
+        let (ret_mode, param_modes) = self.pass_modes(&param_tys, ret_ty)?;
+
+        let mut args_code: Vec<BasicMetadataValueEnum> = Vec::with_capacity(args.len() + 1);
+        let sret = match ret_mode {
+            PassMode::Indirect(ty) => {
+                let sret = self.create_abi_slot(ty, "sret")?;
+                args_code.push(sret.into());
+                Some(sret)
+            },
+            _ => None,
+        };
+        for ((arg, _), param_mode) in args.into_iter().zip(param_modes) {
+            match param_mode {
+                PassMode::Direct => args_code.push(arg.into()),
+                PassMode::Cast(tys) => args_code.extend(
+                    self.build_to_eightbytes(arg, &tys)?.into_iter().map(BasicMetadataValueEnum::from),
+                ),
+                // The callee gets its own copy
+                PassMode::Indirect(ty) => {
+                    let copy = self.create_abi_slot(ty, "byval")?;
+                    self.builder.build_store(copy, arg);
+                    args_code.push(copy.into());
+                },
+            }
+        }
+
+        let call_val = self.builder.build_call(callee, &args_code, name);
+        for (loc, attr) in self.get_abi_attributes(&param_tys, ret_ty)? {
+            call_val.add_attribute(loc, attr);
+        }
+
+        let ret_val = match call_val.try_as_basic_value() {
+            Either::Left(v) => Some(v),
+            Either::Right(_) => None,
+        };
+        Ok(match ret_mode {
+            PassMode::Direct => ret_val,
+            PassMode::Cast(_) => {
+                let ret_val = ret_val.unwrap_or_else(|| unreachable!("missing return value of `{}`", name));
+                Some(self.build_transmute(ret_val, self.get_llvm_basic_type(ret_ty)?)?)
+            },
+            PassMode::Indirect(_) => {
+                let sret = sret.unwrap_or_else(|| unreachable!("missing `sret` for `{}`", name));
+                Some(self.builder.build_load(sret, &format!("{}.sret", name)))
+            },
+        })
+    }
+
+    // Split a struct into its eightbytes
+    fn build_to_eightbytes(
+        &self, value: BasicValueEnum<'ctx>, eightbytes: &[BasicTypeEnum<'ctx>],
+    ) -> Result<Vec<BasicValueEnum<'ctx>>, String> {
+        let cast = self.build_transmute(value, self.get_cast_type(eightbytes))?;
+        if eightbytes.len() == 1 {
+            return Ok(vec![cast]);
+        }
+        Ok((0..eightbytes.len() as u32)
+            .map(|idx| {
+                self.builder
+                    .build_extract_value(cast.into_struct_value(), idx, "abi.eightbyte")
+                    .unwrap_or_else(|| unreachable!("struct is missing eightbyte `{}`", idx))
+            })
+            .collect())
+    }
+
+    // Put a struct back together from its eightbytes
+    pub(super) fn build_from_eightbytes(
+        &self, values: Vec<BasicValueEnum<'ctx>>, eightbytes: &[BasicTypeEnum<'ctx>],
+        struct_ty: BasicTypeEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, String> {
+        let cast = match values.len() {
+            1 => values[0],
+            _ => {
+                let mut cast = self.get_cast_type(eightbytes).into_struct_type().get_undef();
+                for (idx, value) in values.into_iter().enumerate() {
+                    cast = self
+                        .builder
+                        .build_insert_value(cast, value, idx as u32, "abi.eightbyte")
+                        .unwrap_or_else(|| unreachable!("struct is missing eightbyte `{}`", idx))
+                        .into_struct_value();
+                }
+                cast.as_basic_value_enum()
+            },
+        };
+        self.build_transmute(cast, struct_ty)
+    }
+
+    // Reinterpret the bytes of `value` as type `to` through a stack slot that's big and
+    // aligned enough for either type
+    pub(super) fn build_transmute(
+        &self, value: BasicValueEnum<'ctx>, to: BasicTypeEnum<'ctx>,
+    ) -> Result<BasicValueEnum<'ctx>, String> {
+        let from = value.get_type();
+        let slot_ty =
+            if self.target_data.get_abi_size(&to) > self.target_data.get_abi_size(&from) { to } else { from };
+        let slot = self.create_abi_slot(slot_ty, "abi.cast")?;
+        let align = self.target_data.get_abi_alignment(&from).max(self.target_data.get_abi_alignment(&to));
+        if let Some(inst) = slot.as_instruction_value() {
+            inst.set_alignment(align).map_err(|err| err.to_string())?;
+        }
+
+        let from_ptr = self.builder.build_pointer_cast(
+            slot,
+            from.ptr_type(inkwell::AddressSpace::Generic),
+            "abi.cast.from",
+        );
+        self.builder.build_store(from_ptr, value);
+        let to_ptr =
+            self.builder.build_pointer_cast(slot, to.ptr_type(inkwell::AddressSpace::Generic), "abi.cast.to");
+        Ok(self.builder.build_load(to_ptr, "abi.cast.load"))
+    }
+
+    // An alloca in the current function's entry block
+    fn create_abi_slot(&self, ty: BasicTypeEnum<'ctx>, name: &str) -> Result<PointerValue<'ctx>, String> {
+        let entry = self
+            .builder
+            .get_insert_block()
+            .and_then(|bb| bb.get_parent())
+            .and_then(|func| func.get_first_basic_block())
+            .ok_or_else(|| "ABI slot built outside of a function".to_string())?;
+
+        let builder = self.context.create_builder();
+        match entry.get_first_instruction() {
+            Some(inst) => builder.position_before(&inst),
+            None => builder.position_at_end(entry),
+        }
+        Ok(builder.build_alloca(ty, name))
+    }
+}
@@ -48,6 +48,8 @@ impl<'a> Symbolic for CodegenSymbol<'a> {
             AssocData::Fn(_) => "Fn",
             AssocData::Var(_) => "Var",
             AssocData::Struct(_) => "Struct",
+            AssocData::Trait(_) => "Trait",
+            AssocData::Enum(_) => "Enum",
             AssocData::Module(_) => "Module",
         }
     }
@@ -0,0 +1,239 @@
+use inkwell::values::BasicMetadataValueEnum;
+
+use super::*;
+
+// `debug(value)` prints a value and a newline to stderr. The value is walked using its
+// type to build a single `dprintf` format string and its args, so a struct prints with
+// its field names, like `Point { x: 1, y: 2 }`. Pointers print as addresses and enums as
+// the name of their variant.
+
+impl<'ctx> Codegen<'ctx> {
+    pub(super) fn build_debug(&self, value: BasicValueEnum<'ctx>, ty: &Type) -> Result<(), String> {
+        let mut fmt = String::new();
+        let mut args = vec![];
+        self.build_debug_args(value, ty, &mut fmt, &mut args)?;
+        fmt.push('\n');
+
+        let i32_ty = self.context.i32_type();
+        let dprintf = self.module.get_function("dprintf").unwrap_or_else(|| {
+            let i8_ptr_ty = self.context.i8_type().ptr_type(inkwell::AddressSpace::Generic);
+            let ty = i32_ty.fn_type(&[i32_ty.into(), i8_ptr_ty.into()], true);
+            self.module.add_function("dprintf", ty, None)
+        });
+
+        let fmt = self.builder.build_global_string_ptr(&fmt, "debug.fmt").as_pointer_value();
+        let mut dprintf_args: Vec<BasicMetadataValueEnum> = vec![
+            i32_ty.const_int(2, false).into(), // stderr
+            fmt.into(),
+        ];
+        dprintf_args.extend(args);
+        self.builder.build_call(dprintf, &dprintf_args, "");
+        Ok(())
+    }
+
+    // Append the format for `value` to `fmt` and the values it prints to `args`
+    pub(super) fn build_debug_args(
+        &self, value: BasicValueEnum<'ctx>, ty: &Type, fmt: &mut String,
+        args: &mut Vec<BasicMetadataValueEnum<'ctx>>,
+    ) -> Result<(), String> {
+        match ty {
+            int128_types!() => {
+                self.build_debug_int128(value.into_int_value(), *ty == Type::Int128, fmt, args)
+            },
+            signed_int_types!() => {
+                fmt.push_str("%lld");
+                args.push(self.build_debug_int(value.into_int_value(), true).into());
+            },
+            unsigned_int_types!() => {
+                fmt.push_str("%llu");
+                args.push(self.build_debug_int(value.into_int_value(), false).into());
+            },
+            Type::Float => {
+                fmt.push_str("%g");
+                let value = self.builder.build_float_ext(
+                    value.into_float_value(),
+                    self.context.f64_type(),
+                    "debug.float",
+                );
+                args.push(value.into());
+            },
+            Type::Double => {
+                fmt.push_str("%g");
+                args.push(value.into());
+            },
+            Type::Char => {
+                fmt.push_str("%c");
+                let value = self.builder.build_int_z_extend(
+                    value.into_int_value(),
+                    self.context.i32_type(),
+                    "debug.char",
+                );
+                args.push(value.into());
+            },
+            Type::Bool => {
+                fmt.push_str("%s");
+                let true_str = self.builder.build_global_string_ptr("true", "debug.true").as_pointer_value();
+                let false_str =
+                    self.builder.build_global_string_ptr("false", "debug.false").as_pointer_value();
+                args.push(
+                    self.builder
+                        .build_select(value.into_int_value(), true_str, false_str, "debug.bool")
+                        .into(),
+                );
+            },
+            Type::Ptr(_) | Type::Fn(..) => {
+                fmt.push_str("%p");
+                args.push(value.into());
+            },
+            Type::Enum(name) => {
+                fmt.push_str("%s");
+                args.push(self.build_debug_variant(value, ty, name)?.into());
+            },
+            Type::SArray(inner_ty, len) => {
+                fmt.push('[');
+                for idx in 0..*len {
+                    if idx > 0 {
+                        fmt.push_str(", ");
+                    }
+                    let element = self.extract_element(value, idx as u32, "debug.element");
+                    self.build_debug_args(element, inner_ty, fmt, args)?;
+                }
+                fmt.push(']');
+            },
+            Type::Tuple(tys) => {
+                fmt.push('(');
+                for (idx, element_ty) in tys.iter().enumerate() {
+                    if idx > 0 {
+                        fmt.push_str(", ");
+                    }
+                    let element = self.extract_element(value, idx as u32, "debug.element");
+                    self.build_debug_args(element, element_ty, fmt, args)?;
+                }
+                fmt.push(')');
+            },
+            Type::Comp(name) => {
+                fmt.push_str(short_name(name));
+                let fields = self.get_struct_fields(name)?;
+                if fields.is_empty() {
+                    fmt.push_str(" {}");
+                    return Ok(());
+                }
+
+                fmt.push_str(" { ");
+                for (idx, (field, field_ty)) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        fmt.push_str(", ");
+                    }
+                    fmt.push_str(&format!("{}: ", field));
+                    let field_value = self.extract_element(value, idx as u32, "debug.field");
+                    self.build_debug_args(field_value, field_ty, fmt, args)?;
+                }
+                fmt.push_str(" }");
+            },
+            ty => return Err(format!("can't debug a value of type `{}`", ty)),
+        }
+        Ok(())
+    }
+
+    // Integers up to 64 bits are printed as 64 bits
+    fn build_debug_int(&self, value: IntValue<'ctx>, signed: bool) -> IntValue<'ctx> {
+        let i64_ty = self.context.i64_type();
+        match value.get_type().get_bit_width() {
+            64 => value,
+            _ if signed => self.builder.build_int_s_extend(value, i64_ty, "debug.int"),
+            _ => self.builder.build_int_z_extend(value, i64_ty, "debug.int"),
+        }
+    }
+
+    // No printf conversion takes 128 bits, so the magnitude is printed as three base 10^19
+    // pieces after its sign. A precision of 0 prints nothing for a 0 piece, so each piece is
+    // only zero-padded to 19 digits once a piece before it printed something
+    fn build_debug_int128(
+        &self, value: IntValue<'ctx>, signed: bool, fmt: &mut String,
+        args: &mut Vec<BasicMetadataValueEnum<'ctx>>,
+    ) {
+        let int128_ty = value.get_type();
+        let i64_ty = self.context.i64_type();
+        let i32_ty = self.context.i32_type();
+
+        let magnitude = if signed {
+            let is_neg = self.builder.build_int_compare(
+                IntPredicate::SLT,
+                value,
+                int128_ty.const_zero(),
+                "debug.is_neg",
+            );
+            let minus = self.builder.build_global_string_ptr("-", "debug.minus").as_pointer_value();
+            let no_sign = self.builder.build_global_string_ptr("", "debug.no_sign").as_pointer_value();
+            fmt.push_str("%s");
+            args.push(self.builder.build_select(is_neg, minus, no_sign, "debug.sign").into());
+
+            // `int128::MIN` negates to itself, which is its magnitude as a `uint128`
+            let negated = self.builder.build_int_neg(value, "debug.negated");
+            self.builder.build_select(is_neg, negated, value, "debug.magnitude").into_int_value()
+        } else {
+            value
+        };
+
+        let base = int128_ty.const_int(10_000_000_000_000_000_000, false);
+        let low = self.builder.build_int_unsigned_rem(magnitude, base, "debug.low");
+        let rest = self.builder.build_int_unsigned_div(magnitude, base, "debug.rest");
+        let mid = self.builder.build_int_unsigned_rem(rest, base, "debug.mid");
+        let high = self.builder.build_int_unsigned_div(rest, base, "debug.high");
+
+        let mut printed = self.context.bool_type().const_zero();
+        for (idx, piece) in [high, mid, low].into_iter().enumerate() {
+            let piece = self.builder.build_int_truncate(piece, i64_ty, "debug.piece");
+            // The last piece always prints at least one digit
+            let unpadded = i32_ty.const_int(if idx == 2 { 1 } else { 0 }, false);
+            let precision = match idx {
+                0 => unpadded,
+                _ => self
+                    .builder
+                    .build_select(printed, i32_ty.const_int(19, false), unpadded, "debug.precision")
+                    .into_int_value(),
+            };
+            let is_nonzero =
+                self.builder.build_int_compare(IntPredicate::NE, piece, i64_ty.const_zero(), "debug.nonzero");
+            printed = self.builder.build_or(printed, is_nonzero, "debug.printed");
+
+            fmt.push_str("%.*llu");
+            args.push(precision.into());
+            args.push(piece.into());
+        }
+    }
+
+    // Pick the variant's name by its discriminant. Variants with a payload print as
+    // `Variant(..)`
+    fn build_debug_variant(
+        &self, value: BasicValueEnum<'ctx>, ty: &Type, name: &str,
+    ) -> Result<PointerValue<'ctx>, String> {
+        let discriminant = self.extract_element(value, 0, "debug.discriminant").into_int_value();
+        let mut variant_str =
+            self.builder.build_global_string_ptr("<invalid>", "debug.variant").as_pointer_value();
+        for (idx, (variant, payload, _)) in self.get_enum_symbol(ty)?.variants().iter().enumerate() {
+            let variant = if payload.is_empty() {
+                format!("{}::{}", short_name(name), variant)
+            } else {
+                format!("{}::{}(..)", short_name(name), variant)
+            };
+            let is_variant = self.builder.build_int_compare(
+                IntPredicate::EQ,
+                discriminant,
+                self.get_discriminant(ty, idx)?,
+                "debug.is_variant",
+            );
+            let name_str = self.builder.build_global_string_ptr(&variant, "debug.variant").as_pointer_value();
+            variant_str = self
+                .builder
+                .build_select(is_variant, name_str, variant_str, "debug.variant")
+                .into_pointer_value();
+        }
+        Ok(variant_str)
+    }
+}
+
+// Struct and enum names are printed without their module
+fn short_name(name: &str) -> &str {
+    name.split_once("::").map(|(_, name)| name).unwrap_or(name)
+}
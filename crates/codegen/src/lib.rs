@@ -1,30 +1,44 @@
-use either::Either;
+use inkwell::attributes::{Attribute, AttributeLoc};
 use inkwell::basic_block::BasicBlock;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
 use inkwell::module::Module;
 use inkwell::passes::PassManager;
-use inkwell::targets::{FileType, InitializationConfig, Target, TargetMachine};
-use inkwell::types::{AnyType, AnyTypeEnum, BasicMetadataTypeEnum, BasicType, BasicTypeEnum};
-use inkwell::values::{BasicValue, BasicValueEnum, FunctionValue, InstructionOpcode, PointerValue};
+use inkwell::targets::{FileType, InitializationConfig, Target, TargetData, TargetMachine};
+use inkwell::types::{
+    AnyType, AnyTypeEnum, ArrayType, BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType,
+    StructType,
+};
+use inkwell::values::{
+    BasicValue, BasicValueEnum, CallableValue, FunctionValue, GlobalValue, InstructionOpcode, IntValue,
+    PointerValue,
+};
 use inkwell::{IntPredicate, OptimizationLevel};
 use std::path::PathBuf;
 use std::process;
 
+use abi::PassMode;
 use codegen_symbol::CodegenSymbol;
 use common::symbol_table::Symbolic;
-use common::{CliArgs, Literal, Operator, Prototype, Symbol, SymbolTable, Type};
+use common::{
+    CliArgs, Literal, Loc, MatchArm, Operator, PanicStrategy, Pattern, Prototype, Symbol, SymbolTable, Type,
+};
 use lower::hir::{VisitableNode, Visitor};
 use lower::{hir, Hir};
 
 #[macro_use]
 extern crate common;
 
+mod abi;
 mod codegen_symbol;
+mod debug;
 #[macro_use]
 mod macros;
 mod jit_externs;
 mod ops;
+mod panic;
+mod patterns;
+mod print;
 #[cfg(test)]
 mod tests;
 
@@ -39,8 +53,11 @@ pub struct Codegen<'ctx> {
     main: Option<FunctionValue<'ctx>>,
     opt_level: usize,
     no_verify: bool,
+    overflow_checks: bool,
+    panic_strategy: PanicStrategy,
     module_name: String,
     loop_data: Option<LoopData<'ctx>>,
+    target_data: TargetData,
 }
 
 impl<'ctx> Codegen<'ctx> {
@@ -67,6 +84,9 @@ impl<'ctx> Codegen<'ctx> {
 
         let symbol_table: SymbolTable<CodegenSymbol<'ctx>> = Codegen::convert_table(symbol_table)?;
 
+        // Enum layouts depend on the target's type sizes
+        let target_machine = Codegen::build_target_machine();
+
         let mut codegen = Codegen {
             context: &context,
             builder,
@@ -76,15 +96,30 @@ impl<'ctx> Codegen<'ctx> {
             main: None,
             opt_level: args.opt_level,
             no_verify: args.no_verify,
+            overflow_checks: args.checks_overflow(),
+            panic_strategy: args.panic,
             module_name: module_name.to_owned(),
             loop_data: None,
+            target_data: target_machine.get_target_data(),
         };
 
         codegen.walk(hir)?;
 
-        // This flag is just for the test suite
+        // This flag is just for the test suite. The layout goes ahead of the IR
         if is_test {
-            return Ok(CodegenResult::Ir(codegen.module.print_to_string().to_string()));
+            let mut ir = String::new();
+            if args.show_layout {
+                ir.push_str(&codegen.layout()?);
+            }
+            ir.push_str(&codegen.module.print_to_string().to_string());
+            return Ok(CodegenResult::Ir(ir));
+        }
+
+        if args.show_layout {
+            println!("Layout:");
+            println!(" module: {:?}", module_name);
+            print!("{}", codegen.layout()?);
+            println!();
         }
 
         if args.show_ir {
@@ -103,7 +138,8 @@ impl<'ctx> Codegen<'ctx> {
         let module_file = module_file.as_path().with_extension("o");
 
         // Write the object file to the build directory
-        let target_machine = Codegen::build_target_machine(&codegen.module);
+        codegen.module.set_data_layout(&codegen.target_data.get_data_layout());
+        codegen.module.set_triple(&TargetMachine::get_default_triple());
         target_machine
             .write_to_file(&codegen.module, FileType::Object, &module_file)
             .expect("Error writing object file");
@@ -113,11 +149,11 @@ impl<'ctx> Codegen<'ctx> {
 
     // Optimizes for host CPU
     // TODO: Make more generic
-    fn build_target_machine(module: &Module) -> TargetMachine {
+    fn build_target_machine() -> TargetMachine {
         Target::initialize_x86(&InitializationConfig::default());
         let triple = TargetMachine::get_default_triple();
         let target = Target::from_triple(&triple).expect("Target error");
-        let target_machine = target
+        target
             .create_target_machine(
                 &triple,
                 &TargetMachine::get_host_cpu_name().to_string(),
@@ -126,11 +162,7 @@ impl<'ctx> Codegen<'ctx> {
                 inkwell::targets::RelocMode::Default,
                 inkwell::targets::CodeModel::Default,
             )
-            .expect("Target machine error");
-
-        module.set_data_layout(&target_machine.get_target_data().get_data_layout());
-        module.set_triple(&triple);
-        target_machine
+            .expect("Target machine error")
     }
 
     fn run_jit(module: &Module) {
@@ -160,6 +192,9 @@ impl<'ctx> Codegen<'ctx> {
         // Do prototypes next so declaration order doesn't matter
         self.codegen_all_prototypes(prototypes)?;
 
+        // Vtables point at methods, so they come after the prototypes
+        self.codegen_all_vtables()?;
+
         // Do the rest
         for node in nodes {
             node.accept(self)?;
@@ -173,7 +208,9 @@ impl<'ctx> Codegen<'ctx> {
         }
     }
 
-    // Codegen all structs to ensure that declaration order doesn't matter
+    // Codegen all structs to ensure that declaration order doesn't matter. Every struct is
+    // declared as an opaque type before any bodies are set, so a field can point to any
+    // struct, including the one it's in
     fn codegen_all_structs(&self) -> Result<(), String> {
         let structs = self.symbol_table.filter(|sym| sym.kind() == "Struct");
         let struct_parts = structs
@@ -186,68 +223,307 @@ impl<'ctx> Codegen<'ctx> {
                     .iter()
                     .map(|(_, ty)| Type::from(*ty))
                     .collect();
-                (self.context.opaque_struct_type(&sym.name), field_tys)
+                (self.context.opaque_struct_type(&sym.name), field_tys, sym.repr())
             })
             .collect::<Vec<_>>();
 
-        for (opaque_struct, field_tys) in struct_parts {
-            let fields = field_tys
+        // Enum bodies depend on their payloads, so they're filled in last
+        for sym in self.symbol_table.filter(|sym| sym.kind() == "Enum") {
+            self.context.opaque_struct_type(&sym.inner().name);
+        }
+
+        for (opaque_struct, field_tys, repr) in struct_parts {
+            let mut fields = field_tys
                 .iter()
                 .map(|ty| self.get_llvm_basic_type(ty))
                 .collect::<Result<Vec<_>, String>>()?;
-            opaque_struct.set_body(&fields, false);
+
+            // LLVM structs can't be given an alignment, but they're as aligned as their most
+            // aligned field. A trailing `[0 x <N x i8>]` is aligned to N and takes up no space
+            // other than the padding it adds, so the field indices stay the same
+            if let Some(align) = repr.align {
+                let align_ty = self.context.i8_type().vec_type(align).array_type(0);
+                fields.push(align_ty.as_basic_type_enum());
+            }
+            opaque_struct.set_body(&fields, repr.packed);
+        }
+
+        self.codegen_all_enums()
+    }
+
+    // The size, alignment, and field offsets of each struct declared in this module
+    fn layout(&self) -> Result<String, String> {
+        let mut structs = self
+            .symbol_table
+            .filter(|sym| sym.kind() == "Struct")
+            .into_iter()
+            .map(|sym| sym.inner())
+            .filter(|sym| sym.module == self.module_name && sym.is_exportable())
+            .collect::<Vec<_>>();
+        structs.sort();
+
+        let mut layout = String::new();
+        for sym in structs {
+            let ty = Type::Comp(sym.name.to_owned());
+            let struct_ty = self.get_llvm_basic_type(&ty)?.into_struct_type();
+            let attrs = sym.attrs().iter().map(|attr| format!(" {}", attr)).collect::<String>();
+            layout.push_str(&format!(
+                " struct {}{}: size {}, align {}\n",
+                sym.name,
+                attrs,
+                self.target_data.get_abi_size(&struct_ty),
+                self.target_data.get_abi_alignment(&struct_ty)
+            ));
+            for (idx, (field, field_ty)) in self.get_struct_fields(&sym.name)?.into_iter().enumerate() {
+                let offset = self
+                    .target_data
+                    .offset_of_element(&struct_ty, idx as u32)
+                    .unwrap_or_else(|| unreachable!("struct `{}` is missing field `{}`", sym.name, field));
+                let size = self.target_data.get_abi_size(&self.get_llvm_basic_type(&field_ty)?);
+                layout.push_str(&format!("  {}: {}, offset {}, size {}\n", field, field_ty, offset, size));
+            }
+        }
+        Ok(layout)
+    }
+
+    // Lay out each enum as an `int32` tag followed by a payload big enough for its
+    // largest variant. An enum can hold another enum by value, so keep going until every
+    // payload is sized.
+    fn codegen_all_enums(&self) -> Result<(), String> {
+        let mut enums = self
+            .symbol_table
+            .filter(|sym| sym.kind() == "Enum")
+            .iter()
+            .map(|sym| sym.inner().clone())
+            .collect::<Vec<_>>();
+
+        while !enums.is_empty() {
+            let mut pending = Vec::with_capacity(enums.len());
+            for sym in &enums {
+                let payload_tys = (0..sym.variants().len())
+                    .map(|idx| self.get_variant_payload_type(sym, idx))
+                    .collect::<Result<Vec<_>, String>>()?;
+                if !payload_tys.iter().all(|ty| ty.is_sized()) {
+                    pending.push(sym.clone());
+                    continue;
+                }
+
+                let align = payload_tys
+                    .iter()
+                    .filter(|ty| ty.count_fields() > 0)
+                    .map(|ty| self.target_data.get_abi_alignment(ty))
+                    .max()
+                    .unwrap_or(0);
+                let size =
+                    payload_tys.iter().map(|ty| self.target_data.get_store_size(ty)).max().unwrap_or(0);
+
+                let tag = self.context.i32_type().as_basic_type_enum();
+                let enum_ty = self
+                    .module
+                    .get_struct_type(&sym.name)
+                    .unwrap_or_else(|| unreachable!("missing enum definition for `{}`", sym.name));
+                if size == 0 {
+                    enum_ty.set_body(&[tag], false);
+                } else {
+                    let payload = self
+                        .context
+                        .custom_width_int_type(align * 8)
+                        .array_type(size.div_ceil(align as u64) as u32);
+                    enum_ty.set_body(&[tag, payload.as_basic_type_enum()], false);
+                }
+            }
+
+            if pending.len() == enums.len() {
+                return Err(format!("recursive enum `{}` has infinite size", pending[0].name));
+            }
+            enums = pending;
         }
 
         Ok(())
     }
 
+    // The payload of a variant as a literal struct of its fields
+    fn get_variant_payload_type(&self, sym: &Symbol, idx: usize) -> Result<StructType<'ctx>, String> {
+        let fields = get_variant_field_types(sym, idx)
+            .iter()
+            .map(|ty| self.get_llvm_basic_type(ty))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(self.context.struct_type(&fields, false))
+    }
+
+    // A struct's field names and types
+    fn get_struct_fields(&self, name: &str) -> Result<Vec<(String, Type)>, String> {
+        let sym = self
+            .symbol_table
+            .get(name)
+            .map(|sym| sym.inner())
+            .ok_or_else(|| format!("unknown struct: `{}`", name))?;
+        Ok(sym
+            .fields()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(field, ty)| (field.to_owned(), Type::from(ty)))
+            .collect())
+    }
+
+    // Read a field or element out of a struct, tuple, or array value
+    fn extract_element(&self, aggregate: BasicValueEnum<'ctx>, idx: u32, name: &str) -> BasicValueEnum<'ctx> {
+        match aggregate {
+            BasicValueEnum::StructValue(value) => self.builder.build_extract_value(value, idx, name),
+            BasicValueEnum::ArrayValue(value) => self.builder.build_extract_value(value, idx, name),
+            value => unreachable!("expected an aggregate value, got `{:?}`", value),
+        }
+        .unwrap_or_else(|| unreachable!("aggregate is missing element `{}`", idx))
+    }
+
+    // Look up the tag value for a variant of an enum type
+    fn get_discriminant(&self, ty: &Type, idx: usize) -> Result<IntValue<'ctx>, String> {
+        let sym = self.get_enum_symbol(ty)?;
+        Ok(self.context.i32_type().const_int(sym.variants()[idx].2 as u64, true))
+    }
+
+    fn get_enum_symbol(&self, ty: &Type) -> Result<&Symbol, String> {
+        match ty {
+            Type::Enum(name) => self
+                .symbol_table
+                .get(name)
+                .map(|sym| sym.inner())
+                .ok_or_else(|| format!("unknown enum: `{}`", name)),
+            ty => unreachable!("expected enum type, got `{}`", ty),
+        }
+    }
+
     // Codegen all prototypes to ensure that call order doesn't matter
     fn codegen_all_prototypes(&self, prototypes: Vec<Prototype>) -> Result<(), String> {
         for proto in prototypes {
-            // Get LLVM types for function args
-            let mut args_types = vec![];
-            for (_, arg_ty) in proto.params() {
-                let llvm_ty = match self.get_llvm_any_type(arg_ty)? {
-                    AnyTypeEnum::FloatType(ty) => BasicMetadataTypeEnum::FloatType(ty),
-                    AnyTypeEnum::IntType(ty) => BasicMetadataTypeEnum::IntType(ty),
-                    AnyTypeEnum::ArrayType(ty) => BasicMetadataTypeEnum::ArrayType(ty),
-                    AnyTypeEnum::StructType(ty) => BasicMetadataTypeEnum::StructType(ty),
-                    AnyTypeEnum::PointerType(ty) => BasicMetadataTypeEnum::PointerType(ty),
-                    ty => unreachable!(
-                        "unsupported argument type `{}` in prototype `{}()`",
-                        ty.print_to_string(),
-                        proto.name(),
-                    ),
-                };
-                args_types.push(llvm_ty);
-            }
-
-            // Generate function based on return type
-            let func_type = match self.get_llvm_any_type(proto.ret_ty())? {
-                AnyTypeEnum::FloatType(ty) => ty.fn_type(&args_types, false),
-                AnyTypeEnum::IntType(ty) => ty.fn_type(&args_types, false),
-                AnyTypeEnum::VoidType(ty) => ty.fn_type(&args_types, false),
-                AnyTypeEnum::StructType(ty) => ty.fn_type(&args_types, false),
-                AnyTypeEnum::PointerType(ty) => ty.fn_type(&args_types, false),
-                ty => unreachable!(
-                    "unsupported return type `{}` in prototype `{}()`",
-                    ty.print_to_string(),
-                    proto.name(),
-                ),
-            };
+            let param_tys = proto.params().iter().map(|(_, ty)| ty.clone()).collect::<Vec<_>>();
+            let func_type = self
+                .get_llvm_fn_type(&param_tys, proto.ret_ty(), proto.is_variadic())
+                .map_err(|err| format!("{} in prototype `{}()`", err, proto.name()))?;
 
             // Add function to current module's symbol table. Defaults to external
-            // linkage with None.
-            let func = self.module.add_function(proto.name(), func_type, None);
+            // linkage with None. `#[export_name]` replaces the symbol name in the object
+            let link_name = proto.attr("export_name").and_then(|attr| attr.arg()).unwrap_or(proto.name());
+            let func = self.module.add_function(link_name, func_type, None);
+
+            // Structs passed in memory need the `sret` and `byval` attributes
+            for (loc, attr) in self.get_abi_attributes(&param_tys, proto.ret_ty())? {
+                func.add_attribute(loc, attr);
+            }
+
+            // Optimizer hints from `#[inline]`, `#[noinline]`, and `#[cold]`
+            for (attr, llvm_attr) in [("inline", "inlinehint"), ("noinline", "noinline"), ("cold", "cold")] {
+                if proto.attr(attr).is_some() {
+                    let kind_id = Attribute::get_named_enum_kind_id(llvm_attr);
+                    func.add_attribute(
+                        AttributeLoc::Function,
+                        self.context.create_enum_attribute(kind_id, 0),
+                    );
+                }
+            }
 
             // Name all args
-            func.get_param_iter().enumerate().for_each(|(i, arg)| {
-                arg.set_name(&proto.params()[i].0);
-            });
+            self.name_params(func, &proto)?;
         }
         Ok(())
     }
 
+    // Look up a function in the module by its prototype name. Functions with
+    // `#[export_name]` were added under the exported name instead
+    fn get_function(&self, name: &str) -> Option<FunctionValue<'ctx>> {
+        let link_name = self
+            .symbol_table
+            .get(name)
+            .filter(|sym| sym.kind() == "Fn")
+            .and_then(|sym| sym.inner().attr("export_name"))
+            .and_then(|attr| attr.arg())
+            .unwrap_or(name);
+        self.module.get_function(link_name)
+    }
+
+    // Codegen a vtable for each trait implemented by a struct in this module. Slots are in
+    // the same order as the trait's methods. Structs are sorted by name so the vtables are
+    // emitted in a stable order
+    fn codegen_all_vtables(&self) -> Result<(), String> {
+        let i8_ptr_ty = self.context.i8_type().ptr_type(inkwell::AddressSpace::Generic);
+        let mut structs = self
+            .symbol_table
+            .filter(|sym| sym.kind() == "Struct" && sym.inner().module == self.module_name)
+            .into_iter()
+            .map(|sym| sym.inner())
+            .collect::<Vec<_>>();
+        structs.sort_by(|a, b| a.name.cmp(&b.name));
+        for struct_sym in structs {
+            for trait_name in struct_sym.impls() {
+                let trait_sym = self
+                    .symbol_table
+                    .get(trait_name)
+                    .unwrap_or_else(|| unreachable!("missing symbol for trait `{}` in codegen", trait_name))
+                    .inner();
+
+                let mut slots = Vec::with_capacity(trait_sym.trait_methods().len());
+                for method in trait_sym.trait_methods() {
+                    let fq_name = format!("_{}_{}", struct_sym.name, method.name);
+                    let func = self
+                        .symbol_table
+                        .filter(|sym| sym.kind() == "Fn" && sym.inner().fq_name() == Some(fq_name.as_str()))
+                        .first()
+                        .and_then(|sym| self.get_function(sym.name()))
+                        .ok_or(format!(
+                            "missing method `{}` in vtable for `{}`",
+                            method.name, struct_sym.name
+                        ))?;
+                    slots.push(func.as_global_value().as_pointer_value().const_cast(i8_ptr_ty));
+                }
+
+                let vtable = self.module.add_global(
+                    self.get_vtable_type(slots.len())?,
+                    None,
+                    &vtable_name(&struct_sym.name, trait_name),
+                );
+                vtable.set_initializer(&i8_ptr_ty.const_array(&slots));
+                vtable.set_constant(true);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Vtables for imported structs are defined in their own module, so they're declared on
+    // first use
+    fn get_vtable(&self, struct_name: &str, trait_name: &str) -> Result<GlobalValue<'ctx>, String> {
+        let name = vtable_name(struct_name, trait_name);
+        if let Some(vtable) = self.module.get_global(&name) {
+            return Ok(vtable);
+        }
+
+        let len = self
+            .symbol_table
+            .get(trait_name)
+            .unwrap_or_else(|| unreachable!("missing symbol for trait `{}` in codegen", trait_name))
+            .inner()
+            .trait_methods()
+            .len();
+        Ok(self.module.add_global(self.get_vtable_type(len)?, None, &name))
+    }
+
+    fn get_vtable_type(&self, len: usize) -> Result<ArrayType<'ctx>, String> {
+        let len = len.try_into().map_err(|err| format!("failed to convert vtable size: `{}`", err))?;
+        Ok(self.context.i8_type().ptr_type(inkwell::AddressSpace::Generic).array_type(len))
+    }
+
+    // Trait objects are fat pointers made up of a data pointer and a vtable pointer
+    fn get_dyn_type(&self) -> StructType<'ctx> {
+        let i8_ptr_ty = self.context.i8_type().ptr_type(inkwell::AddressSpace::Generic);
+        self.context.struct_type(
+            &[
+                i8_ptr_ty.as_basic_type_enum(),
+                i8_ptr_ty.ptr_type(inkwell::AddressSpace::Generic).as_basic_type_enum(),
+            ],
+            false,
+        )
+    }
+
     // Codegen variable initializers. Match combinations of init presence and type. When
     // init is None, initialize with 0.
     fn codegen_var_init(
@@ -267,10 +543,21 @@ impl<'ctx> Codegen<'ctx> {
             (int16_types!(), None) => Some(self.context.i16_type().const_zero().as_basic_value_enum()),
             (int32_types!(), None) => Some(self.context.i32_type().const_zero().as_basic_value_enum()),
             (int64_types!(), None) => Some(self.context.i64_type().const_zero().as_basic_value_enum()),
+            (int128_types!() | size_types!(), None) => Some(self.get_llvm_basic_type(ty)?.const_zero()),
             (Type::Float, None) => Some(self.context.f32_type().const_zero().as_basic_value_enum()),
             (Type::Double, None) => Some(self.context.f64_type().const_zero().as_basic_value_enum()),
             (Type::Bool, None) => Some(self.context.bool_type().const_zero().as_basic_value_enum()),
-            (Type::Void | Type::SArray(..) | Type::Comp(_) | Type::Ptr(_), None) => {
+            (
+                Type::Void
+                | Type::SArray(..)
+                | Type::Comp(_)
+                | Type::Ptr(_)
+                | Type::Dyn(_)
+                | Type::Enum(_)
+                | Type::Tuple(_)
+                | Type::Fn(..),
+                None,
+            ) => {
                 unreachable!("void/invalid type for init annotation in `codegen_var_init()`")
             },
         };
@@ -300,6 +587,7 @@ impl<'ctx> Codegen<'ctx> {
             int16_types!() => builder.build_alloca(self.context.i16_type(), name),
             int32_types!() => builder.build_alloca(self.context.i32_type(), name),
             int64_types!() => builder.build_alloca(self.context.i64_type(), name),
+            int128_types!() | size_types!() => builder.build_alloca(self.get_llvm_basic_type(ty)?, name),
             Type::Float => builder.build_alloca(self.context.f32_type(), name),
             Type::Double => builder.build_alloca(self.context.f64_type(), name),
             Type::Bool => builder.build_alloca(self.context.bool_type(), name),
@@ -307,6 +595,7 @@ impl<'ctx> Codegen<'ctx> {
                 let sarray_ty = match self.get_llvm_any_type(&ty.as_ref().clone())? {
                     AnyTypeEnum::FloatType(ty) => (ty.as_basic_type_enum(), sz),
                     AnyTypeEnum::IntType(ty) => (ty.as_basic_type_enum(), sz),
+                    AnyTypeEnum::StructType(ty) => (ty.as_basic_type_enum(), sz),
                     _ => todo!(),
                 };
                 builder.build_alloca(
@@ -318,42 +607,77 @@ impl<'ctx> Codegen<'ctx> {
                     name,
                 )
             },
-            Type::Comp(_) => {
+            Type::Comp(_) | Type::Enum(_) | Type::Tuple(_) => {
                 let struct_ty = self.get_llvm_basic_type(ty)?;
                 builder.build_alloca(struct_ty, name)
             },
-            Type::Ptr(ty) => {
-                let ptr_ty =
-                    self.get_llvm_basic_type(ty)?.into_struct_type().ptr_type(inkwell::AddressSpace::Generic);
-                builder.build_alloca(ptr_ty, name)
-            },
+            Type::Ptr(_) | Type::Fn(..) => builder.build_alloca(self.get_llvm_basic_type(ty)?, name),
+            Type::Dyn(_) => builder.build_alloca(self.get_dyn_type(), name),
             Type::Void => {
                 unreachable!("void type for stack variable in `create_entry_block_alloca()`")
             },
         })
     }
 
-    // Helper to fetch a pointer to an array element
-    fn get_array_element(&mut self, array: hir::Node, idx: hir::Node) -> Result<PointerValue<'ctx>, String> {
-        use hir::node::Kind::*;
-
-        // Codegen the array value
-        let array_ptr = match array.kind {
-            // TODO: As an optimization, when an Ident is found, pull the pointer out of
-            // the symbol table rather than calling visit_node(). This will skip the
-            // generated load instruction
-            Ident { .. } | FSelector { .. } => {
-                let value =
-                    self.visit_node(array)?.unwrap_or_else(|| unreachable!("can't find struct pointer"));
-                derive_composite_pointer!(value)
+    // Helper to fetch a pointer to an array element. The index is bounds checked unless
+    // it's a constant that's known to be in range
+    fn get_array_element(
+        &mut self, array: hir::Node, idx: hir::Node, loc: &Loc,
+    ) -> Result<PointerValue<'ctx>, String> {
+        let len = match array.ty() {
+            Type::SArray(_, len) => *len as u64,
+            t => unreachable!("can't index `{}`", t),
+        };
+        let array_ty = array.ty().clone();
+
+        // Codegen the array value. Arrays in variables and fields are loaded, so index
+        // through the pointer they were loaded from. Other arrays, like call results, are
+        // only values, so they're spilled to the stack first
+        let array_value = self.visit_node(array)?.expr_value()?;
+        let array_ptr = match array_value.as_instruction_value().map(|inst| inst.get_opcode()) {
+            Some(InstructionOpcode::Load) => derive_composite_pointer!(array_value),
+            _ => {
+                let parent = self
+                    .builder
+                    .get_insert_block()
+                    .and_then(|x| x.get_parent())
+                    .ok_or_else(|| "parent function not found when indexing an array".to_string())?;
+                let array_ptr = self.create_entry_block_alloca("array.tmp", &array_ty, &parent)?;
+                self.builder.build_store(array_ptr, array_value);
+                array_ptr
             },
-            _ => unreachable!("unsupported type for array index"),
         };
 
-        // Codegen the index
-        let idx =
-            self.visit_node(idx)?.unwrap_or_else(|| unreachable!("missing value in index")).into_int_value();
+        // Codegen the index. Indexes wider than a pointer are checked at their own width, so
+        // they can't wrap around into bounds when they're narrowed
+        let idx_ty = idx.ty().clone();
+        let idx_value = self.visit_node(idx)?.unwrap_or_else(|| unreachable!("missing value in index"));
+        let size_ty = self.context.ptr_sized_int_type(&self.target_data, None);
+        let checked_idx = if idx_value.into_int_value().get_type().get_bit_width() > size_ty.get_bit_width() {
+            idx_value.into_int_value()
+        } else {
+            self.cast((idx_value, &idx_ty), &Type::USize)?.into_int_value()
+        };
+
+        if !matches!(checked_idx.get_zero_extended_constant(), Some(i) if i < len) {
+            let out_of_bounds = self.builder.build_int_compare(
+                IntPredicate::UGE,
+                checked_idx,
+                checked_idx.get_type().const_int(len, false),
+                "array.index.oob",
+            );
+            self.build_check_debug(
+                out_of_bounds,
+                "index out of bounds: the len is %zu but the index is ",
+                &[size_ty.const_int(len, false).into()],
+                (idx_value, &idx_ty),
+                loc,
+            )?;
+        }
 
+        // Widen the index to a pointer-sized int, so that narrow unsigned indexes aren't
+        // sign extended by the GEP
+        let idx = self.cast((idx_value, &idx_ty), &Type::USize)?.into_int_value();
         let zero = self.context.i32_type().const_zero();
 
         unsafe { Ok(self.builder.build_in_bounds_gep(array_ptr, &[zero, idx], "array.index.gep")) }
@@ -375,20 +699,21 @@ impl<'ctx> Codegen<'ctx> {
             _ => unimplemented!("unsupported composite type for selector"),
         };
 
-        // If the composite is already a pointer, as in the case of chained fields, don't
-        // try to coerce into a pointer. Otherwise derive pointer from load
-        // instruction. In some cases the loaded value is a pointer. If not, derive as
-        // well.
-        let struct_ptr = if comp_value.is_pointer_value() {
-            let comp_ptr = comp_value.into_pointer_value();
-            let comp_load = self.builder.build_load(comp_ptr, "");
-            if comp_load.is_pointer_value() {
-                comp_load.into_pointer_value()
-            } else {
-                derive_composite_pointer!(comp_load)
-            }
-        } else {
-            derive_composite_pointer!(comp_value)
+        // If the composite is already a pointer to the struct, as in the case of chained
+        // fields or receivers, don't try to coerce into a pointer. Otherwise derive pointer
+        // from load instruction. In some cases the loaded value is a pointer. If not, derive
+        // as well.
+        let struct_ptr = match comp_value {
+            BasicValueEnum::PointerValue(ptr) if ptr.get_type().get_element_type().is_struct_type() => ptr,
+            BasicValueEnum::PointerValue(ptr) => {
+                let comp_load = self.builder.build_load(ptr, "");
+                if comp_load.is_pointer_value() {
+                    comp_load.into_pointer_value()
+                } else {
+                    derive_composite_pointer!(comp_load)
+                }
+            },
+            _ => derive_composite_pointer!(comp_value),
         };
 
         Ok(self
@@ -403,6 +728,8 @@ impl<'ctx> Codegen<'ctx> {
             int16_types!() => self.context.i16_type().as_basic_type_enum(),
             int32_types!() => self.context.i32_type().as_basic_type_enum(),
             int64_types!() => self.context.i64_type().as_basic_type_enum(),
+            int128_types!() => self.context.i128_type().as_basic_type_enum(),
+            size_types!() => self.context.ptr_sized_int_type(&self.target_data, None).as_basic_type_enum(),
             Type::Float => self.context.f32_type().as_basic_type_enum(),
             Type::Double => self.context.f64_type().as_basic_type_enum(),
             Type::Bool => self.context.bool_type().as_basic_type_enum(),
@@ -413,6 +740,7 @@ impl<'ctx> Codegen<'ctx> {
                     AnyTypeEnum::ArrayType(ty) => ty.array_type(size).as_basic_type_enum(),
                     AnyTypeEnum::FloatType(ty) => ty.array_type(size).as_basic_type_enum(),
                     AnyTypeEnum::IntType(ty) => ty.array_type(size).as_basic_type_enum(),
+                    AnyTypeEnum::StructType(ty) => ty.array_type(size).as_basic_type_enum(),
                     _ => todo!(),
                 }
             },
@@ -423,20 +751,85 @@ impl<'ctx> Codegen<'ctx> {
                     unreachable!("missing struct definition for `{}` in `get_llvm_basic_type()`", name)
                 })
                 .as_basic_type_enum(),
-            Type::Ptr(ptr_ty) => {
-                let name = ptr_ty.get_comp_name();
-                self.module
-                    .get_struct_type(name)
-                    .unwrap_or_else(|| {
-                        unreachable!("missing struct definition for `{}` in `get_llvm_basic_type()`", name)
-                    })
-                    .ptr_type(inkwell::AddressSpace::Generic) // TODO: right address space?
-                    .as_basic_type_enum()
+            Type::Ptr(ptr_ty) => self
+                .get_llvm_basic_type(ptr_ty)?
+                .ptr_type(inkwell::AddressSpace::Generic) // TODO: right address space?
+                .as_basic_type_enum(),
+            Type::Dyn(_) => self.get_dyn_type().as_basic_type_enum(),
+            Type::Enum(name) => self
+                .module
+                .get_struct_type(name)
+                .unwrap_or_else(|| {
+                    unreachable!("missing enum definition for `{}` in `get_llvm_basic_type()`", name)
+                })
+                .as_basic_type_enum(),
+            // Tuples are anonymous structs of their elements
+            Type::Tuple(tys) => {
+                let element_tys =
+                    tys.iter().map(|ty| self.get_llvm_basic_type(ty)).collect::<Result<Vec<_>, String>>()?;
+                self.context.struct_type(&element_tys, false).as_basic_type_enum()
             },
+            Type::Fn(param_tys, ret_ty) => self
+                .get_llvm_fn_type(param_tys, ret_ty, false)?
+                .ptr_type(inkwell::AddressSpace::Generic)
+                .as_basic_type_enum(),
             Type::Void => unreachable!("void can't be coerced into LLVM basic type"),
         })
     }
 
+    // Functions use LLVM's default calling convention, which is the C one, so pointers to
+    // them can be passed to C as callbacks. Structs are lowered as described in `abi.rs`.
+    // Variadic externs take their extra args after the params, already promoted by the tych
+    fn get_llvm_fn_type(
+        &self, param_tys: &[Type], ret_ty: &Type, is_var_args: bool,
+    ) -> Result<FunctionType<'ctx>, String> {
+        let (ret_mode, param_modes) = self.pass_modes(param_tys, ret_ty)?;
+
+        // Get LLVM types for function args. A struct returned in memory is written to a
+        // pointer the caller passes first
+        let mut args_types = vec![];
+        if let PassMode::Indirect(ty) = &ret_mode {
+            args_types.push(BasicMetadataTypeEnum::PointerType(ty.ptr_type(inkwell::AddressSpace::Generic)));
+        }
+        for (param_ty, param_mode) in param_tys.iter().zip(param_modes) {
+            let llvm_ty = match param_mode {
+                PassMode::Cast(tys) => {
+                    args_types.extend(tys.into_iter().map(BasicMetadataTypeEnum::from));
+                    continue;
+                },
+                PassMode::Indirect(ty) => {
+                    BasicMetadataTypeEnum::PointerType(ty.ptr_type(inkwell::AddressSpace::Generic))
+                },
+                PassMode::Direct => match self.get_llvm_any_type(param_ty)? {
+                    AnyTypeEnum::FloatType(ty) => BasicMetadataTypeEnum::FloatType(ty),
+                    AnyTypeEnum::IntType(ty) => BasicMetadataTypeEnum::IntType(ty),
+                    AnyTypeEnum::ArrayType(ty) => BasicMetadataTypeEnum::ArrayType(ty),
+                    AnyTypeEnum::StructType(ty) => BasicMetadataTypeEnum::StructType(ty),
+                    AnyTypeEnum::PointerType(ty) => BasicMetadataTypeEnum::PointerType(ty),
+                    ty => return Err(format!("unsupported argument type `{}`", ty.print_to_string())),
+                },
+            };
+            args_types.push(llvm_ty);
+        }
+
+        // Generate function based on return type
+        let ret_llvm_ty = match ret_mode {
+            PassMode::Direct => self.get_llvm_any_type(ret_ty)?,
+            PassMode::Cast(tys) => self.get_cast_type(&tys).as_any_type_enum(),
+            PassMode::Indirect(_) => self.context.void_type().as_any_type_enum(),
+        };
+        Ok(match ret_llvm_ty {
+            AnyTypeEnum::FloatType(ty) => ty.fn_type(&args_types, is_var_args),
+            AnyTypeEnum::IntType(ty) => ty.fn_type(&args_types, is_var_args),
+            AnyTypeEnum::VoidType(ty) => ty.fn_type(&args_types, is_var_args),
+            AnyTypeEnum::ArrayType(ty) => ty.fn_type(&args_types, is_var_args),
+            AnyTypeEnum::StructType(ty) => ty.fn_type(&args_types, is_var_args),
+            AnyTypeEnum::PointerType(ty) => ty.fn_type(&args_types, is_var_args),
+            AnyTypeEnum::VectorType(ty) => ty.fn_type(&args_types, is_var_args),
+            ty => return Err(format!("unsupported return type `{}`", ty.print_to_string())),
+        })
+    }
+
     fn get_llvm_any_type(&self, ty: &Type) -> Result<AnyTypeEnum<'ctx>, String> {
         Ok(match ty {
             Type::Void => self.context.void_type().as_any_type_enum(),
@@ -556,13 +949,13 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
         // Restore old loop data
         self.loop_data = old_loop_data;
 
-        // Get the last block written and check for a break before we complete the loop
-        if !self
+        // Get the last block written and check for a break or panic before we complete the loop
+        if self
             .builder
             .get_insert_block()
             .unwrap_or_else(|| unreachable!("can't location insert block"))
             .get_terminator()
-            .is_some_and(|t| t.get_opcode() == InstructionOpcode::Br)
+            .is_none()
         {
             self.builder.build_unconditional_branch(body_bb);
         }
@@ -591,6 +984,39 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
         Ok(None)
     }
 
+    // Each name gets its own stack slot holding its element of the tuple
+    fn visit_destructure(&mut self, names: Vec<String>, antn: Type, init: hir::Node) -> Self::Result {
+        let parent = self
+            .builder
+            .get_insert_block()
+            .and_then(|x| x.get_parent())
+            .ok_or_else(|| "parent function not found when building destructure".to_string())?;
+
+        let tys = match &antn {
+            Type::Tuple(tys) => tys,
+            ty => unreachable!("can't destructure `{}` in codegen", ty),
+        };
+        let tuple = self.visit_node(init)?.expr_value()?.into_struct_value();
+
+        for (idx, (name, ty)) in names.iter().zip(tys).enumerate().filter(|(_, (name, _))| *name != "_") {
+            let idx = idx.try_into().map_err(|err| format!("failed to convert tuple index: `{}`", err))?;
+            let value = self
+                .builder
+                .build_extract_value(tuple, idx, name)
+                .unwrap_or_else(|| unreachable!("tuple is missing element `{}`", idx));
+            self.bind_value(name, ty, value, &parent)?;
+        }
+
+        Ok(None)
+    }
+
+    // No stack slot is made. The name refers to the variable behind the pointer
+    fn visit_alias(&mut self, name: String, ty: Type, ptr: hir::Node) -> Self::Result {
+        let ptr = self.visit_node(ptr)?.expr_value()?.into_pointer_value();
+        self.symbol_table.insert(CodegenSymbol::new_var(name.as_str(), &ty, &self.module_name, ptr));
+        Ok(None)
+    }
+
     fn visit_fn(&mut self, proto: Prototype, body: Option<hir::Node>) -> Self::Result {
         let sym = self
             .symbol_table
@@ -603,7 +1029,6 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
         // `insert_prototypes()`. This ensures we can codegen a call before the
         // `codegen_func()` is called.
         let function = self
-            .module
             .get_function(proto.name())
             .unwrap_or_else(|| unreachable!("missing function `{}` in module table", proto.name()));
 
@@ -622,23 +1047,64 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
         // Make sure the builder will insert new instructions at the end
         self.builder.position_at_end(bb);
 
-        // Allocate space for the function's arguments on the stack
-        for (i, arg) in function.get_param_iter().enumerate() {
-            let (name, ty) = &proto.params()[i];
-            let alloca = self.create_entry_block_alloca(name, ty, &function)?;
-            self.builder.build_store(alloca, arg);
+        // Allocate space for the function's arguments on the stack. Structs passed in
+        // memory are already a copy owned by this function, so they're used in place
+        let param_tys = proto.params().iter().map(|(_, ty)| ty.clone()).collect::<Vec<_>>();
+        let (ret_mode, param_modes) = self.pass_modes(&param_tys, proto.ret_ty())?;
+        let mut llvm_params = function.get_param_iter();
+        let mut next_param =
+            || llvm_params.next().ok_or_else(|| format!("missing argument in `{}()`", proto.name()));
+        let sret = match ret_mode {
+            PassMode::Indirect(_) => Some(next_param()?.into_pointer_value()),
+            _ => None,
+        };
+        for ((name, ty), param_mode) in proto.params().iter().zip(param_modes) {
+            let alloca = match param_mode {
+                PassMode::Indirect(_) => next_param()?.into_pointer_value(),
+                mode => {
+                    let arg = match mode {
+                        PassMode::Cast(tys) => {
+                            let eightbytes =
+                                tys.iter().map(|_| next_param()).collect::<Result<Vec<_>, _>>()?;
+                            self.build_from_eightbytes(eightbytes, &tys, self.get_llvm_basic_type(ty)?)?
+                        },
+                        _ => next_param()?,
+                    };
+                    let alloca = self.create_entry_block_alloca(name, ty, &function)?;
+                    self.builder.build_store(alloca, arg);
+                    alloca
+                },
+            };
             self.symbol_table.insert(CodegenSymbol::new_var(name.as_str(), ty, &self.module_name, alloca));
         }
 
         let body_val = self.visit_node(body)?;
 
-        // Build the return function based on the prototype's return value and the last statement
+        // Build the return function based on the prototype's return value and the last statement.
+        // A body that ends in a panic has no return.
+        let terminated = self.builder.get_insert_block().and_then(|bb| bb.get_terminator()).is_some();
         match (proto.ret_ty(), body_val) {
-            (rt, Some(v)) if rt != &Type::Void => self.builder.build_return(Some(&v)),
+            _ if terminated => (),
+            (rt, Some(v)) if rt != &Type::Void => match &ret_mode {
+                PassMode::Direct => {
+                    self.builder.build_return(Some(&v));
+                },
+                PassMode::Cast(tys) => {
+                    let v = self.build_transmute(v, self.get_cast_type(tys))?;
+                    self.builder.build_return(Some(&v));
+                },
+                PassMode::Indirect(_) => {
+                    let sret = sret.unwrap_or_else(|| unreachable!("missing `sret` in `{}()`", proto.name()));
+                    self.builder.build_store(sret, v);
+                    self.builder.build_return(None);
+                },
+            },
             (rt, None) if rt != &Type::Void => {
                 return Err(format!("Function should return `{}` but last statement is void", rt))
             },
-            _ => self.builder.build_return(None),
+            _ => {
+                self.builder.build_return(None);
+            },
         };
 
         self.symbol_table.leave_scope();
@@ -691,6 +1157,28 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
         }
     }
 
+    fn visit_panic(&mut self, msg: String, loc: Loc) -> Self::Result {
+        self.build_panic(&msg, &loc);
+        Ok(None)
+    }
+
+    fn visit_debug(&mut self, value: hir::Node) -> Self::Result {
+        let ty = value.ty().clone();
+        let value = self.visit_node(value)?.expr_value()?;
+        self.build_debug(value, &ty)?;
+        Ok(None)
+    }
+
+    fn visit_print(&mut self, pieces: Vec<String>, args: Vec<hir::Node>) -> Self::Result {
+        let mut values = vec![];
+        for arg in args {
+            let ty = arg.ty().clone();
+            values.push((self.visit_node(arg)?.expr_value()?, ty));
+        }
+        self.build_print(&pieces, values)?;
+        Ok(None)
+    }
+
     fn visit_lit(&mut self, value: Literal<hir::Node>, ty: Type) -> Self::Result {
         use Literal::*;
 
@@ -703,9 +1191,33 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
             UInt16(v) => self.context.i16_type().const_int(v as u64, false).as_basic_value_enum(),
             UInt32(v) => self.context.i32_type().const_int(v as u64, false).as_basic_value_enum(),
             UInt64(v) => self.context.i64_type().const_int(v, false).as_basic_value_enum(),
+            // LLVM takes the words of wide constants least significant first
+            Int128(v) => self
+                .context
+                .i128_type()
+                .const_int_arbitrary_precision(&[v as u64, (v >> 64) as u64])
+                .as_basic_value_enum(),
+            UInt128(v) => self
+                .context
+                .i128_type()
+                .const_int_arbitrary_precision(&[v as u64, (v >> 64) as u64])
+                .as_basic_value_enum(),
+            ISize(v) => self
+                .context
+                .ptr_sized_int_type(&self.target_data, None)
+                .const_int(v as u64, true)
+                .as_basic_value_enum(),
+            USize(v) => self
+                .context
+                .ptr_sized_int_type(&self.target_data, None)
+                .const_int(v, false)
+                .as_basic_value_enum(),
             Float(v) => self.context.f32_type().const_float(v as f64).as_basic_value_enum(),
             Double(v) => self.context.f64_type().const_float(v).as_basic_value_enum(),
             Bool(v) => self.context.bool_type().const_int(v as u64, true).as_basic_value_enum(),
+            Str(v) => {
+                self.builder.build_global_string_ptr(&v, "str").as_pointer_value().as_basic_value_enum()
+            },
             Array { elements, inner_ty } => {
                 // Get inner LLVM type and codegen all element values
                 let inner_llvm_ty =
@@ -730,22 +1242,97 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
                         let vals = vals.iter().map(|v| v.into_int_value()).collect::<Vec<_>>();
                         wrapped_ty.const_array(&vals).as_basic_value_enum()
                     },
+                    // Trait objects aren't constants, so build the array one element at a
+                    // time
+                    BasicTypeEnum::StructType(_) => {
+                        let mut array = self.get_llvm_basic_type(&ty)?.into_array_type().get_undef();
+                        for (idx, val) in vals.into_iter().enumerate() {
+                            let idx = idx
+                                .try_into()
+                                .map_err(|err| format!("failed to convert array index: `{}`", err))?;
+                            array = self
+                                .builder
+                                .build_insert_value(array, val, idx, "array.insert")
+                                .unwrap_or_else(|| unreachable!("array literal larger than its type"))
+                                .into_array_value();
+                        }
+                        array.as_basic_value_enum()
+                    },
                     _ => todo!(),
                 }
             },
-            Comp(fields) => {
+            Tuple(elements) => {
+                let mut tuple = self.get_llvm_basic_type(&ty)?.into_struct_type().get_undef();
+                for (idx, el) in elements.into_iter().enumerate() {
+                    let val = self.visit_node(el)?.expr_value()?;
+                    let idx =
+                        idx.try_into().map_err(|err| format!("failed to convert tuple index: `{}`", err))?;
+                    tuple = self
+                        .builder
+                        .build_insert_value(tuple, val, idx, "tuple.insert")
+                        .unwrap_or_else(|| unreachable!("tuple literal larger than its type"))
+                        .into_struct_value();
+                }
+                tuple.as_basic_value_enum()
+            },
+            // Pointers and function pointers without an initializer are null
+            Comp(_) if matches!(ty, Type::Ptr(_) | Type::Fn(..)) => {
+                self.get_llvm_basic_type(&ty)?.const_zero()
+            },
+            // A trait object without an initializer is null
+            Comp(_) if matches!(ty, Type::Dyn(_)) => self.get_dyn_type().const_zero().as_basic_value_enum(),
+            // An enum without an initializer is its first variant with a zeroed payload
+            Comp(_) if matches!(ty, Type::Enum(_)) => {
+                let enum_ty = self.get_llvm_basic_type(&ty)?.into_struct_type();
+                let discriminant = self.get_discriminant(&ty, 0)?;
+                let mut values = vec![discriminant.as_basic_value_enum()];
+                if let Some(payload_ty) = enum_ty.get_field_type_at_index(1) {
+                    values.push(payload_ty.const_zero());
+                }
+                enum_ty.const_named_struct(&values).as_basic_value_enum()
+            },
+            Comp(fields) if fields.iter().all(|n| matches!(n.kind, hir::node::Kind::Lit { .. })) => {
                 let llvm_struct_type = self
                     .module
                     .get_struct_type(&ty.to_string())
                     .unwrap_or_else(|| unreachable!("can't find struct definition"));
 
-                let values = fields
+                let mut values = fields
                     .into_iter()
                     .flat_map(|n| self.visit_node(n).transpose())
                     .collect::<Result<Vec<_>, String>>()?;
 
+                // An aligned struct ends with a zero-sized field that sets its alignment
+                let align_fields = llvm_struct_type
+                    .get_field_types()
+                    .into_iter()
+                    .skip(values.len())
+                    .map(|ty| ty.const_zero());
+                values.extend(align_fields);
+
                 llvm_struct_type.const_named_struct(&values).as_basic_value_enum()
             },
+            // Fields that aren't constants, like the captures in a closure's environment, are
+            // inserted one at a time
+            Comp(fields) => {
+                let llvm_struct_type = self
+                    .module
+                    .get_struct_type(&ty.to_string())
+                    .unwrap_or_else(|| unreachable!("can't find struct definition"));
+
+                let mut comp = llvm_struct_type.get_undef();
+                for (idx, field) in fields.into_iter().enumerate() {
+                    let val = self.visit_node(field)?.expr_value()?;
+                    let idx =
+                        idx.try_into().map_err(|err| format!("failed to convert field index: `{}`", err))?;
+                    comp = self
+                        .builder
+                        .build_insert_value(comp, val, idx, "struct.insert")
+                        .unwrap_or_else(|| unreachable!("struct literal larger than its type"))
+                        .into_struct_value();
+                }
+                comp.as_basic_value_enum()
+            },
         };
         Ok(Some(lit))
     }
@@ -759,15 +1346,17 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
             .pointer()
             .expect("missing pointer on symbol");
 
-        // Don't generate load if it's a pointer already
-        if let Type::Ptr(_) = ty {
-            Ok(Some(ptr.as_basic_value_enum()))
-        } else {
-            Ok(Some(self.builder.build_load(ptr, &name)))
+        // A variable that's typed as a pointer to itself is a method receiver, which is
+        // passed by address. Variables that hold a pointer are loaded like any other
+        if let Type::Ptr(inner_ty) = &ty {
+            if ptr.get_type().get_element_type() == self.get_llvm_any_type(inner_ty)? {
+                return Ok(Some(ptr.as_basic_value_enum()));
+            }
         }
+        Ok(Some(self.builder.build_load(ptr, &name)))
     }
 
-    fn visit_binop(&mut self, op: Operator, lhs: hir::Node, rhs: hir::Node) -> Self::Result {
+    fn visit_binop(&mut self, op: Operator, lhs: hir::Node, rhs: hir::Node, loc: Loc) -> Self::Result {
         use Operator::*;
 
         let lhs_ty = lhs.ty();
@@ -777,10 +1366,18 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
 
         // Generate the proper instruction for each op
         match op {
+            Add | Sub | Mul if self.overflow_checks && matches!(lhs_ty, int_types!()) => {
+                self.checked_arith(op, (lhs_val, lhs_ty), (rhs_val, rhs_ty), &loc)
+            },
             Add => self.add((lhs_val, lhs_ty), (rhs_val, rhs_ty)),
             Sub => self.sub((lhs_val, lhs_ty), (rhs_val, rhs_ty)),
             Mul => self.mul((lhs_val, lhs_ty), (rhs_val, rhs_ty)),
-            Div => self.div((lhs_val, lhs_ty), (rhs_val, rhs_ty)),
+            Div => {
+                if self.overflow_checks {
+                    self.check_div((lhs_val, lhs_ty), (rhs_val, rhs_ty), &loc)?;
+                }
+                self.div((lhs_val, lhs_ty), (rhs_val, rhs_ty))
+            },
             And | BitAnd => self.and((lhs_val, lhs_ty), (rhs_val, rhs_ty)),
             BitXor => self.xor((lhs_val, lhs_ty), (rhs_val, rhs_ty)),
             Or | BitOr => self.or((lhs_val, lhs_ty), (rhs_val, rhs_ty)),
@@ -802,26 +1399,20 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
         }
     }
 
-    fn visit_call(&mut self, name: String, args: Vec<hir::Node>) -> Self::Result {
+    fn visit_call(&mut self, name: String, args: Vec<hir::Node>, ty: Type) -> Self::Result {
         // Look up the function. Error if it's not been defined.
-        let func = self.module.get_function(&name).ok_or(format!("unknown function call: {}", name))?;
+        let func = self.get_function(&name).ok_or(format!("unknown function call: {}", name))?;
 
         // Codegen the call args
         let mut args_code = Vec::with_capacity(args.len());
         for arg in args {
-            args_code.push(self.visit_node(arg)?.expr_value()?.into());
+            let arg_ty = arg.ty().clone();
+            args_code.push((self.visit_node(arg)?.expr_value()?, arg_ty));
         }
 
-        // Build the call instruction
-        let call_val = self.builder.build_call(func, &args_code, &("call_".to_owned() + &name));
-
-        // If func has a non-void return type, it will produce a call_val that is
-        // converted into a BasicValueEnum. Otherwise it becomes an InstructionValue,
-        // which we ignore.
-        Ok(match call_val.try_as_basic_value() {
-            Either::Left(v) => Some(v),
-            Either::Right(_) => None,
-        })
+        // Build the call instruction. If func has a non-void return type, it produces a
+        // value. Otherwise it becomes an InstructionValue, which we ignore.
+        self.build_abi_call(func.into(), args_code, &ty, &("call_".to_owned() + &name))
     }
 
     fn visit_cond(
@@ -866,8 +1457,8 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
         let then_val = self.visit_node(then_block)?;
         then_bb = self.builder.get_insert_block().ok_or("can't reset `then` block")?;
 
-        // Only jump to the merge block if we don't have a previous break instruction
-        if !then_bb.get_terminator().is_some_and(|t| t.get_opcode() == InstructionOpcode::Br) {
+        // Only jump to the merge block if we don't have a previous break or panic
+        if then_bb.get_terminator().is_none() {
             self.builder.build_unconditional_branch(merge_bb);
         }
 
@@ -882,8 +1473,8 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
             else_val = self.visit_node(else_block)?;
             else_bb = self.builder.get_insert_block().ok_or("can't reset `else` block")?;
 
-            // Only jump to the merge block if we don't have a previous break instruction
-            if !else_bb.get_terminator().is_some_and(|t| t.get_opcode() == InstructionOpcode::Br) {
+            // Only jump to the merge block if we don't have a previous break or panic
+            if else_bb.get_terminator().is_none() {
                 self.builder.build_unconditional_branch(merge_bb);
             }
 
@@ -892,7 +1483,7 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
         }
 
         if let (Some(then_val), Some(else_val)) = (then_val, else_val) {
-            let phi = make_phi_for_type!(self.builder, self.context, ty, "if.else.phi");
+            let phi = make_phi_for_type!(self, &ty, "if.else.phi");
             phi.add_incoming(&[(&then_val, then_bb), (&else_val, else_bb)]);
             Ok(Some(phi.as_basic_value()))
         } else {
@@ -900,6 +1491,12 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
         }
     }
 
+    fn visit_match(
+        &mut self, scrutinee: hir::Node, arms: Vec<MatchArm<hir::Node>>, ty: Type, loc: Loc,
+    ) -> Self::Result {
+        self.codegen_match(scrutinee, arms, ty, &loc)
+    }
+
     fn visit_block(&mut self, list: Vec<hir::Node>) -> Self::Result {
         self.symbol_table.enter_scope();
 
@@ -913,15 +1510,221 @@ impl<'ctx> hir::Visitor for Codegen<'ctx> {
         Ok(node_val)
     }
 
-    fn visit_index(&mut self, array: hir::Node, idx: hir::Node) -> Self::Result {
-        let element_ptr = self.get_array_element(array, idx)?;
+    fn visit_index(&mut self, array: hir::Node, idx: hir::Node, by_ref: bool, loc: Loc) -> Self::Result {
+        let element_ptr = self.get_array_element(array, idx, &loc)?;
+        if by_ref {
+            return Ok(Some(element_ptr.as_basic_value_enum()));
+        }
         Ok(Some(self.builder.build_load(element_ptr, "array.index")))
     }
 
-    fn visit_fselector(&mut self, comp: hir::Node, idx: u32) -> Self::Result {
+    fn visit_fselector(&mut self, comp: hir::Node, idx: u32, ty: Type) -> Self::Result {
+        // Tuples that aren't stored in a variable, like ones returned from calls, are only
+        // values
+        if !matches!(
+            comp.kind,
+            hir::node::Kind::Ident { .. } | hir::node::Kind::FSelector { .. } | hir::node::Kind::Let { .. }
+        ) && matches!(comp.ty(), Type::Tuple(_))
+        {
+            let tuple = self.visit_node(comp)?.expr_value()?.into_struct_value();
+            return Ok(Some(
+                self.builder
+                    .build_extract_value(tuple, idx, &format!("tuple.{}", idx))
+                    .unwrap_or_else(|| unreachable!("tuple is missing element `{}`", idx)),
+            ));
+        }
+
         let field_ptr = self.get_struct_element(comp, idx)?;
+
+        // A field that's typed as a pointer to itself is a method receiver, which is passed
+        // by address
+        if let Type::Ptr(inner_ty) = &ty {
+            if field_ptr.get_type().get_element_type() == self.get_llvm_any_type(inner_ty)? {
+                return Ok(Some(field_ptr.as_basic_value_enum()));
+            }
+        }
         Ok(Some(self.builder.build_load(field_ptr, &format!("struct.{}", idx))))
     }
+
+    fn visit_addr_of(&mut self, name: String, _ty: Type) -> Self::Result {
+        let ptr = self
+            .symbol_table
+            .get(&name)
+            .unwrap_or_else(|| unreachable!("codegen failed to resolve `{}`", name))
+            .pointer()
+            .expect("missing pointer on symbol");
+        Ok(Some(ptr.as_basic_value_enum()))
+    }
+
+    fn visit_fn_ref(&mut self, name: String, _ty: Type) -> Self::Result {
+        let func = self.get_function(&name).ok_or(format!("unknown function reference: {}", name))?;
+        Ok(Some(func.as_global_value().as_pointer_value().as_basic_value_enum()))
+    }
+
+    // Numeric casts are converted in place. Casts to a trait object build one from a
+    // pointer to the struct and the vtable for its impl
+    fn visit_cast(&mut self, expr: hir::Node, ty: Type) -> Self::Result {
+        let trait_name = match &ty {
+            Type::Dyn(trait_name) => trait_name.to_owned(),
+            _ => {
+                let expr_ty = expr.ty().clone();
+                let value = self.visit_node(expr)?.expr_value()?;
+                return self.cast((value, &expr_ty), &ty).map(Some);
+            },
+        };
+        let expr_ty = expr.ty().clone();
+        let struct_name = match &expr_ty {
+            Type::Comp(name) => name.to_owned(),
+            Type::Ptr(boxed) => boxed.get_comp_name().to_owned(),
+            ty => unreachable!("can't cast `{}` to a trait object in codegen", ty),
+        };
+
+        // Same as `get_struct_element()`. Derive the pointer from the load if the value
+        // isn't one already. Struct values that weren't loaded, like the result of an `if`
+        // or a call, are spilled to the stack first
+        let value = self.visit_node(expr)?.expr_value()?;
+        let struct_ptr = match value {
+            BasicValueEnum::PointerValue(ptr) if ptr.get_type().get_element_type().is_struct_type() => ptr,
+            BasicValueEnum::PointerValue(ptr) => self.builder.build_load(ptr, "").into_pointer_value(),
+            _ => match value.as_instruction_value().map(|inst| inst.get_opcode()) {
+                Some(InstructionOpcode::Load) => derive_composite_pointer!(value),
+                _ => {
+                    let parent =
+                        self.builder.get_insert_block().and_then(|x| x.get_parent()).ok_or_else(|| {
+                            "parent function not found when building trait object".to_string()
+                        })?;
+                    let struct_ptr = self.create_entry_block_alloca("dyn.tmp", &expr_ty, &parent)?;
+                    self.builder.build_store(struct_ptr, value);
+                    struct_ptr
+                },
+            },
+        };
+
+        let i8_ptr_ty = self.context.i8_type().ptr_type(inkwell::AddressSpace::Generic);
+        let data_ptr = self.builder.build_pointer_cast(struct_ptr, i8_ptr_ty, "dyn.data");
+        let vtable_ptr = self.builder.build_pointer_cast(
+            self.get_vtable(&struct_name, &trait_name)?.as_pointer_value(),
+            i8_ptr_ty.ptr_type(inkwell::AddressSpace::Generic),
+            "dyn.vtable",
+        );
+
+        let fat_ptr = self
+            .builder
+            .build_insert_value(self.get_dyn_type().get_undef(), data_ptr, 0, "dyn.insert.data")
+            .unwrap_or_else(|| unreachable!("failed to insert data pointer in trait object"))
+            .into_struct_value();
+        let fat_ptr = self
+            .builder
+            .build_insert_value(fat_ptr, vtable_ptr, 1, "dyn.insert.vtable")
+            .unwrap_or_else(|| unreachable!("failed to insert vtable pointer in trait object"));
+        Ok(Some(fat_ptr.into_struct_value().as_basic_value_enum()))
+    }
+
+    // Call a trait object's method indirectly through its vtable slot. The data pointer is
+    // passed as `self`
+    fn visit_dyn_call(&mut self, comp: hir::Node, idx: u32, args: Vec<hir::Node>, ty: Type) -> Self::Result {
+        let fat_ptr = self.visit_node(comp)?.expr_value()?.into_struct_value();
+        let data_ptr = self
+            .builder
+            .build_extract_value(fat_ptr, 0, "dyn.data")
+            .unwrap_or_else(|| unreachable!("missing data pointer in trait object"));
+        let vtable_ptr = self
+            .builder
+            .build_extract_value(fat_ptr, 1, "dyn.vtable")
+            .unwrap_or_else(|| unreachable!("missing vtable pointer in trait object"))
+            .into_pointer_value();
+
+        let slot_idx = self.context.i32_type().const_int(idx as u64, false);
+        let slot_ptr = unsafe { self.builder.build_in_bounds_gep(vtable_ptr, &[slot_idx], "dyn.slot") };
+        let method_ptr = self.builder.build_load(slot_ptr, "dyn.method").into_pointer_value();
+
+        // Codegen the call args
+        let mut args_code = Vec::with_capacity(args.len() + 1);
+        args_code.push((data_ptr, pointer_wrap!(Type::UInt8)));
+        for arg in args {
+            let arg_ty = arg.ty().clone();
+            args_code.push((self.visit_node(arg)?.expr_value()?, arg_ty));
+        }
+
+        // Rebuild the method's type from the args, with `self` as an `i8*`
+        let param_tys = args_code.iter().map(|(_, ty)| ty.clone()).collect::<Vec<_>>();
+        let fn_ty = self.get_llvm_fn_type(&param_tys, &ty, false)?;
+        let method_ptr = self.builder.build_pointer_cast(
+            method_ptr,
+            fn_ty.ptr_type(inkwell::AddressSpace::Generic),
+            "dyn.method.cast",
+        );
+        let method = CallableValue::try_from(method_ptr)
+            .map_err(|_| "invalid method pointer in trait object vtable".to_string())?;
+
+        self.build_abi_call(method, args_code, &ty, "call_dyn")
+    }
+
+    // Call through a function pointer held in a variable or field
+    fn visit_indirect_call(&mut self, callee: hir::Node, args: Vec<hir::Node>, ty: Type) -> Self::Result {
+        let callee_ptr = self.visit_node(callee)?.expr_value()?.into_pointer_value();
+        let callee =
+            CallableValue::try_from(callee_ptr).map_err(|_| "invalid function pointer".to_string())?;
+
+        // Codegen the call args
+        let mut args_code = Vec::with_capacity(args.len());
+        for arg in args {
+            let arg_ty = arg.ty().clone();
+            args_code.push((self.visit_node(arg)?.expr_value()?, arg_ty));
+        }
+
+        self.build_abi_call(callee, args_code, &ty, "call_indirect")
+    }
+
+    // Build the enum on the stack. Store the tag, then view the payload as the variant's
+    // fields and store the args into it.
+    fn visit_variant(&mut self, idx: u32, args: Vec<hir::Node>, ty: Type) -> Self::Result {
+        let parent = self
+            .builder
+            .get_insert_block()
+            .and_then(|x| x.get_parent())
+            .ok_or_else(|| "parent function not found when building enum variant".to_string())?;
+        let enum_alloca = self.create_entry_block_alloca("enum", &ty, &parent)?;
+
+        let tag_ptr = self
+            .builder
+            .build_struct_gep(enum_alloca, 0, "enum.tag")
+            .map_err(|_| "failed to build enum tag GEP")?;
+        self.builder.build_store(tag_ptr, self.get_discriminant(&ty, idx as usize)?);
+
+        if !args.is_empty() {
+            let payload_ty = self.get_variant_payload_type(self.get_enum_symbol(&ty)?, idx as usize)?;
+            let payload_ptr = self
+                .builder
+                .build_struct_gep(enum_alloca, 1, "enum.payload")
+                .map_err(|_| "failed to build enum payload GEP")?;
+            let payload_ptr = self.builder.build_pointer_cast(
+                payload_ptr,
+                payload_ty.ptr_type(inkwell::AddressSpace::Generic),
+                "enum.payload.cast",
+            );
+            for (field_idx, arg) in args.into_iter().enumerate() {
+                let value = self.visit_node(arg)?.expr_value()?;
+                let field_ptr = self
+                    .builder
+                    .build_struct_gep(payload_ptr, field_idx as u32, "enum.field")
+                    .map_err(|_| "failed to build enum field GEP")?;
+                self.builder.build_store(field_ptr, value);
+            }
+        }
+
+        Ok(Some(self.builder.build_load(enum_alloca, "enum.load")))
+    }
+}
+
+// The types of a variant's fields. Structs are held by value, so a variant can outlive
+// the frame that built it
+fn get_variant_field_types(sym: &Symbol, idx: usize) -> Vec<Type> {
+    sym.variants()[idx].1.iter().map(|ty| Type::from(ty.as_str())).collect()
+}
+
+fn vtable_name(struct_name: &str, trait_name: &str) -> String {
+    format!("_{}_{}_vtable", struct_name, trait_name)
 }
 
 // This is a little wonky. Allows us to return a file path for main or a string for the
@@ -1,37 +1,86 @@
 #[macro_export]
 macro_rules! make_undef_value {
-    ($ctx:expr, $ty:expr) => {
+    ($cg:expr, $ty:expr) => {
         match $ty {
-            int8_types!() | Type::Char => $ctx.i8_type().get_undef().as_basic_value_enum(),
-            int16_types!() => $ctx.i16_type().get_undef().as_basic_value_enum(),
-            int32_types!() => $ctx.i32_type().get_undef().as_basic_value_enum(),
-            int64_types!() => $ctx.i64_type().get_undef().as_basic_value_enum(),
-            Type::Float => $ctx.f32_type().get_undef().as_basic_value_enum(),
-            Type::Double => $ctx.f64_type().get_undef().as_basic_value_enum(),
-            Type::Bool => $ctx.bool_type().get_undef().as_basic_value_enum(),
-            Type::Void => $ctx.i8_type().get_undef().as_basic_value_enum(),
-            Type::SArray(..) => todo!(),
-            Type::Comp(_) => todo!(),
-            Type::Ptr(_) => todo!(),
+            int8_types!() | Type::Char => $cg.context.i8_type().get_undef().as_basic_value_enum(),
+            int16_types!() => $cg.context.i16_type().get_undef().as_basic_value_enum(),
+            int32_types!() => $cg.context.i32_type().get_undef().as_basic_value_enum(),
+            int64_types!() => $cg.context.i64_type().get_undef().as_basic_value_enum(),
+            int128_types!() => $cg.context.i128_type().get_undef().as_basic_value_enum(),
+            size_types!() => {
+                $cg.context.ptr_sized_int_type(&$cg.target_data, None).get_undef().as_basic_value_enum()
+            },
+            Type::Float => $cg.context.f32_type().get_undef().as_basic_value_enum(),
+            Type::Double => $cg.context.f64_type().get_undef().as_basic_value_enum(),
+            Type::Bool => $cg.context.bool_type().get_undef().as_basic_value_enum(),
+            Type::Void => $cg.context.i8_type().get_undef().as_basic_value_enum(),
+            Type::SArray(..) => {
+                $cg.get_llvm_basic_type($ty)?.into_array_type().get_undef().as_basic_value_enum()
+            },
+            Type::Comp(_) => {
+                $cg.get_llvm_basic_type($ty)?.into_struct_type().get_undef().as_basic_value_enum()
+            },
+            Type::Ptr(_) => {
+                $cg.get_llvm_basic_type($ty)?.into_pointer_type().get_undef().as_basic_value_enum()
+            },
+            Type::Dyn(_) => {
+                $cg.get_llvm_basic_type($ty)?.into_struct_type().get_undef().as_basic_value_enum()
+            },
+            Type::Enum(_) => {
+                $cg.get_llvm_basic_type($ty)?.into_struct_type().get_undef().as_basic_value_enum()
+            },
+            Type::Tuple(_) => {
+                $cg.get_llvm_basic_type($ty)?.into_struct_type().get_undef().as_basic_value_enum()
+            },
+            Type::Fn(..) => {
+                $cg.get_llvm_basic_type($ty)?.into_pointer_type().get_undef().as_basic_value_enum()
+            },
         }
     };
 }
 
 #[macro_export]
 macro_rules! make_phi_for_type {
-    ($bldr:expr, $ctx:expr, $ty:expr, $name:expr) => {
+    ($cg:expr, $ty:expr, $name:expr) => {
         match $ty {
-            int8_types!() | Type::Char => $bldr.build_phi($ctx.i8_type(), &($name.to_owned() + ".int8")),
-            int16_types!() => $bldr.build_phi($ctx.i16_type(), &($name.to_owned() + ".int16")),
-            int32_types!() => $bldr.build_phi($ctx.i32_type(), &($name.to_owned() + ".int32")),
-            int64_types!() => $bldr.build_phi($ctx.i64_type(), &($name.to_owned() + ".int64")),
-            Type::Float => $bldr.build_phi($ctx.f32_type(), &($name.to_owned() + ".float")),
-            Type::Double => $bldr.build_phi($ctx.f64_type(), &($name.to_owned() + ".double")),
-            Type::Bool => $bldr.build_phi($ctx.bool_type(), &($name.to_owned() + ".bool")),
-            Type::Void => $bldr.build_phi($ctx.i8_type(), &($name.to_owned() + ".void")),
-            Type::SArray(..) => todo!(),
-            Type::Comp(_) => todo!(),
-            Type::Ptr(_) => todo!(),
+            int8_types!() | Type::Char => {
+                $cg.builder.build_phi($cg.context.i8_type(), &($name.to_owned() + ".int8"))
+            },
+            int16_types!() => $cg.builder.build_phi($cg.context.i16_type(), &($name.to_owned() + ".int16")),
+            int32_types!() => $cg.builder.build_phi($cg.context.i32_type(), &($name.to_owned() + ".int32")),
+            int64_types!() => $cg.builder.build_phi($cg.context.i64_type(), &($name.to_owned() + ".int64")),
+            int128_types!() => {
+                $cg.builder.build_phi($cg.context.i128_type(), &($name.to_owned() + ".int128"))
+            },
+            size_types!() => $cg.builder.build_phi(
+                $cg.context.ptr_sized_int_type(&$cg.target_data, None),
+                &($name.to_owned() + ".size"),
+            ),
+            Type::Float => $cg.builder.build_phi($cg.context.f32_type(), &($name.to_owned() + ".float")),
+            Type::Double => $cg.builder.build_phi($cg.context.f64_type(), &($name.to_owned() + ".double")),
+            Type::Bool => $cg.builder.build_phi($cg.context.bool_type(), &($name.to_owned() + ".bool")),
+            Type::Void => $cg.builder.build_phi($cg.context.i8_type(), &($name.to_owned() + ".void")),
+            Type::SArray(..) => {
+                $cg.builder.build_phi($cg.get_llvm_basic_type($ty)?, &($name.to_owned() + ".sarray"))
+            },
+            Type::Comp(_) => {
+                $cg.builder.build_phi($cg.get_llvm_basic_type($ty)?, &($name.to_owned() + ".comp"))
+            },
+            Type::Ptr(_) => {
+                $cg.builder.build_phi($cg.get_llvm_basic_type($ty)?, &($name.to_owned() + ".ptr"))
+            },
+            // Trait objects are a fat pointer of the data and vtable pointers
+            Type::Dyn(_) => {
+                $cg.builder.build_phi($cg.get_llvm_basic_type($ty)?, &($name.to_owned() + ".dyn"))
+            },
+            Type::Enum(_) => {
+                $cg.builder.build_phi($cg.get_llvm_basic_type($ty)?, &($name.to_owned() + ".enum"))
+            },
+            Type::Tuple(_) => {
+                $cg.builder.build_phi($cg.get_llvm_basic_type($ty)?, &($name.to_owned() + ".tuple"))
+            },
+            // Functions are selected by their pointers
+            Type::Fn(..) => $cg.builder.build_phi($cg.get_llvm_basic_type($ty)?, &($name.to_owned() + ".fn")),
         }
     };
 }
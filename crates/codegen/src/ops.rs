@@ -76,6 +76,86 @@ impl<'ctx> Codegen<'ctx> {
         }
     }
 
+    // Integer add/sub/mul via the `llvm.*.with.overflow` intrinsics, panicking on overflow
+    pub(super) fn checked_arith(
+        &self, op: Operator, lhs: (BasicValueEnum<'ctx>, &Type), rhs: (BasicValueEnum<'ctx>, &Type),
+        loc: &Loc,
+    ) -> OpResult<'ctx> {
+        let sign = match lhs.1 {
+            signed_int_types!() => "s",
+            unsigned_int_types!() => "u",
+            _ => return Err(format!("Unsupported type in checked `{}` operation", op)),
+        };
+        let (name, verb) = match op {
+            Operator::Add => ("add", "add"),
+            Operator::Sub => ("sub", "subtract"),
+            Operator::Mul => ("mul", "multiply"),
+            x => return Err(format!("No overflow check for operator: `{}`", x)),
+        };
+
+        let int_ty = lhs.0.into_int_value().get_type();
+        let intrinsic_name = format!("llvm.{}{}.with.overflow.i{}", sign, name, int_ty.get_bit_width());
+        let intrinsic = self.module.get_function(&intrinsic_name).unwrap_or_else(|| {
+            let ret_ty = self.context.struct_type(&[int_ty.into(), self.context.bool_type().into()], false);
+            let fn_ty = ret_ty.fn_type(&[int_ty.into(), int_ty.into()], false);
+            self.module.add_function(&intrinsic_name, fn_ty, None)
+        });
+
+        let res = self
+            .builder
+            .build_call(intrinsic, &[lhs.0.into(), rhs.0.into()], &format!("{}.checked", name))
+            .try_as_basic_value()
+            .left()
+            .ok_or("Overflow intrinsic returned void")?
+            .into_struct_value();
+        let value = self
+            .builder
+            .build_extract_value(res, 0, &format!("{}.int", name))
+            .ok_or("Overflow intrinsic result has no value")?;
+        let overflowed = self
+            .builder
+            .build_extract_value(res, 1, &format!("{}.overflow", name))
+            .ok_or("Overflow intrinsic result has no flag")?
+            .into_int_value();
+
+        self.build_check(overflowed, &format!("attempt to {} with overflow", verb), loc)?;
+        Ok(value)
+    }
+
+    // Panic on integer division by zero, and on `MIN / -1` for signed types
+    pub(super) fn check_div(
+        &self, lhs: (BasicValueEnum<'ctx>, &Type), rhs: (BasicValueEnum<'ctx>, &Type), loc: &Loc,
+    ) -> Result<(), String> {
+        if !matches!(lhs.1, int_types!()) {
+            return Ok(());
+        }
+
+        let lhs_val = lhs.0.into_int_value();
+        let rhs_val = rhs.0.into_int_value();
+        let int_ty = rhs_val.get_type();
+
+        let is_zero =
+            self.builder.build_int_compare(IntPredicate::EQ, rhs_val, int_ty.const_zero(), "div.zero");
+        self.build_check(is_zero, "attempt to divide by zero", loc)?;
+
+        if matches!(lhs.1, signed_int_types!()) {
+            let min = int_ty
+                .const_int(1, false)
+                .const_shl(int_ty.const_int(int_ty.get_bit_width() as u64 - 1, false));
+            let is_min = self.builder.build_int_compare(IntPredicate::EQ, lhs_val, min, "div.min");
+            let is_neg_one = self.builder.build_int_compare(
+                IntPredicate::EQ,
+                rhs_val,
+                int_ty.const_all_ones(),
+                "div.neg1",
+            );
+            let overflowed = self.builder.build_and(is_min, is_neg_one, "div.overflow");
+            self.build_check(overflowed, "attempt to divide with overflow", loc)?;
+        }
+
+        Ok(())
+    }
+
     pub(super) fn and(
         &self, lhs: (BasicValueEnum<'ctx>, &Type), rhs: (BasicValueEnum<'ctx>, &Type),
     ) -> OpResult<'ctx> {
@@ -118,6 +198,11 @@ impl<'ctx> Codegen<'ctx> {
         use Operator::*;
 
         let inst = match (lhs.1, op) {
+            (Type::Comp(_) | Type::SArray(..), Eq) => self.cmp_aggregate(lhs, rhs)?,
+            (Type::Comp(_) | Type::SArray(..), NotEq) => {
+                let eq = self.cmp_aggregate(lhs, rhs)?;
+                self.builder.build_not(eq, "ne.aggregate")
+            },
             (int_types!() | Type::Bool | Type::Char, Eq) => self.builder.build_int_compare(
                 IntPredicate::EQ,
                 lhs.0.into_int_value(),
@@ -222,6 +307,27 @@ impl<'ctx> Codegen<'ctx> {
         Ok(self.builder.build_int_cast(inst, self.context.bool_type(), "cmp.bool").as_basic_value_enum())
     }
 
+    // Structs and fixed arrays are equal when all of their fields or elements are
+    fn cmp_aggregate(
+        &self, lhs: (BasicValueEnum<'ctx>, &Type), rhs: (BasicValueEnum<'ctx>, &Type),
+    ) -> Result<IntValue<'ctx>, String> {
+        let element_tys = match lhs.1 {
+            Type::Comp(name) => self.get_struct_fields(name)?.into_iter().map(|(_, ty)| ty).collect(),
+            Type::SArray(ty, len) => vec![*ty.clone(); *len],
+            ty => unreachable!("expected struct or array in `cmp_aggregate()`, got `{}`", ty),
+        };
+
+        let mut eq = self.context.bool_type().const_int(1, false);
+        for (idx, ty) in element_tys.iter().enumerate() {
+            let idx = idx.try_into().map_err(|err| format!("failed to convert element index: `{}`", err))?;
+            let lhs_el = self.extract_element(lhs.0, idx, "eq.lhs");
+            let rhs_el = self.extract_element(rhs.0, idx, "eq.rhs");
+            let el_eq = self.cmp(Operator::Eq, (lhs_el, ty), (rhs_el, ty))?.into_int_value();
+            eq = self.builder.build_and(eq, el_eq, "eq.and");
+        }
+        Ok(eq)
+    }
+
     pub(super) fn assign(&mut self, lhs: hir::Node, rhs: BasicValueEnum<'ctx>) -> OpResult<'ctx> {
         use hir::node::Kind::*;
 
@@ -232,7 +338,7 @@ impl<'ctx> Codegen<'ctx> {
                 .unwrap_or_else(|| unreachable!("unknown variable in assignment: {}", name))
                 .pointer()
                 .expect("missing pointer on symbol"),
-            Index { array, idx, .. } => self.get_array_element(*array, *idx)?,
+            Index { array, idx, loc, .. } => self.get_array_element(*array, *idx, &loc)?,
             FSelector { comp, idx, .. } => self.get_struct_element(*comp, idx)?,
             _ => unreachable!("bad LHS in codegen assignment: `{}`", lhs),
         };
@@ -255,4 +361,58 @@ impl<'ctx> Codegen<'ctx> {
             _ => Err("Unsupported type in `neg` operation".to_string()),
         }
     }
+
+    // Conversions
+
+    // Numeric casts checked by the tych. Integers are extended according to the signedness
+    // of the source type. `bool` and `char` are unsigned
+    pub(super) fn cast(&self, val: (BasicValueEnum<'ctx>, &Type), ty: &Type) -> OpResult<'ctx> {
+        let (value, from_ty) = val;
+        if from_ty == ty {
+            return Ok(value);
+        }
+
+        let llvm_ty = self.get_llvm_basic_type(ty)?;
+        Ok(match (from_ty, ty) {
+            (int_types!() | Type::Bool | Type::Char, int_types!() | Type::Char) => {
+                let (int_value, int_ty) = (value.into_int_value(), llvm_ty.into_int_type());
+                let (from_width, to_width) = (int_value.get_type().get_bit_width(), int_ty.get_bit_width());
+                let cast_value = if from_width > to_width {
+                    self.builder.build_int_truncate(int_value, int_ty, "cast.trunc")
+                } else if from_width == to_width {
+                    int_value
+                } else if matches!(from_ty, signed_int_types!()) {
+                    self.builder.build_int_s_extend(int_value, int_ty, "cast.sext")
+                } else {
+                    self.builder.build_int_z_extend(int_value, int_ty, "cast.zext")
+                };
+                cast_value.as_basic_value_enum()
+            },
+            (signed_int_types!(), float_types!()) => self
+                .builder
+                .build_signed_int_to_float(value.into_int_value(), llvm_ty.into_float_type(), "cast.sitofp")
+                .as_basic_value_enum(),
+            (unsigned_int_types!(), float_types!()) => self
+                .builder
+                .build_unsigned_int_to_float(value.into_int_value(), llvm_ty.into_float_type(), "cast.uitofp")
+                .as_basic_value_enum(),
+            (float_types!(), signed_int_types!()) => self
+                .builder
+                .build_float_to_signed_int(value.into_float_value(), llvm_ty.into_int_type(), "cast.fptosi")
+                .as_basic_value_enum(),
+            (float_types!(), unsigned_int_types!()) => self
+                .builder
+                .build_float_to_unsigned_int(value.into_float_value(), llvm_ty.into_int_type(), "cast.fptoui")
+                .as_basic_value_enum(),
+            (Type::Float, Type::Double) => self
+                .builder
+                .build_float_ext(value.into_float_value(), llvm_ty.into_float_type(), "cast.fpext")
+                .as_basic_value_enum(),
+            (Type::Double, Type::Float) => self
+                .builder
+                .build_float_trunc(value.into_float_value(), llvm_ty.into_float_type(), "cast.fptrunc")
+                .as_basic_value_enum(),
+            (from_ty, ty) => return Err(format!("Unsupported cast from `{}` to `{}`", from_ty, ty)),
+        })
+    }
 }
@@ -0,0 +1,172 @@
+use inkwell::attributes::{Attribute, AttributeLoc};
+use inkwell::module::Linkage;
+use inkwell::values::BasicMetadataValueEnum;
+
+use super::*;
+
+// Runtime checks inserted by the compiler branch to a cold block that calls
+// `__light_panic`. The panic routine is defined (once per module) with internal
+// linkage, prints the message and source location to stderr, and then either aborts or
+// exits with `PANIC_EXIT_CODE`, depending on `--panic`. Messages that
+// include runtime values, like an out of bounds index, are formatted on the stack first.
+
+const PANIC_FN: &str = "__light_panic";
+
+// Exit status used by `--panic=exit`
+const PANIC_EXIT_CODE: u64 = 101;
+
+// Size of the buffer that formatted panic messages are rendered into
+const PANIC_MSG_LEN: u32 = 256;
+
+impl<'ctx> Codegen<'ctx> {
+    // Branch to a panic block if `failed` is true, otherwise continue in a new block
+    pub(super) fn build_check(&self, failed: IntValue<'ctx>, msg: &str, loc: &Loc) -> Result<(), String> {
+        let cont_bb = self.build_panic_branch(failed)?;
+        self.build_panic(msg, loc);
+        self.builder.position_at_end(cont_bb);
+        Ok(())
+    }
+
+    // Like `build_check`, but `fmt` is a printf format string that's rendered with `args`,
+    // followed by `value` printed the way `debug` prints it. It's only rendered once the
+    // check has failed
+    pub(super) fn build_check_debug(
+        &self, failed: IntValue<'ctx>, fmt: &str, args: &[BasicMetadataValueEnum<'ctx>],
+        value: (BasicValueEnum<'ctx>, &Type), loc: &Loc,
+    ) -> Result<(), String> {
+        let cont_bb = self.build_panic_branch(failed)?;
+        let mut fmt = fmt.to_owned();
+        let mut args = args.to_vec();
+        self.build_debug_args(value.0, value.1, &mut fmt, &mut args)?;
+        self.build_panic_fmt(&fmt, &args, loc);
+        self.builder.position_at_end(cont_bb);
+        Ok(())
+    }
+
+    // Call the panic routine. Terminates the current block.
+    pub(super) fn build_panic(&self, msg: &str, loc: &Loc) {
+        let msg = self.builder.build_global_string_ptr(msg, "panic.msg").as_pointer_value();
+        self.call_panic_fn(msg, loc);
+    }
+
+    // Render the message into a stack buffer with `snprintf` and call the panic routine.
+    // Terminates the current block.
+    pub(super) fn build_panic_fmt(&self, fmt: &str, args: &[BasicMetadataValueEnum<'ctx>], loc: &Loc) {
+        let i8_ptr_ty = self.context.i8_type().ptr_type(inkwell::AddressSpace::Generic);
+        let size_ty = self.context.ptr_sized_int_type(&self.target_data, None);
+        let snprintf = self.module.get_function("snprintf").unwrap_or_else(|| {
+            let ty =
+                self.context.i32_type().fn_type(&[i8_ptr_ty.into(), size_ty.into(), i8_ptr_ty.into()], true);
+            self.module.add_function("snprintf", ty, None)
+        });
+
+        let buf = self.builder.build_alloca(self.context.i8_type().array_type(PANIC_MSG_LEN), "panic.buf");
+        let buf = self.builder.build_pointer_cast(buf, i8_ptr_ty, "panic.buf.ptr");
+        let fmt = self.builder.build_global_string_ptr(fmt, "panic.fmt").as_pointer_value();
+
+        let mut snprintf_args: Vec<BasicMetadataValueEnum> =
+            vec![buf.into(), size_ty.const_int(PANIC_MSG_LEN as u64, false).into(), fmt.into()];
+        snprintf_args.extend_from_slice(args);
+        self.builder.build_call(snprintf, &snprintf_args, "");
+
+        self.call_panic_fn(buf, loc);
+    }
+
+    // Split the current block on `failed`. The builder is left in the panic block and the
+    // continuation block is returned
+    fn build_panic_branch(&self, failed: IntValue<'ctx>) -> Result<BasicBlock<'ctx>, String> {
+        let parent = self
+            .builder
+            .get_insert_block()
+            .and_then(|x| x.get_parent())
+            .ok_or_else(|| "Parent function not found when building runtime check".to_string())?;
+
+        let panic_bb = self.context.append_basic_block(parent, "panic");
+        let cont_bb = self.context.append_basic_block(parent, "panic.cont");
+        self.builder.build_conditional_branch(failed, panic_bb, cont_bb);
+        self.builder.position_at_end(panic_bb);
+
+        Ok(cont_bb)
+    }
+
+    fn call_panic_fn(&self, msg: PointerValue<'ctx>, loc: &Loc) {
+        let panic_fn = self.get_panic_fn();
+        let i32_ty = self.context.i32_type();
+
+        let file = self.builder.build_global_string_ptr(&loc.file, "panic.file").as_pointer_value();
+        let args = [
+            msg.into(),
+            file.into(),
+            i32_ty.const_int(loc.line as u64, false).into(),
+            i32_ty.const_int(loc.column as u64, false).into(),
+        ];
+
+        self.builder.build_call(panic_fn, &args, "");
+        self.builder.build_unreachable();
+    }
+
+    // Look up the panic routine, defining it on first use
+    fn get_panic_fn(&self) -> FunctionValue<'ctx> {
+        if let Some(func) = self.module.get_function(PANIC_FN) {
+            return func;
+        }
+
+        let i32_ty = self.context.i32_type();
+        let i8_ptr_ty = self.context.i8_type().ptr_type(inkwell::AddressSpace::Generic);
+
+        // void __light_panic(i8* msg, i8* file, i32 line, i32 column)
+        let panic_ty = self
+            .context
+            .void_type()
+            .fn_type(&[i8_ptr_ty.into(), i8_ptr_ty.into(), i32_ty.into(), i32_ty.into()], false);
+        let panic_fn = self.module.add_function(PANIC_FN, panic_ty, Some(Linkage::Internal));
+        for attr in ["noreturn", "cold", "noinline"] {
+            let attr = self.context.create_enum_attribute(Attribute::get_named_enum_kind_id(attr), 0);
+            panic_fn.add_attribute(AttributeLoc::Function, attr);
+        }
+
+        // libc functions used by the body
+        let dprintf = self.module.get_function("dprintf").unwrap_or_else(|| {
+            let ty = i32_ty.fn_type(&[i32_ty.into(), i8_ptr_ty.into()], true);
+            self.module.add_function("dprintf", ty, None)
+        });
+
+        // Build the body with its own builder so the caller's insert point is untouched
+        let builder = self.context.create_builder();
+        builder.position_at_end(self.context.append_basic_block(panic_fn, "entry"));
+
+        let fmt = builder.build_global_string_ptr("panicked at %s:%d:%d: %s\n", "panic.fmt");
+        let params = panic_fn.get_params();
+        let args = [
+            i32_ty.const_int(2, false).into(), // stderr
+            fmt.as_pointer_value().into(),
+            params[1].into(),
+            params[2].into(),
+            params[3].into(),
+            params[0].into(),
+        ];
+        builder.build_call(dprintf, &args, "");
+        match self.panic_strategy {
+            PanicStrategy::Abort => {
+                let abort = self.module.get_function("abort").unwrap_or_else(|| {
+                    let ty = self.context.void_type().fn_type(&[], false);
+                    self.module.add_function("abort", ty, None)
+                });
+                builder.build_call(abort, &[], "");
+            },
+            PanicStrategy::Exit => {
+                let exit = self.module.get_function("exit").unwrap_or_else(|| {
+                    let ty = self.context.void_type().fn_type(&[i32_ty.into()], false);
+                    self.module.add_function("exit", ty, None)
+                });
+                // The module may already declare `exit` with a different int width
+                let status_ty =
+                    exit.get_type().get_param_types().first().map(|ty| ty.into_int_type()).unwrap_or(i32_ty);
+                builder.build_call(exit, &[status_ty.const_int(PANIC_EXIT_CODE, false).into()], "");
+            },
+        }
+        builder.build_unreachable();
+
+        panic_fn
+    }
+}
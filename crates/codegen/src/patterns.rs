@@ -0,0 +1,228 @@
+use super::*;
+
+type MatchResult<'ctx> = Result<Option<BasicValueEnum<'ctx>>, String>;
+
+// A match over ints, chars, bools, or enum tags without any guards or ranges is a
+// `switch`. Everything else tests each arm in order and falls through to the next one on
+// failure. Either way, each arm gets a block that binds its values, checks its guard, and
+// runs its body. tych has checked that the arms are exhaustive, so falling out of the
+// last arm is unreachable and panics.
+
+impl<'ctx> Codegen<'ctx> {
+    pub(super) fn codegen_match(
+        &mut self, scrutinee: hir::Node, arms: Vec<MatchArm<hir::Node>>, ty: Type, loc: &Loc,
+    ) -> MatchResult<'ctx> {
+        let parent = self
+            .builder
+            .get_insert_block()
+            .and_then(|x| x.get_parent())
+            .ok_or_else(|| "parent function not found when building match".to_string())?;
+
+        let scrutinee_ty = scrutinee.ty().clone();
+        let scrutinee_val = self.visit_node(scrutinee)?.expr_value()?;
+
+        // The value that's compared against literals and variants
+        let test_val = match &scrutinee_ty {
+            Type::Enum(_) => Some(
+                self.builder
+                    .build_extract_value(scrutinee_val.into_struct_value(), 0, "match.tag")
+                    .unwrap_or_else(|| unreachable!("missing tag in enum"))
+                    .into_int_value(),
+            ),
+            int_types!() | Type::Char | Type::Bool => Some(scrutinee_val.into_int_value()),
+            _ => None,
+        };
+
+        let arm_bbs =
+            arms.iter().map(|_| self.context.append_basic_block(parent, "match.arm")).collect::<Vec<_>>();
+        let merge_bb = self.context.append_basic_block(parent, "match.merge");
+
+        let is_switch = test_val.is_some()
+            && arms.iter().all(|arm| arm.guard.is_none() && !matches!(arm.pattern, Pattern::Range { .. }));
+
+        // Each arm's block jumps to the next test when its guard fails
+        let mut next_bbs = Vec::with_capacity(arms.len());
+        if is_switch {
+            let mut cases = vec![];
+            let mut default_bb = None;
+            for (arm, arm_bb) in arms.iter().zip(&arm_bbs) {
+                match self.pattern_const(&arm.pattern, &scrutinee_ty)? {
+                    Some(case) => cases.push((case, *arm_bb)),
+                    None => default_bb = Some(*arm_bb),
+                }
+                next_bbs.push(None);
+            }
+
+            let unreachable_bb = self.context.append_basic_block(parent, "match.unreachable");
+            self.builder.build_switch(
+                test_val.unwrap_or_else(|| unreachable!("switch without a value")),
+                default_bb.unwrap_or(unreachable_bb),
+                &cases,
+            );
+            self.builder.position_at_end(unreachable_bb);
+            self.build_panic("entered unreachable code", loc);
+        } else {
+            for (arm, arm_bb) in arms.iter().zip(&arm_bbs) {
+                let next_bb = self.context.append_basic_block(parent, "match.next");
+                match self.pattern_test(&arm.pattern, &scrutinee_ty, test_val)? {
+                    Some(cond) => self.builder.build_conditional_branch(cond, *arm_bb, next_bb),
+                    None => self.builder.build_unconditional_branch(*arm_bb),
+                };
+                self.builder.position_at_end(next_bb);
+                next_bbs.push(Some(next_bb));
+            }
+            self.build_panic("entered unreachable code", loc);
+        }
+
+        // Arms
+        let mut incoming = Vec::with_capacity(arms.len());
+        for ((arm, arm_bb), next_bb) in arms.into_iter().zip(arm_bbs).zip(next_bbs) {
+            self.builder.position_at_end(arm_bb);
+            self.symbol_table.enter_scope();
+
+            self.bind_pattern(&arm.pattern, &scrutinee_ty, scrutinee_val, &parent)?;
+
+            if let Some(guard) = arm.guard {
+                let guard_val = self.visit_node(guard)?.expr_value()?.into_int_value();
+                let body_bb = self.context.append_basic_block(parent, "match.body");
+                let next_bb = next_bb.unwrap_or_else(|| unreachable!("guarded arm in switch"));
+                self.builder.build_conditional_branch(guard_val, body_bb, next_bb);
+                self.builder.position_at_end(body_bb);
+            }
+
+            let body_val = self.visit_node(arm.body)?;
+            let end_bb = self.builder.get_insert_block().ok_or("can't reset match arm block")?;
+            if end_bb.get_terminator().is_none() {
+                self.builder.build_unconditional_branch(merge_bb);
+                incoming.push((body_val, end_bb));
+            }
+
+            self.symbol_table.leave_scope();
+        }
+
+        self.builder.position_at_end(merge_bb);
+
+        // Merge the arm values if the match has one
+        if ty == Type::Void || incoming.is_empty() || incoming.iter().any(|(val, _)| val.is_none()) {
+            return Ok(None);
+        }
+        let phi = make_phi_for_type!(self, &ty, "match.phi");
+        for (val, bb) in &incoming {
+            let val = val.unwrap_or_else(|| unreachable!("missing match arm value"));
+            phi.add_incoming(&[(&val as &dyn BasicValue, *bb)]);
+        }
+        Ok(Some(phi.as_basic_value()))
+    }
+
+    // The `switch` case for a pattern. Catch-alls are the default and don't have one
+    fn pattern_const(
+        &mut self, pattern: &Pattern<hir::Node>, ty: &Type,
+    ) -> Result<Option<IntValue<'ctx>>, String> {
+        Ok(match pattern {
+            Pattern::Lit(value) => Some(self.visit_node(value.clone())?.expr_value()?.into_int_value()),
+            Pattern::Variant { variant, .. } => {
+                Some(self.get_discriminant(ty, self.get_variant_index(ty, variant)?)?)
+            },
+            Pattern::Wildcard | Pattern::Binding(_) => None,
+            Pattern::Range { .. } => unreachable!("range pattern in switch"),
+        })
+    }
+
+    // Compare the scrutinee against a pattern. Catch-alls always match and don't need a
+    // test
+    fn pattern_test(
+        &mut self, pattern: &Pattern<hir::Node>, ty: &Type, test_val: Option<IntValue<'ctx>>,
+    ) -> Result<Option<IntValue<'ctx>>, String> {
+        if pattern.is_catch_all() {
+            return Ok(None);
+        }
+        let test_val = test_val.unwrap_or_else(|| unreachable!("pattern test without a value"));
+
+        let (lo_pred, hi_pred) = match ty {
+            signed_int_types!() => (IntPredicate::SGE, IntPredicate::SLT),
+            _ => (IntPredicate::UGE, IntPredicate::ULT),
+        };
+
+        Ok(Some(match pattern {
+            Pattern::Range { start, end, inclusive } => {
+                let start = self.visit_node(start.clone())?.expr_value()?.into_int_value();
+                let end = self.visit_node(end.clone())?.expr_value()?.into_int_value();
+                let hi_pred = match (inclusive, hi_pred) {
+                    (true, IntPredicate::SLT) => IntPredicate::SLE,
+                    (true, _) => IntPredicate::ULE,
+                    (false, pred) => pred,
+                };
+                let above = self.builder.build_int_compare(lo_pred, test_val, start, "match.range.lo");
+                let below = self.builder.build_int_compare(hi_pred, test_val, end, "match.range.hi");
+                self.builder.build_and(above, below, "match.range")
+            },
+            pattern => {
+                let case = self
+                    .pattern_const(pattern, ty)?
+                    .unwrap_or_else(|| unreachable!("catch-all pattern has no case"));
+                self.builder.build_int_compare(IntPredicate::EQ, test_val, case, "match.eq")
+            },
+        }))
+    }
+
+    // Bind the scrutinee, or the fields of a variant, to the names in a pattern
+    fn bind_pattern(
+        &mut self, pattern: &Pattern<hir::Node>, ty: &Type, value: BasicValueEnum<'ctx>,
+        parent: &FunctionValue<'ctx>,
+    ) -> Result<(), String> {
+        match pattern {
+            Pattern::Binding(name) => self.bind_value(name, ty, value, parent),
+            Pattern::Variant { variant, fields, .. }
+                if fields.iter().any(|f| matches!(f, Pattern::Binding(_))) =>
+            {
+                // View the payload as the variant's fields
+                let idx = self.get_variant_index(ty, variant)?;
+                let payload_ty = self.get_variant_payload_type(self.get_enum_symbol(ty)?, idx)?;
+                let field_tys = get_variant_field_types(self.get_enum_symbol(ty)?, idx);
+
+                let enum_ptr = self.create_entry_block_alloca("match.enum", ty, parent)?;
+                self.builder.build_store(enum_ptr, value);
+                let payload_ptr = self
+                    .builder
+                    .build_struct_gep(enum_ptr, 1, "match.payload")
+                    .map_err(|_| "failed to build enum payload GEP")?;
+                let payload_ptr = self.builder.build_pointer_cast(
+                    payload_ptr,
+                    payload_ty.ptr_type(inkwell::AddressSpace::Generic),
+                    "match.payload.cast",
+                );
+
+                for (field_idx, (field, field_ty)) in fields.iter().zip(field_tys).enumerate() {
+                    if let Pattern::Binding(name) = field {
+                        let field_ptr = self
+                            .builder
+                            .build_struct_gep(payload_ptr, field_idx as u32, "match.field")
+                            .map_err(|_| "failed to build enum field GEP")?;
+                        let field_val = self.builder.build_load(field_ptr, name);
+                        self.bind_value(name, &field_ty, field_val, parent)?;
+                    }
+                }
+                Ok(())
+            },
+            _ => Ok(()),
+        }
+    }
+
+    // Bound values get a stack slot like a `let`
+    pub(super) fn bind_value(
+        &mut self, name: &str, ty: &Type, value: BasicValueEnum<'ctx>, parent: &FunctionValue<'ctx>,
+    ) -> Result<(), String> {
+        let alloca = self.create_entry_block_alloca(name, ty, parent)?;
+        self.builder.build_store(alloca, value);
+        self.symbol_table.insert(CodegenSymbol::new_var(name, ty, &self.module_name, alloca));
+        Ok(())
+    }
+
+    fn get_variant_index(&self, ty: &Type, variant: &str) -> Result<usize, String> {
+        self.get_enum_symbol(ty)?
+            .variants()
+            .iter()
+            .position(|(name, ..)| name == variant)
+            .ok_or_else(|| format!("enum `{}` has no variant: `{}`", ty, variant))
+    }
+}
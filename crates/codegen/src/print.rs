@@ -0,0 +1,48 @@
+use inkwell::values::BasicMetadataValueEnum;
+
+use super::*;
+
+// `print` and `println` write to stdout with one `printf` call per piece of text and per
+// arg. `*char`s print as strings. Everything else prints the way `debug()` prints it.
+
+impl<'ctx> Codegen<'ctx> {
+    pub(super) fn build_print(
+        &self, pieces: &[String], values: Vec<(BasicValueEnum<'ctx>, Type)>,
+    ) -> Result<(), String> {
+        let printf = self.module.get_function("printf").unwrap_or_else(|| {
+            let i8_ptr_ty = self.context.i8_type().ptr_type(inkwell::AddressSpace::Generic);
+            let ty = self.context.i32_type().fn_type(&[i8_ptr_ty.into()], true);
+            self.module.add_function("printf", ty, None)
+        });
+
+        let mut values = values.into_iter();
+        for (idx, piece) in pieces.iter().enumerate() {
+            if !piece.is_empty() {
+                let fmt = self.builder.build_global_string_ptr(&piece.replace('%', "%%"), "print.text");
+                self.builder.build_call(printf, &[fmt.as_pointer_value().into()], "");
+            }
+
+            // The last piece has no arg after it
+            if idx == pieces.len() - 1 {
+                break;
+            }
+            let (value, ty) = values.next().ok_or_else(|| String::from("missing arg in print"))?;
+
+            let mut fmt = String::new();
+            let mut args: Vec<BasicMetadataValueEnum> = vec![];
+            match ty {
+                Type::Ptr(inner) if *inner == Type::Char => {
+                    fmt.push_str("%s");
+                    args.push(value.into());
+                },
+                ty => self.build_debug_args(value, &ty, &mut fmt, &mut args)?,
+            }
+
+            let fmt = self.builder.build_global_string_ptr(&fmt, "print.fmt").as_pointer_value();
+            let mut printf_args: Vec<BasicMetadataValueEnum> = vec![fmt.into()];
+            printf_args.extend(args);
+            self.builder.build_call(printf, &printf_args, "");
+        }
+        Ok(())
+    }
+}
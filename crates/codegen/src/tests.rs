@@ -4,8 +4,12 @@ use lower::Lower;
 use parse::Parse;
 use tych::Tych;
 
+// Tests can pass a closure that sets up the `CliArgs` of both builds
 macro_rules! run_insta {
     ($prefix:expr, $tests:expr) => {
+        run_insta!($prefix, $tests, |_: &mut CliArgs| {})
+    };
+    ($prefix:expr, $tests:expr, $set_args:expr) => {
         insta::with_settings!({ snapshot_path => "tests/snapshots", prepend_module_to_snapshot => false }, {
             for test in $tests {
                 // Unoptimized code
@@ -14,7 +18,9 @@ macro_rules! run_insta {
                 let (ast, _, _) = Parse::new(&tokens, &mut symbol_table).parse().expect("parsing failed in `codegen` tests");
                 let typed_ast = Tych::new("main", &mut symbol_table).walk(ast).expect("type checking failed in `codegen` tests");
                 let hir = Lower::new("main", &mut symbol_table).walk(typed_ast).expect("lowering failed in `codegen` tests");
-                let args = CliArgs::new();
+                let mut args = CliArgs::new();
+                args.no_overflow_checks = true; // Keep the unoptimized IR readable
+                $set_args(&mut args);
                 let res = Codegen::run(hir, "main", symbol_table, PathBuf::new(), &args, true)
                     .expect("codegen error").to_ir_string();
 
@@ -26,6 +32,7 @@ macro_rules! run_insta {
                 let hir = Lower::new("main", &mut symbol_table).walk(typed_ast).expect("lowering failed in `codegen` tests");
                 let mut args = CliArgs::new();
                 args.opt_level = 1;
+                $set_args(&mut args);
                 let res_opt = Codegen::run(hir, "main", symbol_table, PathBuf::new(), &args, true)
                     .expect("codegen error").to_ir_string();
 
@@ -214,12 +221,232 @@ fn main() {
         2
     }
 }
+"#,
+        ],
+        [
+            "enum_value",
+            r#"
+enum Dir {
+    Left,
+    Right(int)
+}
+fn pick(left: bool) -> Dir {
+    if left {
+        Dir::Left
+    } else {
+        Dir::Right(1)
+    }
+}
+fn main() {
+    let d = pick(true)
+}
+"#,
+        ],
+        [
+            "tuple_value",
+            r#"
+fn swap(flip: bool, a: int, b: float) -> (int, float) {
+    if flip {
+        (a * 2, b * 2.0)
+    } else {
+        (a, b)
+    }
+}
+fn main() {
+    let t = swap(true, 1, 2.0)
+}
+"#,
+        ],
+        [
+            "fn_value",
+            r#"
+fn less(a: int, b: int) -> bool { a < b }
+fn more(a: int, b: int) -> bool { a > b }
+fn main() {
+    let asc = true
+    let f: fn(int, int) -> bool = if asc {
+        less
+    } else {
+        more
+    }
+    f(1, 2)
+}
+"#,
+        ],
+        [
+            "dyn_value",
+            r#"
+trait Shape {
+    fn area() -> float
+}
+struct Square {
+    let side: float
+}
+struct Circle {
+    let r: float
+}
+impl Shape for Square {
+    fn area() -> float { self.side * self.side }
+}
+impl Shape for Circle {
+    fn area() -> float { 3.14 * self.r * self.r }
+}
+fn main() {
+    let sq: Square
+    let c: Circle
+    let s: dyn Shape = if true {
+        sq as dyn Shape
+    } else {
+        c as dyn Shape
+    }
+    s.area()
+}
+"#,
+        ],
+        [
+            "dyn_from_value",
+            r#"
+trait Shape {
+    fn area() -> float
+}
+struct Square {
+    let side: float
+}
+impl Shape for Square {
+    fn area() -> float { self.side * self.side }
+}
+fn unit() -> Square {
+    Square { side: 1.0 }
+}
+fn main() {
+    let a: Square
+    let b = Square { side: 2.0 }
+    let c = true
+    let s: dyn Shape = if c { a } else { b }
+    let u = unit() as dyn Shape
+    s.area() + u.area()
+}
 "#,
         ],
     ];
     run_insta!("cond", tests);
 }
 
+#[test]
+fn test_match() {
+    let tests = [
+        [
+            "struct_value",
+            r#"
+struct Point {
+    let x: int
+    let y: int
+}
+fn main() {
+    let n = 2
+    let p = match n {
+        0 => Point { x: 0, y: 0 },
+        _ => Point { x: n, y: n }
+    }
+    p.x
+}
+"#,
+        ],
+        [
+            "tuple_value",
+            r#"
+fn main() {
+    let n = 2
+    let t = match n {
+        0 => (0, 1.0),
+        1 => (1, 2.0),
+        _ => (n, 0.5)
+    }
+}
+"#,
+        ],
+        [
+            "enum_value",
+            r#"
+enum Shape {
+    Circle(float),
+    Rect(float, float),
+    Empty
+}
+fn grow(s: Shape) -> Shape {
+    match s {
+        Shape::Circle(r) => Shape::Circle(r * 2.0),
+        Shape::Rect(w, h) => Shape::Rect(w * 2.0, h * 2.0),
+        Shape::Empty => Shape::Empty
+    }
+}
+fn main() {
+    let s = grow(Shape::Circle(1.0))
+}
+"#,
+        ],
+        [
+            "dyn_value",
+            r#"
+trait Shape {
+    fn area() -> float
+}
+struct Square {
+    let side: float
+}
+struct Circle {
+    let r: float
+}
+impl Shape for Square {
+    fn area() -> float { self.side * self.side }
+}
+impl Shape for Circle {
+    fn area() -> float { 3.14 * self.r * self.r }
+}
+fn main() {
+    let sq: Square
+    let c: Circle
+    let n = 1
+    let s: dyn Shape = match n {
+        0 => sq as dyn Shape,
+        _ => c as dyn Shape
+    }
+    s.area()
+}
+"#,
+        ],
+    ];
+    run_insta!("match", tests);
+}
+
+#[test]
+fn test_enum() {
+    let tests = [[
+        "struct_payload",
+        r#"
+struct Foo {
+    let a: int
+    let b: float
+}
+enum Maybe {
+    Some(Foo),
+    None
+}
+fn make(a: int) -> Maybe {
+    let f = Foo { a: a, b: 1.5 }
+    Maybe::Some(f)
+}
+fn main() {
+    let n = match make(3) {
+        Maybe::Some(f) => f.a,
+        Maybe::None => 0
+    }
+}
+"#,
+    ]];
+    run_insta!("enum", tests);
+}
+
 #[test]
 fn test_let() {
     let tests = [
@@ -350,9 +577,10 @@ fn main() {
 
 #[test]
 fn test_array() {
-    let tests = [[
-        "basic",
-        r#"
+    let tests = [
+        [
+            "basic",
+            r#"
 fn main() {
     let a: [int; 3] = [1, 2, 3]
     let b: [int; 3]
@@ -360,7 +588,45 @@ fn main() {
     a[1]
 }
 "#,
-    ]];
+        ],
+        [
+            "index_checked",
+            r#"
+fn get(a: [int; 3], i: uint8) -> int {
+    a[i]
+}
+fn main() {
+    let a: [int; 3] = [1, 2, 3]
+    get(a, 1)
+}
+"#,
+        ],
+        [
+            "index_int128",
+            r#"
+fn get(a: [int; 3], i: int128) -> int {
+    a[i]
+}
+fn main() {
+    let a: [int; 3] = [1, 2, 3]
+    get(a, 18446744073709551617)
+}
+"#,
+        ],
+        [
+            "index_call_result",
+            r#"
+fn make() -> [int; 3] {
+    let a: [int; 3] = [1, 2, 3]
+    a
+}
+fn main() {
+    let i = 2
+    make()[i]
+}
+"#,
+        ],
+    ];
     run_insta!("array", tests);
 }
 
@@ -459,6 +725,57 @@ fn main() {
     run_insta!("struct", tests);
 }
 
+#[test]
+fn test_method_receiver() {
+    let tests = [
+        [
+            "value",
+            r#"
+struct Point {
+    let x: int
+    fn bump() { self.x += 1 }
+    fn get() -> int { self.x }
+}
+struct Line {
+    let start: Point
+    let end: Point
+}
+fn main() {
+    let pts: [Point; 2]
+    pts[1].bump()
+    let line: Line
+    line.start.bump()
+    pts[1].get() + line.start.get()
+}
+"#,
+        ],
+        [
+            "pointer",
+            r#"
+struct Point {
+    let x: int
+    fn bump() { self.x += 1 }
+    fn get() -> int { self.x }
+}
+struct Line {
+    let start: *Point
+    let end: *Point
+}
+fn shift(p: *Point, line: Line) -> int {
+    p.bump()
+    line.end.bump()
+    p.get() + line.start.get()
+}
+fn main() {
+    let line: Line
+    shift(line.end, line)
+}
+"#,
+        ],
+    ];
+    run_insta!("method_receiver", tests);
+}
+
 #[test]
 fn test_loop() {
     let tests = [
@@ -638,3 +955,254 @@ fn main() {
     ];
     run_insta!("loop", tests);
 }
+
+#[test]
+fn test_struct_abi() {
+    let tests = [
+        [
+            "small",
+            r#"
+struct Pair {
+    let a: int
+    let b: int
+}
+extern fn swap_pair(p: Pair) -> Pair
+fn first(p: Pair) -> int {
+    p.a
+}
+fn main() {
+    let p = Pair { a: 1, b: 2 }
+    first(swap_pair(p))
+}
+"#,
+        ],
+        [
+            "medium",
+            r#"
+struct Mixed {
+    let x: double
+    let y: int
+}
+extern fn scale_mixed(m: Mixed, by: double) -> Mixed
+fn make(y: int) -> Mixed {
+    Mixed { x: 1.5, y: y }
+}
+fn main() {
+    let m = scale_mixed(make(2), 2.0)
+    m.y
+}
+"#,
+        ],
+        [
+            "large",
+            r#"
+struct Big {
+    let a: int64
+    let b: int64
+    let c: int64
+}
+extern fn grow_big(b: Big) -> Big
+fn sum(b: Big) -> int64 {
+    b.a + b.b + b.c
+}
+fn main() {
+    let b = Big { a: 1, b: 2, c: 3 }
+    sum(grow_big(b))
+}
+"#,
+        ],
+        [
+            "tuple_enum",
+            r#"
+enum Shape {
+    Circle(float),
+    Rect(float, float)
+}
+extern fn flip(t: (int, float)) -> (float, int)
+extern fn grow(s: Shape) -> Shape
+fn main() {
+    let t = flip((1, 2.0))
+    let s = grow(Shape::Circle(1.0))
+}
+"#,
+        ],
+        [
+            "registers",
+            r#"
+struct Pair {
+    let a: int
+    let b: int
+}
+struct Vec2 {
+    let x: double
+    let y: double
+}
+struct Big {
+    let a: int64
+    let b: int64
+    let c: int64
+}
+extern fn sum_pairs(a: Pair, b: Pair, c: Pair, d: Pair, e: Pair, f: Pair, g: Pair) -> int
+extern fn sum_vecs(a: Vec2, b: Vec2, c: Vec2, d: Vec2, e: Vec2, f: Pair) -> double
+extern fn pairs_to_big(a: Pair, b: Pair, c: Pair, d: Pair, e: Pair, f: Pair) -> Big
+fn main() {
+    let p = Pair { a: 1, b: 2 }
+    let v = Vec2 { x: 1.0, y: 2.0 }
+    sum_pairs(p, p, p, p, p, p, p)
+    sum_vecs(v, v, v, v, v, p)
+    pairs_to_big(p, p, p, p, p, p)
+}
+"#,
+        ],
+    ];
+    run_insta!("struct_abi", tests);
+}
+
+#[test]
+fn test_debug() {
+    let tests = [
+        [
+            "basic",
+            r#"
+struct Point {
+    let x: int
+    let y: float
+}
+fn main() {
+    let p: Point
+    p.x = 1
+    let a: [bool; 2] = [true, false]
+    debug(p)
+    debug(a)
+    debug((p, 'c'))
+}
+"#,
+        ],
+        [
+            "eq",
+            r#"
+struct Point {
+    let x: int
+    let y: [int; 2]
+}
+fn main() {
+    let p: Point
+    let q: Point
+    q.y[1] = 2
+    debug(p == q)
+    debug(p.y != q.y)
+}
+"#,
+        ],
+        [
+            "int128",
+            r#"
+fn main() {
+    let a: int128 = -170141183460469231731687303715884105728
+    let b: uint128 = 340282366920938463463374607431768211455
+    debug(a)
+    debug(b)
+}
+"#,
+        ],
+    ];
+    run_insta!("debug", tests);
+}
+
+#[test]
+fn test_panic() {
+    let tests = [[
+        "basic",
+        r#"
+fn main() {
+    panic("something broke")
+}
+"#,
+    ]];
+    run_insta!("panic", tests);
+    run_insta!("panic_exit", tests, |args: &mut CliArgs| args.panic = PanicStrategy::Exit);
+}
+
+#[test]
+fn test_overflow() {
+    let tests = [
+        [
+            "arith",
+            r#"
+fn arith(a: int, b: int, c: uint8, d: uint8) -> int {
+    let x = a + b
+    let y = c * d - c
+    x - a * b
+}
+fn main() {
+    arith(1, 2, 3, 4)
+}
+"#,
+        ],
+        [
+            "div",
+            r#"
+fn div(a: int, b: int, c: uint, d: uint) -> int {
+    let r = c / d
+    a / b
+}
+fn main() {
+    div(6, 3, 6, 3)
+}
+"#,
+        ],
+    ];
+    run_insta!("overflow", tests);
+    run_insta!("overflow_checked", tests, |args: &mut CliArgs| {
+        args.no_overflow_checks = false;
+        args.overflow_checks = true;
+    });
+}
+
+#[test]
+fn test_layout() {
+    let tests = [
+        [
+            "repr_c",
+            r#"
+#[repr(C)]
+struct Mixed {
+    let a: int8
+    let b: int64
+    let c: int16
+}
+fn main() {
+    let m: Mixed
+}
+"#,
+        ],
+        [
+            "packed",
+            r#"
+#[repr(packed)]
+struct Packed {
+    let a: int8
+    let b: int64
+    let c: int16
+}
+fn main() {
+    let p: Packed
+}
+"#,
+        ],
+        [
+            "align",
+            r#"
+#[align(16)]
+struct Aligned {
+    let a: int
+    let b: int8
+}
+fn main() {
+    let a: Aligned
+}
+"#,
+        ],
+    ];
+    run_insta!("layout", tests, |args: &mut CliArgs| args.show_layout = true);
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+// An attribute on a declaration, like `#[inline]` or `#[export_name("foo")]`. Args are kept
+// as strings. The parser checks them against the attribute they're for
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize, Hash)]
+pub struct Attribute {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl Attribute {
+    pub fn new(name: &str, args: Vec<String>) -> Self {
+        Attribute { name: name.to_owned(), args }
+    }
+
+    // The first arg, for attributes that take at most one
+    pub fn arg(&self) -> Option<&str> {
+        self.args.first().map(|arg| arg.as_str())
+    }
+}
+
+impl Display for Attribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.args.is_empty() {
+            write!(f, "#[{}]", self.name)
+        } else {
+            write!(f, "#[{}({})]", self.name, self.args.join(", "))
+        }
+    }
+}
@@ -23,6 +23,10 @@ pub struct CliArgs {
     #[clap(long, parse(from_flag))]
     pub show_ir: bool,
 
+    /// Display the size, alignment, and field offsets of each struct
+    #[clap(long, parse(from_flag))]
+    pub show_layout: bool,
+
     /// Run jit rather than outputting a binary
     #[clap(long, parse(from_flag))]
     pub run_jit: bool,
@@ -35,6 +39,18 @@ pub struct CliArgs {
     #[clap(short = 'O', long, value_name="level", default_value_t = 1, parse(try_from_str=valid_opt_level))]
     pub opt_level: usize,
 
+    /// Trap on integer overflow and division by zero (default at -O0)
+    #[clap(long, parse(from_flag))]
+    pub overflow_checks: bool,
+
+    /// Don't check for integer overflow or division by zero
+    #[clap(long, parse(from_flag), conflicts_with = "overflow-checks")]
+    pub no_overflow_checks: bool,
+
+    /// What a panic does after printing its message: `abort` or `exit` (with status 101)
+    #[clap(long, value_name = "strategy", default_value = "abort", parse(try_from_str = valid_panic_strategy))]
+    pub panic: PanicStrategy,
+
     /// Disable LLVM function validation (useful for debugging)
     #[clap(short, long, parse(from_flag))]
     pub no_verify: bool,
@@ -61,15 +77,35 @@ impl CliArgs {
             show_typed_ast: false,
             show_hir: false,
             show_ir: false,
+            show_layout: false,
             run_jit: false,
             output: None,
             opt_level: 0,
+            overflow_checks: false,
+            no_overflow_checks: false,
+            panic: PanicStrategy::Abort,
             no_verify: false,
             compile_only: false,
             build_dir: None,
             files: vec![],
         }
     }
+
+    // Overflow checks are on for unoptimized builds unless they're turned off
+    pub fn checks_overflow(&self) -> bool {
+        !self.no_overflow_checks && (self.overflow_checks || self.opt_level == 0)
+    }
+
+    // `debug_assert` is only checked in unoptimized builds
+    pub fn debug_asserts(&self) -> bool {
+        self.opt_level == 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicStrategy {
+    Abort,
+    Exit,
 }
 
 impl Default for CliArgs {
@@ -87,3 +123,36 @@ fn valid_opt_level(s: &str) -> Result<usize, String> {
         Err("Must be one of: 0 (none), 1 (basic)".to_string())
     }
 }
+
+fn valid_panic_strategy(s: &str) -> Result<PanicStrategy, String> {
+    match s {
+        "abort" => Ok(PanicStrategy::Abort),
+        "exit" => Ok(PanicStrategy::Exit),
+        _ => Err("Must be one of: abort, exit".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CliArgs;
+
+    #[test]
+    fn test_checks_overflow() {
+        // (opt level, --overflow-checks, --no-overflow-checks, expected)
+        let tests = [
+            (0, false, false, true),
+            (1, false, false, false),
+            (1, true, false, true),
+            (0, false, true, false),
+            (1, false, true, false),
+        ];
+
+        for (opt_level, overflow_checks, no_overflow_checks, expected) in tests {
+            let mut args = CliArgs::new();
+            args.opt_level = opt_level;
+            args.overflow_checks = overflow_checks;
+            args.no_overflow_checks = no_overflow_checks;
+            assert_eq!(args.checks_overflow(), expected);
+        }
+    }
+}
@@ -0,0 +1,62 @@
+// Split a `print` format string around its `{}` placeholders. `{{` and `}}` are literal
+// braces. There's always one more piece of text than there are placeholders, so the
+// pieces can be interleaved with the args
+pub fn split_format(fmt: &str) -> Result<Vec<String>, String> {
+    let mut pieces = vec![String::new()];
+    let mut chars = fmt.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                push_char(&mut pieces, c);
+            },
+            ('{', Some('}')) => {
+                chars.next();
+                pieces.push(String::new());
+            },
+            ('{', _) => {
+                let mut spec = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => spec.push(c),
+                        None => return Err(String::from("Unmatched `{`")),
+                    }
+                }
+                return Err(format!("Unsupported placeholder `{{{}}}`. Only `{{}}` is supported", spec));
+            },
+            ('}', _) => return Err(String::from("Unmatched `}`")),
+            (c, _) => push_char(&mut pieces, c),
+        }
+    }
+    Ok(pieces)
+}
+
+fn push_char(pieces: &mut [String], c: char) {
+    pieces.last_mut().unwrap_or_else(|| unreachable!("format string without pieces")).push(c);
+}
+
+#[cfg(test)]
+mod test {
+    use super::split_format;
+
+    #[test]
+    fn test_split_format() {
+        let tests = [
+            ("", Ok(vec![""])),
+            ("hello", Ok(vec!["hello"])),
+            ("{}", Ok(vec!["", ""])),
+            ("x = {}, y = {}\n", Ok(vec!["x = ", ", y = ", "\n"])),
+            ("{{}} {}", Ok(vec!["{} ", ""])),
+            ("{", Err("Unmatched `{`")),
+            ("}", Err("Unmatched `}`")),
+            ("{x}", Err("Unsupported placeholder `{x}`. Only `{}` is supported")),
+        ];
+
+        for (fmt, expected) in tests {
+            let expected =
+                expected.map(|pieces| pieces.into_iter().map(String::from).collect()).map_err(String::from);
+            assert_eq!(split_format(fmt), expected);
+        }
+    }
+}
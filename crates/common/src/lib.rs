@@ -1,13 +1,21 @@
 use serde::{Deserialize, Serialize};
 
-pub use cli_args::CliArgs;
+pub use attribute::Attribute;
+pub use cli_args::{CliArgs, PanicStrategy};
+pub use format::split_format;
 pub use literal::Literal;
+pub use loc::Loc;
+pub use pattern::{MatchArm, Pattern};
 pub use prototype::Prototype;
 pub use symbol_table::{Symbol, SymbolTable};
 
+pub mod attribute;
 mod cli_args;
+pub mod format;
 pub mod literal;
+pub mod loc;
 mod macros;
+pub mod pattern;
 pub mod prototype;
 pub mod symbol_table;
 
@@ -18,6 +26,7 @@ pub enum Operator {
     Add,
     AddEq,
     And,
+    As,
     Assign,
     BitAnd,
     BitOr,
@@ -51,6 +60,7 @@ impl std::fmt::Display for Operator {
             AddEq => "+=",
             Assign => "=",
             And => "&&",
+            As => "as",
             BitAnd => "&",
             BitOr => "|",
             BitXor => "^",
@@ -83,10 +93,14 @@ pub enum Type {
     Int16,
     Int32,
     Int64,
+    Int128,
+    ISize,
     UInt8,
     UInt16,
     UInt32,
     UInt64,
+    UInt128,
+    USize,
     Float,
     Double,
     Bool,
@@ -96,11 +110,24 @@ pub enum Type {
     SArray(Box<Type>, usize),
     Comp(String),
     Ptr(Box<Type>),
+    Dyn(String),
+    Enum(String),
+    Tuple(Vec<Type>),
+    Fn(Vec<Type>, Box<Type>),
 }
 
 impl Type {
     pub fn is_primitive(&self) -> bool {
-        !matches!(self, Type::Comp(_) | Type::SArray(_, _) | Type::Ptr(_))
+        !matches!(
+            self,
+            Type::Comp(_)
+                | Type::SArray(_, _)
+                | Type::Ptr(_)
+                | Type::Dyn(_)
+                | Type::Enum(_)
+                | Type::Tuple(_)
+                | Type::Fn(..)
+        )
     }
 
     pub fn get_ptr_ty(&self) -> &Type {
@@ -124,9 +151,9 @@ impl From<&str> for Type {
 
         if ty.starts_with("sarray") {
             let start = ty.find('(').unwrap_or_else(|| unreachable!("malformed array annotation"));
-            let end = ty.find(')').unwrap_or_else(|| unreachable!("malformed array annotation"));
+            let end = ty.rfind(')').unwrap_or_else(|| unreachable!("malformed array annotation"));
             let (inner, sz) = ty[start + 1..end]
-                .split_once(", ")
+                .rsplit_once(", ")
                 .unwrap_or_else(|| unreachable!("malformed array annotation"));
             return SArray(
                 Box::new(Type::from(inner)),
@@ -134,15 +161,51 @@ impl From<&str> for Type {
             );
         }
 
+        // Tuples are a list of types in parens
+        if let Some(inner) = ty.strip_prefix('(').and_then(|ty| ty.strip_suffix(')')) {
+            return Tuple(split_types(inner));
+        }
+
+        // The params end at the `)` matching the one after `fn`. Anything after is the
+        // return type
+        if let Some(rest) = ty.strip_prefix("fn(") {
+            let (mut depth, mut end) = (0, None);
+            for (i, c) in rest.char_indices() {
+                match c {
+                    '(' => depth += 1,
+                    ')' if depth == 0 => {
+                        end = Some(i);
+                        break;
+                    },
+                    ')' => depth -= 1,
+                    _ => (),
+                }
+            }
+            let end = end.unwrap_or_else(|| unreachable!("malformed function type annotation"));
+            let params = match &rest[..end] {
+                "" => vec![],
+                params => split_types(params),
+            };
+            let ret_ty = match rest[end + 1..].strip_prefix(" -> ") {
+                Some(ret_ty) => Type::from(ret_ty),
+                None => Void,
+            };
+            return Fn(params, Box::new(ret_ty));
+        }
+
         match ty {
             "int8" => Int8,
             "int16" => Int16,
             "int32" => Int32,
             "int64" => Int64,
+            "int128" => Int128,
+            "isize" => ISize,
             "uint8" => UInt8,
             "uint16" => UInt16,
             "uint32" => UInt32,
             "uint64" => UInt64,
+            "uint128" => UInt128,
+            "usize" => USize,
             "float" => Float,
             "double" => Double,
             "bool" => Bool,
@@ -150,14 +213,35 @@ impl From<&str> for Type {
             "void" => Void,
             "int" => Int32,
             "uint" => UInt32,
-            comp => match comp.strip_prefix('*') {
-                Some(comp) => pointer_wrap!(Comp(comp.to_owned())),
-                None => Comp(comp.to_owned()),
+            comp => match (comp.strip_prefix('*'), comp.strip_prefix("dyn "), comp.strip_prefix("enum ")) {
+                (Some(inner), ..) => pointer_wrap!(Type::from(inner)),
+                (_, Some(trait_name), _) => Dyn(trait_name.to_owned()),
+                (.., Some(enum_name)) => Enum(enum_name.to_owned()),
+                _ => Comp(comp.to_owned()),
             },
         }
     }
 }
 
+// Split a list of types on the commas that aren't nested in another type
+fn split_types(list: &str) -> Vec<Type> {
+    let mut tys = vec![];
+    let (mut depth, mut start) = (0, 0);
+    for (i, c) in list.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                tys.push(Type::from(list[start..i].trim()));
+                start = i + 1;
+            },
+            _ => (),
+        }
+    }
+    tys.push(Type::from(list[start..].trim()));
+    tys
+}
+
 impl Default for Type {
     fn default() -> Self {
         Self::Void
@@ -175,6 +259,19 @@ impl std::fmt::Display for Type {
         let s = match self {
             Type::Comp(ty) => ty.to_owned(),
             Type::Ptr(boxed) => format!("*{}", *boxed),
+            Type::Dyn(trait_name) => format!("dyn {}", trait_name),
+            Type::Enum(enum_name) => format!("enum {}", enum_name),
+            Type::SArray(ty, size) => format!("sarray({}, {})", ty, size),
+            Type::Tuple(tys) => {
+                format!("({})", tys.iter().map(|ty| ty.to_string()).collect::<Vec<_>>().join(", "))
+            },
+            Type::Fn(params, ret_ty) => {
+                let params = params.iter().map(|ty| ty.to_string()).collect::<Vec<_>>().join(", ");
+                match ret_ty.as_ref() {
+                    Type::Void => format!("fn({})", params),
+                    ret_ty => format!("fn({}) -> {}", params, ret_ty),
+                }
+            },
             _ => format!("{:?}", self).to_ascii_lowercase(),
         };
         write!(f, "{}", s)
@@ -190,5 +287,38 @@ mod test {
         assert_eq!(Type::from("int32"), Type::Int32);
         assert_eq!(Type::from("Int32"), Type::Comp(String::from("Int32")));
         assert_eq!(Type::from("sarray(int, 3)"), Type::SArray(Box::new(Type::Int32), 3));
+        assert_eq!(Type::from("dyn main::Shape"), Type::Dyn(String::from("main::Shape")));
+        assert_eq!(
+            Type::from("sarray(dyn main::Shape, 2)"),
+            Type::SArray(Box::new(Type::Dyn(String::from("main::Shape"))), 2)
+        );
+        assert_eq!(Type::from("enum main::Color"), Type::Enum(String::from("main::Color")));
+        assert_eq!(
+            Type::from("(int, (bool, main::Foo), sarray(char, 2))"),
+            Type::Tuple(vec![
+                Type::Int32,
+                Type::Tuple(vec![Type::Bool, Type::Comp(String::from("main::Foo"))]),
+                Type::SArray(Box::new(Type::Char), 2)
+            ])
+        );
+        assert_eq!(Type::from("(uint64, *Foo)").to_string(), "(uint64, *Foo)");
+        assert_eq!(
+            Type::from("sarray((int, bool), 2)"),
+            Type::SArray(Box::new(Type::Tuple(vec![Type::Int32, Type::Bool])), 2)
+        );
+        assert_eq!(Type::from("*int64"), Type::Ptr(Box::new(Type::Int64)));
+        assert_eq!(Type::from("uint128"), Type::UInt128);
+        assert_eq!(Type::from("*isize"), Type::Ptr(Box::new(Type::ISize)));
+        assert_eq!(Type::USize.to_string(), "usize");
+        assert_eq!(Type::from("*main::Foo"), Type::Ptr(Box::new(Type::Comp(String::from("main::Foo")))));
+        assert_eq!(Type::from("fn()"), Type::Fn(vec![], Box::new(Type::Void)));
+        assert_eq!(
+            Type::from("fn(int32, (bool, char)) -> bool"),
+            Type::Fn(vec![Type::Int32, Type::Tuple(vec![Type::Bool, Type::Char])], Box::new(Type::Bool))
+        );
+        assert_eq!(
+            Type::from("fn(fn(int32) -> int32, *main::Foo) -> fn(int32)").to_string(),
+            "fn(fn(int32) -> int32, *main::Foo) -> fn(int32)"
+        );
     }
 }
@@ -1,18 +1,24 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use std::hash::{Hash, Hasher};
 
 use crate::Type;
 
-#[derive(Debug, PartialEq, Clone, Serialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Literal<T> {
     Int8(i8),
     Int16(i16),
     Int32(i32),
     Int64(i64),
+    Int128(i128),
+    // Pointer-sized literals hold the widest supported pointer
+    ISize(i64),
     UInt8(u8),
     UInt16(u16),
     UInt32(u32),
     UInt64(u64),
+    UInt128(u128),
+    USize(u64),
     Float(f32),
     Double(f64),
     Bool(bool),
@@ -20,6 +26,72 @@ pub enum Literal<T> {
     Str(String),
     Array { elements: Vec<T>, inner_ty: Option<Type> },
     Comp(Vec<T>),
+    Tuple(Vec<T>),
+}
+
+impl<T> Literal<T> {
+    // Copies a scalar literal into a literal of another node type. Literals that hold
+    // other nodes can't be converted and return `None`
+    pub fn to_scalar<U>(&self) -> Option<Literal<U>> {
+        use Literal::*;
+
+        Some(match self {
+            Int8(v) => Int8(*v),
+            Int16(v) => Int16(*v),
+            Int32(v) => Int32(*v),
+            Int64(v) => Int64(*v),
+            Int128(v) => Int128(*v),
+            ISize(v) => ISize(*v),
+            UInt8(v) => UInt8(*v),
+            UInt16(v) => UInt16(*v),
+            UInt32(v) => UInt32(*v),
+            UInt64(v) => UInt64(*v),
+            UInt128(v) => UInt128(*v),
+            USize(v) => USize(*v),
+            Float(v) => Float(*v),
+            Double(v) => Double(*v),
+            Bool(v) => Bool(*v),
+            Char(v) => Char(*v),
+            Str(v) => Str(v.clone()),
+            Array { .. } | Comp(_) | Tuple(_) => return None,
+        })
+    }
+}
+
+// Literals are stored in symbols, which need to be hashable. Floats are compared and
+// hashed by their bits
+impl<T: Eq> Eq for Literal<T> {}
+
+impl<T: Hash> Hash for Literal<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        use Literal::*;
+
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Int8(v) => v.hash(state),
+            Int16(v) => v.hash(state),
+            Int32(v) => v.hash(state),
+            Int64(v) => v.hash(state),
+            Int128(v) => v.hash(state),
+            ISize(v) => v.hash(state),
+            UInt8(v) => v.hash(state),
+            UInt16(v) => v.hash(state),
+            UInt32(v) => v.hash(state),
+            UInt64(v) => v.hash(state),
+            UInt128(v) => v.hash(state),
+            USize(v) => v.hash(state),
+            Float(v) => v.to_bits().hash(state),
+            Double(v) => v.to_bits().hash(state),
+            Bool(v) => v.hash(state),
+            Char(v) => v.hash(state),
+            Str(v) => v.hash(state),
+            Array { elements, inner_ty } => {
+                elements.hash(state);
+                inner_ty.hash(state);
+            },
+            Comp(v) | Tuple(v) => v.hash(state),
+        }
+    }
 }
 
 impl<T: Display> Display for Literal<T> {
@@ -31,10 +103,14 @@ impl<T: Display> Display for Literal<T> {
             Int16(v) => write!(f, "{}", v),
             Int32(v) => write!(f, "{}", v),
             Int64(v) => write!(f, "{}", v),
+            Int128(v) => write!(f, "{}", v),
+            ISize(v) => write!(f, "{}", v),
             UInt8(v) => write!(f, "{}", v),
             UInt16(v) => write!(f, "{}", v),
             UInt32(v) => write!(f, "{}", v),
             UInt64(v) => write!(f, "{}", v),
+            UInt128(v) => write!(f, "{}", v),
+            USize(v) => write!(f, "{}", v),
             Float(v) => write!(f, "{}", v),
             Double(v) => write!(f, "{}", v),
             Bool(v) => write!(f, "{}", v),
@@ -57,6 +133,53 @@ impl<T: Display> Display for Literal<T> {
                 });
                 write!(f, "{})", s.strip_suffix(' ').unwrap_or("'()"))
             },
+            Tuple(elements) => {
+                write!(f, "({})", elements.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", "))
+            },
+        }
+    }
+}
+
+// String literals keep their control sequences as written until they're lowered. Replace
+// them with the characters they stand for
+pub fn unescape(s: &str) -> Result<String, String> {
+    let mut unescaped = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some('0') => unescaped.push('\0'),
+            Some(c @ ('\\' | '"' | '\'')) => unescaped.push(c),
+            Some(c) => return Err(format!("Invalid string control sequence: `\\{}`", c)),
+            None => return Err(String::from("Unterminated string control sequence: `\\`")),
+        }
+    }
+    Ok(unescaped)
+}
+
+#[cfg(test)]
+mod test {
+    use super::unescape;
+
+    #[test]
+    fn test_unescape() {
+        let tests = [
+            ("bar", Ok("bar")),
+            (r"bar\n", Ok("bar\n")),
+            (r"a\tb\r\0", Ok("a\tb\r\0")),
+            (r#"\"quoted\" \\ \'"#, Ok("\"quoted\" \\ '")),
+            (r"\c", Err(r"Invalid string control sequence: `\c`")),
+            (r"bar\", Err(r"Unterminated string control sequence: `\`")),
+        ];
+
+        for (s, expected) in tests {
+            assert_eq!(unescape(s), expected.map(String::from).map_err(String::from));
         }
     }
 }
@@ -0,0 +1,23 @@
+use serde::Serialize;
+use std::fmt::Display;
+
+// A position in a source file. Nodes that can fail at runtime keep the position they
+// were parsed from so the failure can be reported
+#[derive(Debug, Default, PartialEq, Eq, Clone, Serialize)]
+pub struct Loc {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Loc {
+    pub fn new(file: &str, line: usize, column: usize) -> Self {
+        Loc { file: file.to_owned(), line, column }
+    }
+}
+
+impl Display for Loc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
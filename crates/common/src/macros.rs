@@ -40,17 +40,32 @@ macro_rules! int64_types {
     };
 }
 
+#[macro_export]
+macro_rules! int128_types {
+    () => {
+        Type::Int128 | Type::UInt128
+    };
+}
+
+// Pointer-sized integers. Their width comes from the target's data layout
+#[macro_export]
+macro_rules! size_types {
+    () => {
+        Type::ISize | Type::USize
+    };
+}
+
 #[macro_export]
 macro_rules! signed_int_types {
     () => {
-        Type::Int8 | Type::Int16 | Type::Int32 | Type::Int64
+        Type::Int8 | Type::Int16 | Type::Int32 | Type::Int64 | Type::Int128 | Type::ISize
     };
 }
 
 #[macro_export]
 macro_rules! unsigned_int_types {
     () => {
-        Type::UInt8 | Type::UInt16 | Type::UInt32 | Type::UInt64
+        Type::UInt8 | Type::UInt16 | Type::UInt32 | Type::UInt64 | Type::UInt128 | Type::USize
     };
 }
 
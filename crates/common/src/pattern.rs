@@ -0,0 +1,66 @@
+use serde::Serialize;
+use std::fmt::Display;
+
+// One arm of a `match`. Generic over the node type so the AST and HIR can share it
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct MatchArm<T> {
+    pub pattern: Pattern<T>,
+    pub guard: Option<T>,
+    pub body: T,
+}
+
+impl<T> MatchArm<T> {
+    pub fn new(pattern: Pattern<T>, guard: Option<T>, body: T) -> Self {
+        MatchArm { pattern, guard, body }
+    }
+}
+
+impl<T: Display> Display for MatchArm<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.guard {
+            Some(guard) => write!(f, "({} if {} {})", self.pattern, guard, self.body),
+            None => write!(f, "({} {})", self.pattern, self.body),
+        }
+    }
+}
+
+// Literals and range bounds are literal nodes so they get typed like any other literal.
+// Variant fields can only bind or ignore their value.
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum Pattern<T> {
+    Wildcard,
+    Binding(String),
+    Lit(T),
+    Range { start: T, end: T, inclusive: bool },
+    Variant { enum_name: String, variant: String, fields: Vec<Pattern<T>> },
+}
+
+impl<T> Pattern<T> {
+    // Matches every value of the scrutinee's type
+    pub fn is_catch_all(&self) -> bool {
+        matches!(self, Pattern::Wildcard | Pattern::Binding(_))
+    }
+}
+
+impl<T: Display> Display for Pattern<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use Pattern::*;
+
+        match self {
+            Wildcard => write!(f, "_"),
+            Binding(name) => write!(f, "{}", name),
+            Lit(value) => write!(f, "{}", value),
+            Range { start, end, inclusive } => {
+                write!(f, "{}{}{}", start, if *inclusive { "..=" } else { ".." }, end)
+            },
+            Variant { enum_name, variant, fields } => {
+                write!(f, "{}::{}", enum_name, variant)?;
+                if !fields.is_empty() {
+                    let fields = fields.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ");
+                    write!(f, "({})", fields)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
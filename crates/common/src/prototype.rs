@@ -1,7 +1,7 @@
 use serde::Serialize;
 use std::fmt::Display;
 
-use crate::{Symbol, Type};
+use crate::{Attribute, Symbol, Type};
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct Prototype {
@@ -11,6 +11,12 @@ pub struct Prototype {
     is_extern: bool,
     module: String,
     member_of: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    is_variadic: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    generics: Vec<(String, Vec<String>)>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    attrs: Vec<Attribute>,
 }
 
 impl Prototype {
@@ -24,7 +30,17 @@ impl Prototype {
         } else {
             format!("{}::{}", module, name)
         };
-        Prototype { name, params: args, ret_ty, is_extern, module, member_of }
+        Prototype {
+            name,
+            params: args,
+            ret_ty,
+            is_extern,
+            module,
+            member_of,
+            is_variadic: false,
+            generics: vec![],
+            attrs: vec![],
+        }
     }
 
     pub fn name(&self) -> &str {
@@ -54,6 +70,49 @@ impl Prototype {
     pub fn is_extern(&self) -> bool {
         self.is_extern
     }
+
+    // Externs like `printf(fmt: *char, ...)` take any number of args after their params
+    pub fn is_variadic(&self) -> bool {
+        self.is_variadic
+    }
+
+    pub fn set_variadic(&mut self, variadic: bool) {
+        self.is_variadic = variadic;
+    }
+
+    pub fn member_of(&self) -> Option<&str> {
+        self.member_of.as_deref()
+    }
+
+    pub fn set_member_of(&mut self, member_of: Option<String>) {
+        self.member_of = member_of;
+    }
+
+    // Generic parameters and their trait bounds, e.g., `<T: Len + Show>`
+    pub fn generics(&self) -> &[(String, Vec<String>)] {
+        &self.generics
+    }
+
+    pub fn set_generics(&mut self, generics: Vec<(String, Vec<String>)>) {
+        self.generics = generics;
+    }
+
+    pub fn is_generic(&self) -> bool {
+        !self.generics.is_empty()
+    }
+
+    // Attributes from the function's declaration, e.g., `#[inline]`
+    pub fn attrs(&self) -> &[Attribute] {
+        &self.attrs
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&Attribute> {
+        self.attrs.iter().find(|attr| attr.name == name)
+    }
+
+    pub fn set_attrs(&mut self, attrs: Vec<Attribute>) {
+        self.attrs = attrs;
+    }
 }
 
 impl From<&Prototype> for Symbol {
@@ -78,7 +137,7 @@ impl From<&Prototype> for Symbol {
             }
         };
 
-        Symbol::new_fn(
+        let mut sym = Symbol::new_fn(
             (&cooked_name, &proto.name),
             args,
             &proto.ret_ty,
@@ -86,7 +145,10 @@ impl From<&Prototype> for Symbol {
             &proto.module,
             true,
             proto.member_of.as_deref(),
-        )
+        );
+        sym.set_variadic(proto.is_variadic);
+        sym.set_attrs(&proto.attrs);
+        sym
     }
 }
 
@@ -106,18 +168,39 @@ impl From<Symbol> for Prototype {
             is_extern: sym.is_extern(),
             module: module.to_owned(),
             member_of: sym.member_of().map(|x| x.to_owned()),
+            is_variadic: sym.is_variadic(),
+            generics: vec![],
+            attrs: sym.attrs().to_vec(),
         }
     }
 }
 
 impl Display for Prototype {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut s = format!("({}", self.name);
+        let mut s = String::from("(");
+        for attr in &self.attrs {
+            s += &format!("{} ", attr);
+        }
+        s += &self.name;
+        if !self.generics.is_empty() {
+            let generics = self
+                .generics
+                .iter()
+                .map(|(name, bounds)| match bounds.is_empty() {
+                    true => name.to_owned(),
+                    false => format!("{}: {}", name, bounds.join(" + ")),
+                })
+                .collect::<Vec<_>>();
+            s += &format!("<{}>", generics.join(", "));
+        }
         if !self.params.is_empty() {
             for arg in &self.params {
                 s += &format!(" {}:{}", arg.0, arg.1);
             }
         }
+        if self.is_variadic {
+            s += " ...";
+        }
         write!(f, "{})", s)
     }
 }
@@ -1,6 +1,6 @@
 use std::{collections::HashMap, fmt::Display};
 
-pub use symbol::{AssocData, FnData, StructData, Symbol, VarData};
+pub use symbol::{AssocData, EnumData, FnData, StructData, StructRepr, Symbol, TraitData, VarData};
 
 pub mod symbol;
 
@@ -51,6 +51,15 @@ where
             .insert(name.to_owned(), sym)
     }
 
+    // For symbols created while deeper in scope that need to outlive it, like the types
+    // and functions made for closures
+    pub fn insert_global(&mut self, name: &str, sym: T) -> Option<T> {
+        self.tables
+            .get_mut(&0)
+            .unwrap_or_else(|| unreachable!("No global scope in `insert_global()`"))
+            .insert(name.to_owned(), sym)
+    }
+
     pub fn get(&self, name: &str) -> Option<&T> {
         let mut sym = None;
         for depth in (0..=self.scope_depth).rev() {
@@ -72,6 +81,16 @@ where
         sym
     }
 
+    // The depth of the innermost scope where `name` is found
+    pub fn depth_of(&self, name: &str) -> Option<u32> {
+        (0..=self.scope_depth).rev().find(|depth| {
+            self.tables
+                .get(depth)
+                .unwrap_or_else(|| unreachable!("no table found at scope depth `{}`", depth))
+                .contains_key(name)
+        })
+    }
+
     // Wrapper around get(). Try to first find the simple name (as for externs), then the
     // fully qualified name
     pub fn resolve_symbol(&self, name: &str, module_name: &str) -> Option<&T> {
@@ -238,6 +257,32 @@ mod test {
         assert_eq!(st.get("foo"), Some(&sym1));
     }
 
+    #[test]
+    fn test_symbol_table_global() {
+        let mut st = SymbolTable::<Symbol>::new();
+
+        let sym1 = Symbol::new_var("foo", &Type::Bool, MOD_NAME);
+        st.insert(sym1.clone());
+        assert_eq!(st.depth_of("foo"), Some(0));
+
+        // Shadow in a nested scope and insert a global from there
+        st.enter_scope();
+        st.enter_scope();
+        let sym2 = Symbol::new_var("foo", &Type::Int32, MOD_NAME);
+        st.insert(sym2.clone());
+        assert_eq!(st.depth_of("foo"), Some(2));
+        let sym3 = Symbol::new_var("bar", &Type::Int32, MOD_NAME);
+        assert_eq!(st.insert_global("bar", sym3.clone()), None);
+        assert_eq!(st.depth_of("bar"), Some(0));
+        assert_eq!(st.depth_of("baz"), None);
+
+        // The global outlives the scope it was inserted from
+        st.leave_scope();
+        st.leave_scope();
+        assert_eq!(st.get("foo"), Some(&sym1));
+        assert_eq!(st.get("bar"), Some(&sym3));
+    }
+
     #[test]
     fn test_uniq_ident() {
         let mut st = SymbolTable::<Symbol>::new();
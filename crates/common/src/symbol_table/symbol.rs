@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
 use super::Symbolic;
-use crate::Type;
+use crate::{Attribute, Literal, Type};
 
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Hash)]
 pub struct FnData {
@@ -11,17 +11,64 @@ pub struct FnData {
     ret_ty: Type,
     is_extern: bool,
     member_of: Option<String>,
+    is_variadic: bool,
+    attrs: Vec<Attribute>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Hash)]
 pub struct VarData {
     pub ty: Type,
+    pub attrs: Vec<Attribute>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Hash)]
 pub struct StructData {
     pub fields: Option<Vec<(String, String)>>,
     pub methods: Option<Vec<String>>,
+    pub impls: Vec<String>,
+    // Constant initial values for fields declared with one
+    pub defaults: Vec<(String, Literal<()>)>,
+    pub attrs: Vec<Attribute>,
+}
+
+// How a struct's fields are laid out, from its `repr` and `align` attributes. Fields are
+// never reordered, so the default layout is C's. `#[repr(C)]` only records that the struct
+// is shared with C
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize, Hash)]
+pub struct StructRepr {
+    pub c: bool,
+    // No padding between fields
+    pub packed: bool,
+    // The minimum alignment in bytes, from `#[align(N)]`
+    pub align: Option<u32>,
+}
+
+impl From<&[Attribute]> for StructRepr {
+    fn from(attrs: &[Attribute]) -> Self {
+        let mut repr = StructRepr::default();
+        for attr in attrs {
+            match attr.name.as_str() {
+                "repr" => {
+                    repr.c |= attr.args.iter().any(|arg| arg == "C");
+                    repr.packed |= attr.args.iter().any(|arg| arg == "packed");
+                },
+                "align" => repr.align = attr.arg().and_then(|align| align.parse().ok()),
+                _ => (),
+            }
+        }
+        repr
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Hash)]
+pub struct TraitData {
+    pub methods: Vec<Symbol>,
+}
+
+// Each variant is its name, payload types, and discriminant
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Hash)]
+pub struct EnumData {
+    pub variants: Vec<(String, Vec<String>, i64)>,
 }
 
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Hash)]
@@ -29,6 +76,8 @@ pub enum AssocData {
     Fn(FnData),
     Var(VarData),
     Struct(StructData),
+    Trait(TraitData),
+    Enum(EnumData),
     Module(String),
 }
 
@@ -53,6 +102,8 @@ impl Symbol {
                 ret_ty: ret_ty.to_owned(),
                 is_extern,
                 member_of: member_of.map(|x| x.to_owned()),
+                is_variadic: false,
+                attrs: vec![],
             }),
             module: module.to_owned(),
             is_exportable,
@@ -62,7 +113,7 @@ impl Symbol {
     pub fn new_var(name: &str, ty: &Type, module: &str) -> Self {
         Symbol {
             name: name.to_owned(),
-            data: AssocData::Var(VarData { ty: ty.to_owned() }),
+            data: AssocData::Var(VarData { ty: ty.to_owned(), attrs: vec![] }),
             module: module.to_owned(),
             is_exportable: false,
         }
@@ -77,12 +128,35 @@ impl Symbol {
             data: AssocData::Struct(StructData {
                 fields: fields.map(|x| x.to_vec()),
                 methods: methods.map(|x| x.to_vec()),
+                impls: vec![],
+                defaults: vec![],
+                attrs: vec![],
             }),
             module: module.to_owned(),
             is_exportable,
         }
     }
 
+    pub fn new_trait(name: &str, methods: &[Symbol], module: &str, is_exportable: bool) -> Self {
+        Symbol {
+            name: name.to_owned(),
+            data: AssocData::Trait(TraitData { methods: methods.to_vec() }),
+            module: module.to_owned(),
+            is_exportable,
+        }
+    }
+
+    pub fn new_enum(
+        name: &str, variants: &[(String, Vec<String>, i64)], module: &str, is_exportable: bool,
+    ) -> Self {
+        Symbol {
+            name: name.to_owned(),
+            data: AssocData::Enum(EnumData { variants: variants.to_vec() }),
+            module: module.to_owned(),
+            is_exportable,
+        }
+    }
+
     pub fn set_name(&mut self, name: &str) {
         self.name = name.to_owned();
     }
@@ -97,7 +171,7 @@ impl Symbol {
     pub fn fq_name(&self) -> Option<&str> {
         match &self.data {
             AssocData::Fn(data) => Some(&data.fq_name),
-            AssocData::Struct(_) => Some(&self.name),
+            AssocData::Struct(_) | AssocData::Trait(_) | AssocData::Enum(_) => Some(&self.name),
             _ => None,
         }
     }
@@ -130,6 +204,21 @@ impl Symbol {
         }
     }
 
+    // Variadic externs take any number of args after their params
+    pub fn is_variadic(&self) -> bool {
+        match &self.data {
+            AssocData::Fn(s) => s.is_variadic,
+            _ => unreachable!("expected symbol to be a function"),
+        }
+    }
+
+    pub fn set_variadic(&mut self, variadic: bool) {
+        match &mut self.data {
+            AssocData::Fn(s) => s.is_variadic = variadic,
+            _ => unreachable!("expected symbol to be a function"),
+        }
+    }
+
     pub fn member_of(&self) -> Option<&str> {
         match &self.data {
             AssocData::Fn(s) => s.member_of.as_deref(),
@@ -153,6 +242,82 @@ impl Symbol {
         }
     }
 
+    pub fn impls(&self) -> &[String] {
+        match &self.data {
+            AssocData::Struct(s) => &s.impls,
+            _ => unreachable!("expected symbol to be a struct"),
+        }
+    }
+
+    pub fn field_default(&self, field: &str) -> Option<&Literal<()>> {
+        match &self.data {
+            AssocData::Struct(s) => s.defaults.iter().find(|(name, _)| name == field).map(|(_, lit)| lit),
+            _ => unreachable!("expected symbol to be a struct"),
+        }
+    }
+
+    pub fn set_field_defaults(&mut self, defaults: &[(String, Literal<()>)]) {
+        match &mut self.data {
+            AssocData::Struct(s) => s.defaults = defaults.to_vec(),
+            _ => unreachable!("expected symbol to be a struct"),
+        }
+    }
+
+    pub fn repr(&self) -> StructRepr {
+        match &self.data {
+            AssocData::Struct(s) => StructRepr::from(s.attrs.as_slice()),
+            _ => unreachable!("expected symbol to be a struct"),
+        }
+    }
+
+    // Only functions, variables, and structs have attributes
+    pub fn attrs(&self) -> &[Attribute] {
+        match &self.data {
+            AssocData::Fn(FnData { attrs, .. })
+            | AssocData::Var(VarData { attrs, .. })
+            | AssocData::Struct(StructData { attrs, .. }) => attrs,
+            _ => &[],
+        }
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&Attribute> {
+        self.attrs().iter().find(|attr| attr.name == name)
+    }
+
+    pub fn set_attrs(&mut self, new_attrs: &[Attribute]) {
+        match &mut self.data {
+            AssocData::Fn(FnData { attrs, .. })
+            | AssocData::Var(VarData { attrs, .. })
+            | AssocData::Struct(StructData { attrs, .. }) => *attrs = new_attrs.to_vec(),
+            _ => unreachable!("expected symbol to be a function, variable, or struct"),
+        }
+    }
+
+    // Record that a struct implements `trait_name` and add the trait's methods
+    pub fn add_impl(&mut self, trait_name: &str, methods: &[String]) {
+        match &mut self.data {
+            AssocData::Struct(s) => {
+                s.impls.push(trait_name.to_owned());
+                s.methods.get_or_insert_with(Vec::new).extend_from_slice(methods);
+            },
+            _ => unreachable!("expected symbol to be a struct"),
+        }
+    }
+
+    pub fn trait_methods(&self) -> &[Symbol] {
+        match &self.data {
+            AssocData::Trait(t) => &t.methods,
+            _ => unreachable!("expected symbol to be a trait"),
+        }
+    }
+
+    pub fn variants(&self) -> &[(String, Vec<String>, i64)] {
+        match &self.data {
+            AssocData::Enum(e) => &e.variants,
+            _ => unreachable!("expected symbol to be an enum"),
+        }
+    }
+
     pub fn is_import(&self, module: &str) -> bool {
         self.module != module && !self.is_extern()
     }
@@ -180,6 +345,8 @@ impl Symbolic for Symbol {
             AssocData::Fn(_) => "Fn",
             AssocData::Var(_) => "Var",
             AssocData::Struct(_) => "Struct",
+            AssocData::Trait(_) => "Trait",
+            AssocData::Enum(_) => "Enum",
             AssocData::Module(_) => "Module",
         }
     }
@@ -194,7 +361,7 @@ impl Display for Symbol {
         let mut output =
             format!("name: {}, module: {}, exportable: {}", self.name, self.module, self.is_exportable);
         match &self.data {
-            AssocData::Fn(FnData { fq_name, params, ret_ty, is_extern, member_of }) => {
+            AssocData::Fn(FnData { fq_name, params, ret_ty, is_extern, member_of, is_variadic, .. }) => {
                 output += &format!("\n      [Fn] {}(", fq_name);
                 if !params.is_empty() {
                     output += &format!("{}: {}", params[0].0, params[0].1);
@@ -203,13 +370,16 @@ impl Display for Symbol {
                         acc
                     });
                 };
+                if *is_variadic {
+                    output += ", ...";
+                }
                 output += &format!(") -> {}, is_extern: {}", ret_ty, is_extern);
                 if let Some(struct_name) = member_of {
                     output += &format!(", member_of: {}", struct_name);
                 }
             },
-            AssocData::Var(VarData { ty }) => output += &format!("\n      [Var] type: {}", ty),
-            AssocData::Struct(StructData { fields, methods }) => {
+            AssocData::Var(VarData { ty, .. }) => output += &format!("\n      [Var] type: {}", ty),
+            AssocData::Struct(StructData { fields, methods, impls, .. }) => {
                 output += "\n      [Struct] {{ ";
                 if let Some(fields) = fields {
                     if !fields.is_empty() {
@@ -230,9 +400,33 @@ impl Display for Symbol {
                         });
                     }
                 }
+                if !impls.is_empty() {
+                    output += &format!(" | impl {}", impls.join(", "));
+                }
+            },
+            AssocData::Trait(TraitData { methods }) => {
+                output += "\n      [Trait] { ";
+                output += &methods.iter().map(|m| format!("{}()", m.name)).collect::<Vec<_>>().join(", ");
+                output += " }";
+            },
+            AssocData::Enum(EnumData { variants }) => {
+                output += "\n      [Enum] { ";
+                output += &variants
+                    .iter()
+                    .map(|(name, fields, discriminant)| match fields.is_empty() {
+                        true => format!("{} = {}", name, discriminant),
+                        false => format!("{}({}) = {}", name, fields.join(", "), discriminant),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                output += " }";
             },
             AssocData::Module(_) => (),
         }
+        if !self.attrs().is_empty() {
+            let attrs = self.attrs().iter().map(|attr| attr.to_string()).collect::<Vec<_>>();
+            output += &format!(" | {}", attrs.join(" "));
+        }
         write!(f, "{}", output)
     }
 }
@@ -106,23 +106,41 @@ impl Lex {
                 "true" => Bool(true),
                 "false" => Bool(false),
                 "struct" => Struct,
+                "static" => Static,
+                "enum" => Enum,
+                "trait" => Trait,
+                "impl" => Impl,
+                "dyn" => Dyn,
                 "module" => Module,
                 "use" => Use,
                 "loop" => Loop,
+                "match" => Match,
+                "move" => Move,
+                "as" => Op(Operator::As),
                 "while" => While,
                 "break" => Break,
                 "next" => Next,
+                "panic" => Panic,
+                "assert" => Assert(false),
+                "debug_assert" => Assert(true),
+                "debug" => Debug,
+                "print" => Print(false),
+                "println" => Print(true),
                 _ => Ident(identifier),
             };
 
             return Ok(Token::new(tt, cur.line, cur.column));
         }
 
-        // Number literal
+        // Number literal. A `..` after the number starts a range. A number after a `.` is a
+        // tuple index, so another `.` starts the next selector
         if cur.value.is_ascii_digit() {
+            let is_index = matches!(self.tokens.last(), Some(Token { tt: Dot, .. }));
             let mut n = String::from(cur.value);
-            while let Some(c) = self.stream.peek() {
-                if c.value.is_ascii_alphanumeric() || *c == '.' {
+            while let Some(c) = self.stream.peek().cloned() {
+                if c.value.is_ascii_alphanumeric()
+                    || (c == '.' && !is_index && !matches!(self.stream.peek_nth(1), Some(c) if *c == '.'))
+                {
                     n.push(c.value);
                     self.stream.next();
                 } else {
@@ -193,7 +211,7 @@ impl Lex {
         // String literal
         if cur == '"' {
             let mut s = String::new();
-            for c in self.stream.by_ref() {
+            while let Some(c) = self.stream.next() {
                 match c.value {
                     '"' => break,
                     '\0' | '\n' => {
@@ -202,6 +220,20 @@ impl Lex {
                             c,
                         )));
                     },
+                    // Control sequences are kept as written and replaced when they're
+                    // lowered. An escaped `"` doesn't end the string
+                    '\\' => {
+                        s.push('\\');
+                        match self.stream.next() {
+                            Some(next) if next.value != '\0' && next.value != '\n' => s.push(next.value),
+                            next => {
+                                return Err(LexError::from((
+                                    "Unterminated string literal. Expecting `\"`".to_string(),
+                                    next.unwrap_or(c),
+                                )));
+                            },
+                        }
+                    },
                     v => s.push(v),
                 }
             }
@@ -212,6 +244,22 @@ impl Lex {
         // Multi-character operators
         if let Some(next) = self.stream.peek() {
             match cur.value {
+                '.' if next == &'.' => {
+                    self.stream.next();
+                    if matches!(self.stream.peek(), Some(c) if *c == '=') {
+                        self.stream.next();
+                        return Ok(Token::new(DotDotEq, cur.line, cur.column));
+                    }
+                    if matches!(self.stream.peek(), Some(c) if *c == '.') {
+                        self.stream.next();
+                        return Ok(Token::new(Ellipsis, cur.line, cur.column));
+                    }
+                    return Ok(Token::new(DotDot, cur.line, cur.column));
+                },
+                '=' if next == &'>' => {
+                    self.stream.next();
+                    return Ok(Token::new(FatArrow, cur.line, cur.column));
+                },
                 '=' if next == &'=' => {
                     self.stream.next();
                     return Ok(Token::new(Op(Operator::Eq), cur.line, cur.column));
@@ -286,6 +334,8 @@ impl Lex {
             '^' => Op(Operator::BitXor),
             '|' => Op(Operator::BitOr),
             '.' => Dot,
+            '#' => Hash,
+            '_' => Underscore,
             '}' => CloseBrace,
             ']' => CloseBracket,
             ')' => CloseParen,
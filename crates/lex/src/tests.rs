@@ -202,6 +202,90 @@ fn test_sarray() {
     run_insta!("array", tests);
 }
 
+#[test]
+fn test_trait() {
+    let tests = [
+        [
+            "decl",
+            r#"
+trait Len {
+    fn len() -> int
+}
+"#,
+        ],
+        [
+            "impl",
+            r#"
+impl Len for Foo {
+    fn len() -> int { 1 }
+}
+"#,
+        ],
+        [
+            "dyn",
+            r#"
+let l: dyn Len = foo
+"#,
+        ],
+    ];
+    run_insta!("trait", tests);
+}
+
+#[test]
+fn test_enum() {
+    let tests = [[
+        "decl",
+        r#"
+enum Shape {
+    Circle(float),
+    Empty = 2
+}
+let s: Shape = Shape::Circle(1.0)
+"#,
+    ]];
+    run_insta!("enum", tests);
+}
+
+#[test]
+fn test_match() {
+    let tests = [[
+        "arms",
+        r#"
+match x {
+    0 => 1,
+    1..5 => 2,
+    'a'..='z' => 3,
+    y if y > 9 => 4,
+    _ => 5
+}
+"#,
+    ]];
+    run_insta!("match", tests);
+}
+
+#[test]
+fn test_closure() {
+    let tests = [[
+        "basic",
+        r#"
+let f = move |x: int| -> int { x + offset }
+let g = || { f(1) }
+"#,
+    ]];
+    run_insta!("closure", tests);
+}
+
+#[test]
+fn test_tuple() {
+    let tests = [[
+        "index",
+        r#"
+let (q, r): (int, float) = (t.0.1, 1.5)
+"#,
+    ]];
+    run_insta!("tuple", tests);
+}
+
 #[test]
 fn test_while() {
     let tests = [[
@@ -225,6 +309,7 @@ fn test_string() {
             r#""mitral
 ""#,
         ],
+        ["escaped_quote", r#""say \"hi\"\n""#],
     ];
     run_insta!("string", tests);
 }
@@ -277,3 +362,26 @@ ghi
     assert_eq!(ContextElement::new(0 as char, 4, 0), stream.next().unwrap());
     assert_eq!(ContextElement::new(0 as char, 4, 0), stream.next().unwrap());
 }
+
+#[test]
+fn test_cast() {
+    let tests = [[
+        "basic",
+        r#"
+let x = c as uint8 + 1 as float
+"#,
+    ]];
+    run_insta!("cast", tests);
+}
+
+#[test]
+fn test_attr() {
+    let tests = [["align", "#[align(8)]"]];
+    run_insta!("attr", tests);
+}
+
+#[test]
+fn test_ellipsis() {
+    let tests = [["params", "(x, ...)"]];
+    run_insta!("ellipsis", tests);
+}
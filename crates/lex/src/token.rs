@@ -56,6 +56,7 @@ impl Default for Token {
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize)]
 pub enum TokenType {
+    Assert(bool), // (is debug?)
     Bool(bool),
     Break,
     Char(String),
@@ -64,26 +65,42 @@ pub enum TokenType {
     CloseParen,
     Colon,
     Comma,
+    Debug,
     Dot,
+    DotDot,
+    DotDotEq,
+    Dyn,
+    Ellipsis,
     Else,
+    Enum,
     Eof,
     Extern,
+    FatArrow,
     Fn,
     For,
+    Hash,
     Ident(String),
     If,
+    Impl,
     Let,
     Loop,
+    Match,
     Module,
+    Move,
     Next,
     Num(String),
     Op(Operator),
     OpenBrace,
     OpenBracket,
     OpenParen,
+    Panic,
+    Print(bool),     // (is println?)
     Semicolon(bool), // (is implicit?)
+    Static,
     Str(String),
     Struct,
+    Trait,
+    Underscore,
     Use,
     While,
 }
@@ -98,6 +115,12 @@ impl std::fmt::Display for TokenType {
             Ident(i) => write!(f, "{}", i),
             Num(n) => write!(f, "{}", n),
             Dot => write!(f, "."),
+            DotDot => write!(f, ".."),
+            DotDotEq => write!(f, "..="),
+            Ellipsis => write!(f, "..."),
+            FatArrow => write!(f, "=>"),
+            Hash => write!(f, "#"),
+            Underscore => write!(f, "_"),
             tt => write!(f, "{:?}", tt),
         }
     }
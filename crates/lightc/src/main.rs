@@ -76,13 +76,15 @@ fn main() {
 
         // Parser
         let (ast, module_name, imports) =
-            Parse::new(&tokens, &mut symbol_table).parse().unwrap_or_else(|errors| {
-                eprintln!("Encountered the following parsing error(s):");
-                for e in errors.iter() {
-                    eprintln!("\t{}", e);
-                }
-                process::exit(1);
-            });
+            Parse::with_file(&tokens, &mut symbol_table, &file.to_string_lossy()).parse().unwrap_or_else(
+                |errors| {
+                    eprintln!("Encountered the following parsing error(s):");
+                    for e in errors.iter() {
+                        eprintln!("\t{}", e);
+                    }
+                    process::exit(1);
+                },
+            );
 
         // Get the existing module or create and insert an empty one
         let module = module_map.entry(module_name.to_owned()).or_insert_with(|| Module::new(&module_name));
@@ -126,11 +128,15 @@ fn main() {
             .expect("Error resolving imports");
 
         // Type checker
-        let typed_ast =
-            Tych::new(module_name, &mut symbol_table).walk(module.ast.clone()).unwrap_or_else(|e| {
+        let (typed_ast, warnings) = Tych::new(module_name, &mut symbol_table)
+            .walk_with_warnings(module.ast.clone())
+            .unwrap_or_else(|e| {
                 eprintln!("Type checking error: {}", e);
                 process::exit(1);
             });
+        for warning in warnings {
+            eprintln!("Warning: {}", warning);
+        }
 
         if args.show_typed_ast {
             println!("Typed AST:");
@@ -142,10 +148,13 @@ fn main() {
         }
 
         // Lower
-        let hir = Lower::new(module_name, &mut symbol_table).walk(typed_ast).unwrap_or_else(|e| {
-            eprintln!("Lowering error: {}", e);
-            process::exit(1);
-        });
+        let hir = Lower::new(module_name, &mut symbol_table)
+            .with_debug_asserts(args.debug_asserts())
+            .walk(typed_ast)
+            .unwrap_or_else(|e| {
+                eprintln!("Lowering error: {}", e);
+                process::exit(1);
+            });
 
         if args.show_hir {
             println!("HIR:");
@@ -1,6 +1,6 @@
 use serde::Serialize;
 
-use common::{Literal, Operator, Prototype, Type};
+use common::{Literal, Loc, MatchArm, Operator, Prototype, Type};
 pub use node::Node;
 
 pub mod node;
@@ -56,20 +56,33 @@ pub trait Visitor {
     ) -> Self::Result;
     fn visit_loop(&mut self, body: Node) -> Self::Result;
     fn visit_let(&mut self, name: String, antn: Type, init: Option<Node>) -> Self::Result;
+    fn visit_destructure(&mut self, names: Vec<String>, antn: Type, init: Node) -> Self::Result;
+    fn visit_alias(&mut self, name: String, ty: Type, ptr: Node) -> Self::Result;
     fn visit_fn(&mut self, proto: Prototype, body: Option<Node>) -> Self::Result;
     fn visit_break(&mut self) -> Self::Result;
     fn visit_next(&mut self) -> Self::Result;
+    fn visit_panic(&mut self, msg: String, loc: Loc) -> Self::Result;
+    fn visit_debug(&mut self, value: Node) -> Self::Result;
+    fn visit_print(&mut self, pieces: Vec<String>, args: Vec<Node>) -> Self::Result;
     fn visit_lit(&mut self, value: Literal<Node>, ty: Type) -> Self::Result;
     fn visit_ident(&mut self, name: String, ty: Type) -> Self::Result;
-    fn visit_binop(&mut self, op: Operator, lhs: Node, rhs: Node) -> Self::Result;
+    fn visit_binop(&mut self, op: Operator, lhs: Node, rhs: Node, loc: Loc) -> Self::Result;
     fn visit_unop(&mut self, op: Operator, rhs: Node) -> Self::Result;
-    fn visit_call(&mut self, name: String, args: Vec<Node>) -> Self::Result;
+    fn visit_call(&mut self, name: String, args: Vec<Node>, ty: Type) -> Self::Result;
     fn visit_cond(
         &mut self, cond_expr: Node, then_block: Node, else_block: Option<Node>, ty: Type,
     ) -> Self::Result;
+    fn visit_match(&mut self, scrutinee: Node, arms: Vec<MatchArm<Node>>, ty: Type, loc: Loc)
+        -> Self::Result;
     fn visit_block(&mut self, list: Vec<Node>) -> Self::Result;
-    fn visit_index(&mut self, binding: Node, idx: Node) -> Self::Result;
-    fn visit_fselector(&mut self, comp: Node, idx: u32) -> Self::Result;
+    fn visit_index(&mut self, binding: Node, idx: Node, by_ref: bool, loc: Loc) -> Self::Result;
+    fn visit_fselector(&mut self, comp: Node, idx: u32, ty: Type) -> Self::Result;
+    fn visit_addr_of(&mut self, name: String, ty: Type) -> Self::Result;
+    fn visit_fn_ref(&mut self, name: String, ty: Type) -> Self::Result;
+    fn visit_cast(&mut self, expr: Node, ty: Type) -> Self::Result;
+    fn visit_dyn_call(&mut self, comp: Node, idx: u32, args: Vec<Node>, ty: Type) -> Self::Result;
+    fn visit_indirect_call(&mut self, callee: Node, args: Vec<Node>, ty: Type) -> Self::Result;
+    fn visit_variant(&mut self, idx: u32, args: Vec<Node>, ty: Type) -> Self::Result;
 }
 
 pub trait VisitableNode {
@@ -2,7 +2,7 @@ use serde::Serialize;
 use std::fmt::Display;
 
 use super::VisitableNode;
-use common::{Literal, Operator, Prototype, Type};
+use common::{Literal, Loc, MatchArm, Operator, Prototype, Type};
 
 #[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Node {
@@ -34,6 +34,14 @@ impl Node {
         Self { kind: Kind::Let { name, antn, init: init.map(Box::new) } }
     }
 
+    pub fn new_destructure(names: Vec<String>, antn: Type, init: Node) -> Self {
+        Self { kind: Kind::Destructure { names, antn, init: Box::new(init) } }
+    }
+
+    pub fn new_alias(name: String, ty: Type, ptr: Node) -> Self {
+        Self { kind: Kind::Alias { name, ty, ptr: Box::new(ptr) } }
+    }
+
     pub fn new_fn(proto: Prototype, body: Option<Node>) -> Self {
         Self { kind: Kind::Fn { proto, body: body.map(Box::new) } }
     }
@@ -46,6 +54,18 @@ impl Node {
         Self { kind: Kind::Next }
     }
 
+    pub fn new_panic(msg: String, loc: Loc) -> Self {
+        Self { kind: Kind::Panic { msg, loc } }
+    }
+
+    pub fn new_debug(value: Node) -> Self {
+        Self { kind: Kind::Debug { value: Box::new(value) } }
+    }
+
+    pub fn new_print(pieces: Vec<String>, args: Vec<Node>) -> Self {
+        Self { kind: Kind::Print { pieces, args } }
+    }
+
     pub fn new_lit(value: Literal<Node>, ty: Type) -> Self {
         Self { kind: Kind::Lit { value, ty } }
     }
@@ -54,8 +74,8 @@ impl Node {
         Self { kind: Kind::Ident { name, ty } }
     }
 
-    pub fn new_binop(op: Operator, lhs: Node, rhs: Node, ty: Type) -> Self {
-        Self { kind: Kind::BinOp { op, lhs: Box::new(lhs), rhs: Box::new(rhs), ty } }
+    pub fn new_binop(op: Operator, lhs: Node, rhs: Node, ty: Type, loc: Loc) -> Self {
+        Self { kind: Kind::BinOp { op, lhs: Box::new(lhs), rhs: Box::new(rhs), ty, loc } }
     }
 
     pub fn new_unop(op: Operator, rhs: Node, ty: Type) -> Self {
@@ -77,18 +97,46 @@ impl Node {
         }
     }
 
+    pub fn new_match(scrutinee: Node, arms: Vec<MatchArm<Node>>, ty: Type, loc: Loc) -> Self {
+        Self { kind: Kind::Match { scrutinee: Box::new(scrutinee), arms, ty, loc } }
+    }
+
     pub fn new_block(list: Vec<Node>, ty: Type) -> Self {
         Self { kind: Kind::Block { list, ty } }
     }
 
-    pub fn new_index(array: Node, idx: Node, ty: Type) -> Self {
-        Self { kind: Kind::Index { array: Box::new(array), idx: Box::new(idx), ty } }
+    pub fn new_index(array: Node, idx: Node, ty: Type, by_ref: bool, loc: Loc) -> Self {
+        Self { kind: Kind::Index { array: Box::new(array), idx: Box::new(idx), ty, by_ref, loc } }
     }
 
     pub fn new_fselector(comp: Node, idx: u32, ty: Type) -> Self {
         Self { kind: Kind::FSelector { comp: Box::new(comp), idx, ty } }
     }
 
+    pub fn new_addr_of(name: String, ty: Type) -> Self {
+        Self { kind: Kind::AddrOf { name, ty } }
+    }
+
+    pub fn new_fn_ref(name: String, ty: Type) -> Self {
+        Self { kind: Kind::FnRef { name, ty } }
+    }
+
+    pub fn new_cast(expr: Node, ty: Type) -> Self {
+        Self { kind: Kind::Cast { expr: Box::new(expr), ty } }
+    }
+
+    pub fn new_dyn_call(comp: Node, idx: u32, args: Vec<Node>, ty: Type) -> Self {
+        Self { kind: Kind::DynCall { comp: Box::new(comp), idx, args, ty } }
+    }
+
+    pub fn new_indirect_call(callee: Node, args: Vec<Node>, ty: Type) -> Self {
+        Self { kind: Kind::IndirectCall { callee: Box::new(callee), args, ty } }
+    }
+
+    pub fn new_variant(idx: u32, args: Vec<Node>, ty: Type) -> Self {
+        Self { kind: Kind::Variant { idx, args, ty } }
+    }
+
     pub fn ty(&self) -> &Type {
         use Kind::*;
 
@@ -99,9 +147,16 @@ impl Node {
             UnOp { ty, .. } => ty,
             Call { ty, .. } => ty,
             Cond { ty, .. } => ty,
+            Match { ty, .. } => ty,
             Block { ty, .. } => ty,
             Index { ty, .. } => ty,
             FSelector { ty, .. } => ty,
+            AddrOf { ty, .. } => ty,
+            FnRef { ty, .. } => ty,
+            Cast { ty, .. } => ty,
+            DynCall { ty, .. } => ty,
+            IndirectCall { ty, .. } => ty,
+            Variant { ty, .. } => ty,
             _ => unreachable!("statement found where expression expected"),
         }
     }
@@ -116,9 +171,16 @@ impl Node {
             UnOp { ty, .. } => *ty = new_ty,
             Call { ty, .. } => *ty = new_ty,
             Cond { ty, .. } => *ty = new_ty,
+            Match { ty, .. } => *ty = new_ty,
             Block { ty, .. } => *ty = new_ty,
             Index { ty, .. } => *ty = new_ty,
             FSelector { ty, .. } => *ty = new_ty,
+            AddrOf { ty, .. } => *ty = new_ty,
+            FnRef { ty, .. } => *ty = new_ty,
+            Cast { ty, .. } => *ty = new_ty,
+            DynCall { ty, .. } => *ty = new_ty,
+            IndirectCall { ty, .. } => *ty = new_ty,
+            Variant { ty, .. } => *ty = new_ty,
             _ => unreachable!("can't set type on statement"),
         }
     }
@@ -133,10 +195,14 @@ impl Node {
                     | Int16(_)
                     | Int32(_)
                     | Int64(_)
+                    | Int128(_)
+                    | ISize(_)
                     | UInt8(_)
                     | UInt16(_)
                     | UInt32(_)
                     | UInt64(_)
+                    | UInt128(_)
+                    | USize(_)
                     | Float(_)
                     | Double(_),
                 ..
@@ -164,12 +230,36 @@ pub enum Kind {
         antn: Type,
         init: Option<Box<Node>>,
     },
+    Destructure {
+        names: Vec<String>,
+        antn: Type,
+        init: Box<Node>,
+    },
+    // Binds `name` to the variable `ptr` points at, rather than to a new one
+    Alias {
+        name: String,
+        ty: Type,
+        ptr: Box<Node>,
+    },
     Fn {
         proto: Prototype,
         body: Option<Box<Node>>,
     },
     Break,
     Next,
+    Panic {
+        msg: String,
+        #[serde(skip)]
+        loc: Loc,
+    },
+    Debug {
+        value: Box<Node>,
+    },
+    // The text around the args. There's one more piece than there are args
+    Print {
+        pieces: Vec<String>,
+        args: Vec<Node>,
+    },
 
     // Expressions
     Lit {
@@ -185,6 +275,8 @@ pub enum Kind {
         lhs: Box<Node>,
         rhs: Box<Node>,
         ty: Type,
+        #[serde(skip)]
+        loc: Loc,
     },
     UnOp {
         op: Operator,
@@ -202,20 +294,61 @@ pub enum Kind {
         else_block: Option<Box<Node>>,
         ty: Type,
     },
+    Match {
+        scrutinee: Box<Node>,
+        arms: Vec<MatchArm<Node>>,
+        ty: Type,
+        #[serde(skip)]
+        loc: Loc,
+    },
     Block {
         list: Vec<Node>,
         ty: Type,
     },
+    // An element that's a method receiver is passed `by_ref`. `ty` is then the pointer type
     Index {
         array: Box<Node>,
         idx: Box<Node>,
         ty: Type,
+        by_ref: bool,
+        #[serde(skip)]
+        loc: Loc,
     },
     FSelector {
         comp: Box<Node>,
         idx: u32,
         ty: Type,
     },
+    // Pointer to a variable. `ty` is the pointer type
+    AddrOf {
+        name: String,
+        ty: Type,
+    },
+    // Pointer to a function. `ty` is the function pointer type
+    FnRef {
+        name: String,
+        ty: Type,
+    },
+    Cast {
+        expr: Box<Node>,
+        ty: Type,
+    },
+    DynCall {
+        comp: Box<Node>,
+        idx: u32,
+        args: Vec<Node>,
+        ty: Type,
+    },
+    IndirectCall {
+        callee: Box<Node>,
+        args: Vec<Node>,
+        ty: Type,
+    },
+    Variant {
+        idx: u32,
+        args: Vec<Node>,
+        ty: Type,
+    },
 }
 
 impl VisitableNode for Node {
@@ -227,21 +360,33 @@ impl VisitableNode for Node {
                 v.visit_for(start_name, start_antn, start_expr.map(|x| *x), *cond_expr, *step_expr, *body)
             },
             Let { name, antn, init } => v.visit_let(name, antn, init.map(|x| *x)),
+            Destructure { names, antn, init } => v.visit_destructure(names, antn, *init),
+            Alias { name, ty, ptr } => v.visit_alias(name, ty, *ptr),
             Loop { body } => v.visit_loop(*body),
             Fn { proto, body } => v.visit_fn(proto, body.map(|x| *x)),
             Lit { value, ty } => v.visit_lit(value, ty),
             Break => v.visit_break(),
             Next => v.visit_next(),
+            Panic { msg, loc } => v.visit_panic(msg, loc),
+            Debug { value } => v.visit_debug(*value),
+            Print { pieces, args } => v.visit_print(pieces, args),
             Ident { name, ty } => v.visit_ident(name, ty),
-            BinOp { op, lhs, rhs, .. } => v.visit_binop(op, *lhs, *rhs),
+            BinOp { op, lhs, rhs, loc, .. } => v.visit_binop(op, *lhs, *rhs, loc),
             UnOp { op, rhs, .. } => v.visit_unop(op, *rhs),
-            Call { name, args, .. } => v.visit_call(name, args),
+            Call { name, args, ty } => v.visit_call(name, args, ty),
             Cond { cond_expr, then_block, else_block, ty } => {
                 v.visit_cond(*cond_expr, *then_block, else_block.map(|x| *x), ty)
             },
+            Match { scrutinee, arms, ty, loc } => v.visit_match(*scrutinee, arms, ty, loc),
             Block { list, .. } => v.visit_block(list),
-            Index { array, idx, .. } => v.visit_index(*array, *idx),
-            FSelector { comp, idx, .. } => v.visit_fselector(*comp, idx),
+            Index { array, idx, by_ref, loc, .. } => v.visit_index(*array, *idx, by_ref, loc),
+            FSelector { comp, idx, ty } => v.visit_fselector(*comp, idx, ty),
+            AddrOf { name, ty } => v.visit_addr_of(name, ty),
+            FnRef { name, ty } => v.visit_fn_ref(name, ty),
+            Cast { expr, ty } => v.visit_cast(*expr, ty),
+            DynCall { comp, idx, args, ty } => v.visit_dyn_call(*comp, idx, args, ty),
+            IndirectCall { callee, args, ty } => v.visit_indirect_call(*callee, args, ty),
+            Variant { idx, args, ty } => v.visit_variant(idx, args, ty),
         }
     }
 }
@@ -266,12 +411,23 @@ impl Display for Node {
                 }
                 write!(f, "{})", s)
             },
+            Destructure { names, antn, init } => write!(f, "(let ({}):{} {})", names.join(", "), antn, init),
+            Alias { name, ty, ptr } => write!(f, "(alias {}:{} {})", name, ty, ptr),
             Fn { proto, body } => match &body {
                 Some(body) => write!(f, "(define {} {})", proto, body),
                 _ => write!(f, "(define {})", proto),
             },
             Break => write!(f, "break"),
             Next => write!(f, "next"),
+            Panic { msg, .. } => write!(f, "panic(\"{}\")", msg),
+            Debug { value } => write!(f, "(debug {})", value),
+            Print { pieces, args } => {
+                let mut s = format!("(print {:?}", pieces.join("{}"));
+                for arg in args {
+                    s += &format!(" {}", arg);
+                }
+                write!(f, "{})", s)
+            },
             Lit { value, .. } => write!(f, "{}", value),
             Ident { name, .. } => write!(f, "{}", name),
             BinOp { op, lhs, rhs, .. } => write!(f, "({} {} {})", op, lhs, rhs),
@@ -292,6 +448,10 @@ impl Display for Node {
                 }
                 write!(f, "{})", s)
             },
+            Match { scrutinee, arms, .. } => {
+                let arm_string = arms.iter().map(|arm| arm.to_string()).collect::<Vec<_>>().join(" ");
+                write!(f, "(match {} '({}))", scrutinee, arm_string)
+            },
             Block { list, .. } => {
                 let mut s = "'(".to_string();
                 s += &list.iter().fold(String::new(), |mut acc, n| {
@@ -302,6 +462,30 @@ impl Display for Node {
             },
             Index { array, idx, .. } => write!(f, "{}[{}]", array, idx),
             FSelector { comp, idx, .. } => write!(f, "{}.{}", comp, idx),
+            AddrOf { name, .. } => write!(f, "(& {})", name),
+            FnRef { name, .. } => write!(f, "(fn {})", name),
+            Cast { expr, ty } => write!(f, "(as {} {})", expr, ty),
+            DynCall { comp, idx, args, .. } => {
+                let mut s = format!("(dyn {}[{}]", comp, idx);
+                for arg in args {
+                    s += &format!(" {}", arg);
+                }
+                write!(f, "{})", s)
+            },
+            IndirectCall { callee, args, .. } => {
+                let mut s = format!("(*{}", callee);
+                for arg in args {
+                    s += &format!(" {}", arg);
+                }
+                write!(f, "{})", s)
+            },
+            Variant { idx, args, ty } => {
+                let mut s = format!("({}[{}]", ty, idx);
+                for arg in args {
+                    s += &format!(" {}", arg);
+                }
+                write!(f, "{})", s)
+            },
         }
     }
 }
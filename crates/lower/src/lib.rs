@@ -1,7 +1,10 @@
 use std::collections::HashSet;
 
+use common::literal::unescape;
 use common::symbol_table::Symbolic;
-use common::{Literal, Operator, Prototype, Symbol, SymbolTable, Type};
+use common::{
+    split_format, Attribute, Literal, Loc, MatchArm, Operator, Pattern, Prototype, Symbol, SymbolTable, Type,
+};
 pub use hir::Hir;
 use parse::ast::{self, Ast, VisitableNode, Visitor};
 
@@ -18,18 +21,26 @@ extern crate common;
 // - desugars x += 1 to x = x + 1
 // - cooks function names in the AST and symbol table
 // - tracks scope (needed?)
-// - initializes uninitialized variables
-// - drops field information from structs
+// - initializes uninitialized variables, giving struct fields their defaults
+// - drops field information from structs and struct literals
+// - drops traits and lowers impl methods the same as struct methods
+// - drops enums and replaces variant names with their index
+// - lowers method calls on trait objects to calls through the vtable
 // - inserts let statements to support field/method chaining
 // - inserts imported functions into the HIR
 // - discards unreachable nodes (e.g., after a `break`)
 // - desugars while
+// - desugars `assert` into a conditional panic, dropping `debug_assert` when disabled
+// - lowers functions used as values to references to the function with its lowered name
+// - closure-converts closures into an environment struct literal and a lifted `call`
+//   method that binds the captures before running the closure's body
 
 pub struct Lower<'a> {
     symbol_table: &'a mut SymbolTable<Symbol>,
     struct_methods: Vec<hir::Node>,
     imported_functions: HashSet<Symbol>,
     discard_nodes: bool,
+    debug_asserts: bool,
     module: String,
 }
 
@@ -40,10 +51,17 @@ impl<'a> Lower<'a> {
             struct_methods: vec![],
             imported_functions: HashSet::new(),
             discard_nodes: false,
+            debug_asserts: true,
             module: module.to_owned(),
         }
     }
 
+    // Whether `debug_assert` statements are kept
+    pub fn with_debug_asserts(mut self, debug_asserts: bool) -> Self {
+        self.debug_asserts = debug_asserts;
+        self
+    }
+
     pub fn walk(mut self, ast: Ast<ast::Node>) -> Result<Hir<hir::Node>, String> {
         let mut hir = Hir::new();
         let nodes = ast
@@ -70,6 +88,28 @@ impl<'a> Lower<'a> {
         Ok(hir)
     }
 
+    fn lower_pattern(&mut self, pattern: Pattern<ast::Node>) -> Result<Pattern<hir::Node>, String> {
+        let mut lower_value = |value: ast::Node| {
+            self.visit_node(value).map(|n| n.unwrap_or_else(|| unreachable!("missing value in pattern")))
+        };
+        Ok(match pattern {
+            Pattern::Wildcard => Pattern::Wildcard,
+            Pattern::Binding(name) => Pattern::Binding(name),
+            Pattern::Lit(value) => Pattern::Lit(lower_value(value)?),
+            Pattern::Range { start, end, inclusive } => {
+                Pattern::Range { start: lower_value(start)?, end: lower_value(end)?, inclusive }
+            },
+            Pattern::Variant { enum_name, variant, fields } => Pattern::Variant {
+                enum_name,
+                variant,
+                fields: fields
+                    .into_iter()
+                    .map(|f| self.lower_pattern(f))
+                    .collect::<Result<Vec<_>, String>>()?,
+            },
+        })
+    }
+
     fn lower_lit_array(&mut self, lit: Literal<ast::Node>) -> Result<Literal<hir::Node>, String> {
         // Extract the elements vec and the type of the array elements.
         let (elements, ty) = match lit {
@@ -100,6 +140,25 @@ impl<'a> Lower<'a> {
         }
     }
 
+    // Returns the lowered name of a function, for calls and references to it. Externs keep
+    // their name. Also makes a list of all imported functions
+    fn lower_fn_name(&mut self, name: String) -> String {
+        let sym = self
+            .symbol_table
+            .get(&name)
+            .unwrap_or_else(|| unreachable!("missing symbol in `lower_fn_name()` for `{}`", name));
+
+        if sym.is_import(&self.module) {
+            self.imported_functions.insert(sym.clone());
+        }
+
+        // Update the HIR with the lowered name if it hasn't been done already
+        match sym.name() {
+            sym_name if !sym.is_extern() && sym_name != name => sym_name.to_owned(),
+            _ => name,
+        }
+    }
+
     fn init_null(&mut self, name: &str, antn: &Type) -> Result<hir::Node, String> {
         use Type::*;
 
@@ -108,35 +167,60 @@ impl<'a> Lower<'a> {
             Int16 => init_literal!(Int16, 0),
             Int32 => init_literal!(Int32, 0),
             Int64 => init_literal!(Int64, 0),
+            Int128 => init_literal!(Int128, 0),
+            ISize => init_literal!(ISize, 0),
             UInt8 => init_literal!(UInt8, 0),
             UInt16 => init_literal!(UInt16, 0),
             UInt32 => init_literal!(UInt32, 0),
             UInt64 => init_literal!(UInt64, 0),
+            UInt128 => init_literal!(UInt128, 0),
+            USize => init_literal!(USize, 0),
             Float => init_literal!(Float, 0.0),
             Double => init_literal!(Double, 0.0),
             Char => init_literal!(Char, 0),
             Bool => init_literal!(Bool, false),
-            SArray(ty, len) => hir::Node::new_lit(
-                Literal::Array { elements: Vec::with_capacity(*len), inner_ty: Some(*ty.clone()) },
-                Type::SArray(Box::new(*ty.clone()), *len),
-            ),
+            Str => init_literal!(Str, String::new()),
+            SArray(ty, len) => {
+                // Struct elements start with their defaults, the rest are zeroed
+                let elements = match ty.as_ref() {
+                    Comp(_) => vec![self.init_null(name, ty)?; *len],
+                    _ => Vec::with_capacity(*len),
+                };
+                hir::Node::new_lit(
+                    Literal::Array { elements, inner_ty: Some(*ty.clone()) },
+                    Type::SArray(Box::new(*ty.clone()), *len),
+                )
+            },
             Comp(name) => {
                 let sym = self
                     .symbol_table
                     .resolve_symbol(name, &self.module)
                     .cloned()
                     .unwrap_or_else(|| unreachable!("missing symbol for `{}` in `init_null()`", name));
+                // Fields with a default start with it instead of a zeroed value
                 let initializers = if let Some(fields) = sym.fields() {
                     fields
                         .iter()
-                        .map(|(n, a)| self.init_null(n, &(*a).into()))
+                        .map(|(n, a)| match sym.field_default(n).and_then(|lit| lit.to_scalar()) {
+                            Some(lit) => Ok(hir::Node::new_lit(lit, (*a).into())),
+                            None => self.init_null(n, &(*a).into()),
+                        })
                         .collect::<Result<Vec<_>, String>>()?
                 } else {
                     vec![]
                 };
                 hir::Node::new_lit(Literal::Comp(initializers), Type::Comp(name.to_owned()))
             },
-            Ptr(_) => hir::Node::new_lit(Literal::UInt64(0), Type::UInt64), // TODO: Arch?
+            Ptr(_) => hir::Node::new_lit(Literal::Comp(vec![]), antn.clone()), // Null pointer
+            Dyn(_) => hir::Node::new_lit(Literal::Comp(vec![]), antn.clone()), // Null trait object
+            Enum(_) => hir::Node::new_lit(Literal::Comp(vec![]), antn.clone()), // First variant, zeroed
+            Fn(..) => hir::Node::new_lit(Literal::Comp(vec![]), antn.clone()), // Null function pointer
+            Tuple(tys) => hir::Node::new_lit(
+                Literal::Tuple(
+                    tys.iter().map(|ty| self.init_null(name, ty)).collect::<Result<Vec<_>, String>>()?,
+                ),
+                antn.clone(),
+            ),
             Void => unreachable!("void type for `{}` variable initialization annotation", name),
         })
     }
@@ -196,12 +280,27 @@ impl<'a> ast::Visitor for Lower<'a> {
         Ok(Some(hir::Node::new_loop(self.visit_cond(cond_expr, body, Some(break_br), None)?.unwrap())))
     }
 
-    fn visit_let(&mut self, name: String, antn: Type, init: Option<ast::Node>) -> Self::Result {
+    fn visit_let(
+        &mut self, name: String, antn: Option<Type>, init: Option<ast::Node>, _attrs: Vec<Attribute>,
+    ) -> Self::Result {
+        let antn = antn.unwrap_or_else(|| unreachable!("missing type for `{}` in lower", name));
         self.symbol_table.insert(Symbol::new_var(&name, &antn, &self.module));
         let init_node = self.lower_var_init(&name, init.as_ref(), &antn)?;
         Ok(Some(hir::Node::new_let(name, antn, Some(init_node))))
     }
 
+    fn visit_destructure(&mut self, names: Vec<String>, antn: Option<Type>, init: ast::Node) -> Self::Result {
+        let antn = antn.unwrap_or_default();
+        if let Type::Tuple(tys) = &antn {
+            for (name, ty) in names.iter().zip(tys).filter(|(name, _)| *name != "_") {
+                self.symbol_table.insert(Symbol::new_var(name, ty, &self.module));
+            }
+        }
+        let lowered_init =
+            self.visit_node(init)?.unwrap_or_else(|| unreachable!("missing initializer node in destructure"));
+        Ok(Some(hir::Node::new_destructure(names, antn, lowered_init)))
+    }
+
     fn visit_fn(&mut self, proto: Prototype, body: Option<ast::Node>) -> Self::Result {
         let mut proto = proto;
         // Insert a duplicate of the symbol. The new one will have the lowered name. Use
@@ -240,7 +339,7 @@ impl<'a> ast::Visitor for Lower<'a> {
     // Structs don't make it into the HIR. The type with fields is already in the symbol
     // table. This lowers the methods to be added via self.struct_methods
     fn visit_struct(
-        &mut self, _name: String, _fields: Vec<ast::Node>, methods: Vec<ast::Node>,
+        &mut self, _name: String, _fields: Vec<ast::Node>, methods: Vec<ast::Node>, _attrs: Vec<Attribute>,
     ) -> Self::Result {
         // Save the methods separately to pop them up to the top of the HIR later
         let mut lowered_methods = methods
@@ -252,6 +351,23 @@ impl<'a> ast::Visitor for Lower<'a> {
         Ok(None)
     }
 
+    // Traits only exist in the symbol table
+    fn visit_trait(&mut self, _name: String, _methods: Vec<ast::Node>) -> Self::Result {
+        Ok(None)
+    }
+
+    // Impl methods are lowered the same as struct methods
+    fn visit_impl(
+        &mut self, _trait_name: String, struct_name: String, methods: Vec<ast::Node>,
+    ) -> Self::Result {
+        self.visit_struct(struct_name, vec![], methods, vec![])
+    }
+
+    // Enums only exist in the symbol table
+    fn visit_enum(&mut self, _name: String, _variants: Vec<(String, Vec<Type>, i64)>) -> Self::Result {
+        Ok(None)
+    }
+
     fn visit_break(&mut self) -> Self::Result {
         self.discard_nodes = true;
         Ok(Some(hir::Node::new_break()))
@@ -262,6 +378,53 @@ impl<'a> ast::Visitor for Lower<'a> {
         Ok(Some(hir::Node::new_next()))
     }
 
+    fn visit_panic(&mut self, msg: String, loc: Loc) -> Self::Result {
+        self.discard_nodes = true;
+        let msg = unescape(&msg).unwrap_or_else(|e| unreachable!("{} in panic message", e));
+        Ok(Some(hir::Node::new_panic(msg, loc)))
+    }
+
+    // assert(cond) => if cond { } else { panic(msg) }
+    fn visit_assert(&mut self, cond: ast::Node, msg: String, debug: bool, loc: Loc) -> Self::Result {
+        if debug && !self.debug_asserts {
+            return Ok(None);
+        }
+
+        let lowered_cond =
+            self.visit_node(cond)?.unwrap_or_else(|| unreachable!("missing condition node in assert"));
+        let msg = unescape(&msg).unwrap_or_else(|e| unreachable!("{} in assert message", e));
+        Ok(Some(hir::Node::new_cond(
+            lowered_cond,
+            hir::Node::new_block(vec![], Type::Void),
+            Some(hir::Node::new_block(vec![hir::Node::new_panic(msg, loc)], Type::Void)),
+            Type::Void,
+        )))
+    }
+
+    fn visit_debug(&mut self, value: ast::Node) -> Self::Result {
+        let lowered_value =
+            self.visit_node(value)?.unwrap_or_else(|| unreachable!("missing value node in debug"));
+        Ok(Some(hir::Node::new_debug(lowered_value)))
+    }
+
+    fn visit_print(&mut self, fmt: String, args: Vec<ast::Node>, newline: bool) -> Self::Result {
+        let mut pieces = split_format(&fmt)
+            .unwrap_or_else(|e| unreachable!("invalid format string in print: {}", e))
+            .iter()
+            .map(|piece| unescape(piece).unwrap_or_else(|e| unreachable!("{} in format string", e)))
+            .collect::<Vec<_>>();
+        if newline {
+            pieces.last_mut().unwrap_or_else(|| unreachable!("format string without pieces")).push('\n');
+        }
+
+        let mut lowered_args = vec![];
+        for arg in args {
+            lowered_args
+                .push(self.visit_node(arg)?.unwrap_or_else(|| unreachable!("missing arg node in print")));
+        }
+        Ok(Some(hir::Node::new_print(pieces, lowered_args)))
+    }
+
     fn visit_lit(&mut self, value: Literal<ast::Node>, ty: Option<Type>) -> Self::Result {
         use Literal::*;
 
@@ -271,27 +434,44 @@ impl<'a> ast::Visitor for Lower<'a> {
             Int16(l) => Int16(l),
             Int32(l) => Int32(l),
             Int64(l) => Int64(l),
+            Int128(l) => Int128(l),
+            ISize(l) => ISize(l),
             UInt8(l) => UInt8(l),
             UInt16(l) => UInt16(l),
             UInt32(l) => UInt32(l),
             UInt64(l) => UInt64(l),
+            UInt128(l) => UInt128(l),
+            USize(l) => USize(l),
             Float(l) => Float(l),
             Double(l) => Double(l),
             Bool(l) => Bool(l),
             Char(l) => Char(l),
+            Str(l) => Str(unescape(&l).unwrap_or_else(|e| unreachable!("{} in string literal", e))),
             Array { .. } => self.lower_lit_array(value)?,
+            Tuple(elements) => Tuple(
+                elements
+                    .into_iter()
+                    .map(|el| self.visit_node(el))
+                    .filter_map(Result::transpose)
+                    .collect::<Result<Vec<_>, String>>()?,
+            ),
             Comp(_) => todo!(),
         };
         Ok(Some(hir::Node::new_lit(lit, ty.unwrap_or_default())))
     }
 
     fn visit_ident(&mut self, name: String, ty: Option<Type>) -> Self::Result {
+        // Functions used as values
+        if self.symbol_table.get(&name).is_some_and(|sym| sym.kind() == "Fn") {
+            let lowered_name = self.lower_fn_name(name);
+            return Ok(Some(hir::Node::new_fn_ref(lowered_name, ty.unwrap_or_default())));
+        }
         Ok(Some(hir::Node::new_ident(name, ty.unwrap_or_default())))
     }
 
     // Lower `x += 1` to `x = x + 1`
     fn visit_binop(
-        &mut self, op: Operator, lhs: ast::Node, rhs: ast::Node, ty: Option<Type>,
+        &mut self, op: Operator, lhs: ast::Node, rhs: ast::Node, ty: Option<Type>, loc: Loc,
     ) -> Self::Result {
         use Operator::*;
 
@@ -299,14 +479,14 @@ impl<'a> ast::Visitor for Lower<'a> {
         let ty = ty.unwrap_or_default();
         let rhs = self.visit_node(rhs)?.unwrap_or_else(|| unreachable!("missing rhs node in binop"));
         let (top_op, lowered_rhs) = match op {
-            AddEq => (Assign, hir::Node::new_binop(Add, lowered_lhs.clone(), rhs, ty.clone())),
-            SubEq => (Assign, hir::Node::new_binop(Sub, lowered_lhs.clone(), rhs, ty.clone())),
-            MulEq => (Assign, hir::Node::new_binop(Mul, lowered_lhs.clone(), rhs, ty.clone())),
-            DivEq => (Assign, hir::Node::new_binop(Div, lowered_lhs.clone(), rhs, ty.clone())),
+            AddEq => (Assign, hir::Node::new_binop(Add, lowered_lhs.clone(), rhs, ty.clone(), loc.clone())),
+            SubEq => (Assign, hir::Node::new_binop(Sub, lowered_lhs.clone(), rhs, ty.clone(), loc.clone())),
+            MulEq => (Assign, hir::Node::new_binop(Mul, lowered_lhs.clone(), rhs, ty.clone(), loc.clone())),
+            DivEq => (Assign, hir::Node::new_binop(Div, lowered_lhs.clone(), rhs, ty.clone(), loc.clone())),
             _ => (op, rhs),
         };
 
-        Ok(Some(hir::Node::new_binop(top_op, lowered_lhs, lowered_rhs, ty)))
+        Ok(Some(hir::Node::new_binop(top_op, lowered_lhs, lowered_rhs, ty, loc)))
     }
 
     fn visit_unop(&mut self, op: Operator, rhs: ast::Node, ty: Option<Type>) -> Self::Result {
@@ -318,22 +498,7 @@ impl<'a> ast::Visitor for Lower<'a> {
     }
 
     fn visit_call(&mut self, name: String, args: Vec<ast::Node>, ty: Option<Type>) -> Self::Result {
-        let sym = self
-            .symbol_table
-            .get(&name)
-            .unwrap_or_else(|| unreachable!("missing symbol in `visit_call()` for `{}`", name));
-
-        // Update the HIR with the lowered name if it hasn't been done already and it's
-        // not an extern call
-        let lowered_name = match sym.name() {
-            sym_name if !sym.is_extern() && sym_name != name => sym_name.to_owned(),
-            _ => name,
-        };
-
-        // Make a list of all imported functions
-        if sym.is_import(&self.module) {
-            self.imported_functions.insert(sym.clone());
-        }
+        let lowered_name = self.lower_fn_name(name);
 
         let mut lowered_args = vec![];
         for arg in args {
@@ -344,6 +509,22 @@ impl<'a> ast::Visitor for Lower<'a> {
         Ok(Some(hir::Node::new_call(lowered_name, lowered_args, ty.unwrap_or_default())))
     }
 
+    fn visit_indirect_call(
+        &mut self, callee: ast::Node, args: Vec<ast::Node>, ty: Option<Type>,
+    ) -> Self::Result {
+        let lowered_callee =
+            self.visit_node(callee)?.unwrap_or_else(|| unreachable!("missing callee node in indirect call"));
+
+        let mut lowered_args = vec![];
+        for arg in args {
+            lowered_args.push(
+                self.visit_node(arg)?.unwrap_or_else(|| unreachable!("missing arg node in indirect call")),
+            );
+        }
+
+        Ok(Some(hir::Node::new_indirect_call(lowered_callee, lowered_args, ty.unwrap_or_default())))
+    }
+
     fn visit_cond(
         &mut self, cond_expr: ast::Node, then_block: ast::Node, else_block: Option<ast::Node>,
         ty: Option<Type>,
@@ -363,6 +544,28 @@ impl<'a> ast::Visitor for Lower<'a> {
         Ok(Some(hir::Node::new_cond(lowered_cond, lowered_then, lowered_else, ty.unwrap_or_default())))
     }
 
+    fn visit_match(
+        &mut self, scrutinee: ast::Node, arms: Vec<MatchArm<ast::Node>>, ty: Option<Type>, loc: Loc,
+    ) -> Self::Result {
+        let lowered_scrutinee =
+            self.visit_node(scrutinee)?.unwrap_or_else(|| unreachable!("missing scrutinee node in match"));
+        let lowered_arms = arms
+            .into_iter()
+            .map(|arm| {
+                let guard = arm
+                    .guard
+                    .map(|g| {
+                        self.visit_node(g).map(|n| n.unwrap_or_else(|| unreachable!("missing guard node")))
+                    })
+                    .transpose()?;
+                let body =
+                    self.visit_node(arm.body)?.unwrap_or_else(|| unreachable!("missing match arm body"));
+                Ok(MatchArm::new(self.lower_pattern(arm.pattern)?, guard, body))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Some(hir::Node::new_match(lowered_scrutinee, lowered_arms, ty.unwrap_or_default(), loc)))
+    }
+
     fn visit_block(&mut self, list: Vec<ast::Node>, ty: Option<Type>) -> Self::Result {
         self.symbol_table.enter_scope();
 
@@ -379,12 +582,14 @@ impl<'a> ast::Visitor for Lower<'a> {
         Ok(Some(hir::Node::new_block(lowered_list, ty.unwrap_or_default())))
     }
 
-    fn visit_index(&mut self, binding: ast::Node, idx: ast::Node, ty: Option<Type>) -> Self::Result {
+    fn visit_index(
+        &mut self, binding: ast::Node, idx: ast::Node, ty: Option<Type>, loc: Loc,
+    ) -> Self::Result {
         let lowered_binding =
             self.visit_node(binding)?.unwrap_or_else(|| unreachable!("missing binding node in index"));
         let lowered_index =
             self.visit_node(idx)?.unwrap_or_else(|| unreachable!("missing index node in index"));
-        Ok(Some(hir::Node::new_index(lowered_binding, lowered_index, ty.unwrap_or_default())))
+        Ok(Some(hir::Node::new_index(lowered_binding, lowered_index, ty.unwrap_or_default(), false, loc)))
     }
 
     fn visit_fselector(&mut self, comp: ast::Node, field: String, ty: Option<Type>) -> Self::Result {
@@ -392,6 +597,12 @@ impl<'a> ast::Visitor for Lower<'a> {
             .visit_node(comp)?
             .unwrap_or_else(|| unreachable!("missing composite node in field selector"));
 
+        // Tuple fields are already indexes
+        if let Type::Tuple(_) = lowered_comp.ty() {
+            let idx = field.parse().map_err(|err| format!("failed to convert tuple index: `{}`", err))?;
+            return Ok(Some(hir::Node::new_fselector(lowered_comp, idx, ty.unwrap_or_default())));
+        }
+
         let comp_name = match lowered_comp.ty() {
             Type::Ptr(boxed) => match &**boxed {
                 Type::Comp(name) => name.to_owned(),
@@ -431,22 +642,170 @@ impl<'a> ast::Visitor for Lower<'a> {
         let lowered_comp = self
             .visit_node(comp)?
             .unwrap_or_else(|| unreachable!("missing composite node in method selector"));
+
+        // Trait object methods are called through the vtable slot with the method's index in
+        // the trait
+        if let Type::Dyn(trait_name) = lowered_comp.ty().clone() {
+            let idx = self
+                .symbol_table
+                .resolve_symbol(&trait_name, &self.module)
+                .unwrap_or_else(|| unreachable!("missing symbol for trait `{}` in lower", trait_name))
+                .trait_methods()
+                .iter()
+                .position(|m| m.name == name)
+                .unwrap_or_else(|| unreachable!("trait `{}` has no method: `{}`", trait_name, name))
+                .try_into()
+                .map_err(|err| format!("failed to convert vtable index: `{}`", err))?;
+            let lowered_args = args
+                .into_iter()
+                .map(|n| self.visit_node(n).map(|n| n.unwrap_or_else(|| unreachable!("missing arg node"))))
+                .collect::<Result<Vec<_>, String>>()?;
+            return Ok(Some(hir::Node::new_dyn_call(
+                lowered_comp,
+                idx,
+                lowered_args,
+                ty.unwrap_or_default(),
+            )));
+        }
+
         let lowered_call = self
             .visit_call(name, args, ty)?
             .unwrap_or_else(|| unreachable!("missing call node in method selector"));
         match lowered_call.kind {
             hir::node::Kind::Call { name, mut args, ty } => {
-                // Replace `self` node with the real composite value
+                // Replace `self` node with the real composite value. Variables, fields, and
+                // array elements are passed by address. Any other value is a temporary, so
+                // it's spilled to the stack first
+                let mut spill = None;
                 args[0] = match lowered_comp.kind {
+                    _ if matches!(lowered_comp.ty(), Type::Ptr(_)) => lowered_comp,
                     hir::node::Kind::Ident { name, ty } => hir::Node::new_ident(name, pointer_wrap!(ty)),
                     hir::node::Kind::FSelector { comp, idx, ty } => {
                         hir::Node::new_fselector(*comp, idx, pointer_wrap!(ty))
                     },
-                    e => unimplemented!("unexpected node type for `self`: `{:?}`", e),
+                    hir::node::Kind::Index { array, idx, ty, loc, .. } => {
+                        hir::Node::new_index(*array, *idx, pointer_wrap!(ty), true, loc)
+                    },
+                    _ => {
+                        let tmp_name = self.symbol_table.uniq_ident(None);
+                        let tmp_ty = lowered_comp.ty().clone();
+                        spill =
+                            Some(hir::Node::new_let(tmp_name.clone(), tmp_ty.clone(), Some(lowered_comp)));
+                        hir::Node::new_ident(tmp_name, pointer_wrap!(tmp_ty))
+                    },
                 };
-                Ok(Some(hir::Node::new_call(name, args, ty)))
+
+                let call = hir::Node::new_call(name, args, ty.clone());
+                Ok(Some(match spill {
+                    Some(spill) => hir::Node::new_block(vec![spill, call], ty),
+                    None => call,
+                }))
             },
             _ => unreachable!("unknown node kind in `visit_mselector()`"),
         }
     }
+
+    // The closure's body is lifted into the `call` method of its environment struct, and
+    // the closure becomes a literal of that struct. Captures are bound at the top of the
+    // body, either to their copy in the environment or to the variable the environment
+    // points at.
+    fn visit_closure(
+        &mut self, _params: Vec<(String, Type)>, _ret_ty: Type, body: ast::Node, is_move: bool,
+        captures: Vec<(String, Type)>, ty: Option<Type>,
+    ) -> Self::Result {
+        let ty = ty.unwrap_or_else(|| unreachable!("missing type for closure in lower"));
+        let env_ty = pointer_wrap!(ty);
+        let call_name = format!("_{}_call", ty.get_comp_name());
+        let call_sym = self
+            .symbol_table
+            .get(&call_name)
+            .cloned()
+            .unwrap_or_else(|| unreachable!("missing symbol in `visit_closure()` for `{}`", call_name));
+
+        // Methods of closures are found while inside of a function, so make sure the
+        // lowered name is global
+        self.symbol_table.insert_global(call_sym.name(), call_sym.clone());
+        let proto = Prototype::from(call_sym);
+        let env_param = proto.params()[0].0.to_owned();
+
+        self.symbol_table.enter_scope();
+        for (name, ty) in proto.params() {
+            self.symbol_table.insert(Symbol::new_var(name, ty, &self.module));
+        }
+        for (name, field_ty) in &captures {
+            let var_ty = if is_move { field_ty } else { field_ty.get_ptr_ty() };
+            self.symbol_table.insert(Symbol::new_var(name, var_ty, &self.module));
+        }
+        let lowered_body =
+            self.visit_node(body)?.unwrap_or_else(|| unreachable!("missing body node in closure"));
+        self.symbol_table.leave_scope();
+
+        let mut list = Vec::with_capacity(captures.len());
+        for (idx, (name, field_ty)) in captures.iter().enumerate() {
+            let idx = idx.try_into().map_err(|err| format!("failed to convert capture index: `{}`", err))?;
+            let field = hir::Node::new_fselector(
+                hir::Node::new_ident(env_param.clone(), env_ty.clone()),
+                idx,
+                field_ty.clone(),
+            );
+            list.push(match is_move {
+                true => hir::Node::new_let(name.to_owned(), field_ty.clone(), Some(field)),
+                false => hir::Node::new_alias(name.to_owned(), field_ty.get_ptr_ty().clone(), field),
+            });
+        }
+        let lifted_body = match lowered_body.kind {
+            hir::node::Kind::Block { list: body_list, ty } => {
+                list.extend(body_list);
+                hir::Node::new_block(list, ty)
+            },
+            _ => unreachable!("closure body isn't a block in lower"),
+        };
+        self.struct_methods.push(hir::Node::new_fn(proto, Some(lifted_body)));
+
+        let fields = captures
+            .into_iter()
+            .map(|(name, field_ty)| match is_move {
+                true => hir::Node::new_ident(name, field_ty),
+                false => hir::Node::new_addr_of(name, field_ty),
+            })
+            .collect();
+        Ok(Some(hir::Node::new_lit(Literal::Comp(fields), ty)))
+    }
+
+    fn visit_cast(&mut self, expr: ast::Node, ty: Option<Type>) -> Self::Result {
+        let lowered_expr =
+            self.visit_node(expr)?.unwrap_or_else(|| unreachable!("missing expression node in cast"));
+        Ok(Some(hir::Node::new_cast(lowered_expr, ty.unwrap_or_default())))
+    }
+
+    fn visit_variant(
+        &mut self, enum_name: String, variant: String, args: Vec<ast::Node>, ty: Option<Type>,
+    ) -> Self::Result {
+        let idx = self
+            .symbol_table
+            .get(&enum_name)
+            .unwrap_or_else(|| unreachable!("missing symbol for enum `{}` in lower", enum_name))
+            .variants()
+            .iter()
+            .position(|(name, ..)| name == &variant)
+            .unwrap_or_else(|| unreachable!("enum `{}` has no variant: `{}`", enum_name, variant))
+            .try_into()
+            .map_err(|err| format!("failed to convert variant index: `{}`", err))?;
+        let lowered_args = args
+            .into_iter()
+            .map(|n| self.visit_node(n).map(|n| n.unwrap_or_else(|| unreachable!("missing arg node"))))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Some(hir::Node::new_variant(idx, lowered_args, ty.unwrap_or_default())))
+    }
+
+    // tych has put the fields in declaration order so the names can be dropped
+    fn visit_struct_lit(
+        &mut self, _name: String, fields: Vec<(String, ast::Node)>, ty: Option<Type>,
+    ) -> Self::Result {
+        let lowered_fields = fields
+            .into_iter()
+            .map(|(_, n)| self.visit_node(n).map(|n| n.unwrap_or_else(|| unreachable!("missing field node"))))
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Some(hir::Node::new_lit(Literal::Comp(lowered_fields), ty.unwrap_or_default())))
+    }
 }
@@ -276,3 +276,311 @@ fn main() {
     ];
     run_insta!("while", tests);
 }
+
+#[test]
+fn test_trait() {
+    let tests = [
+        [
+            "generic_call",
+            r#"
+trait Len {
+    fn len() -> int
+}
+struct Foo {
+    let size: int
+}
+impl Len for Foo {
+    fn len() -> int { self.size }
+}
+fn total<T: Len>(x: T) -> int {
+    x.len()
+}
+fn main() {
+    let a: Foo
+    total(a)
+}
+"#,
+        ],
+        [
+            "dyn_call",
+            r#"
+trait Shape {
+    fn area() -> float
+    fn scale(by: float) -> float
+}
+struct Square {
+    let side: float
+}
+impl Shape for Square {
+    fn area() -> float { self.side * self.side }
+    fn scale(by: float) -> float { self.side * by }
+}
+fn main() {
+    let sq: Square
+    let s: dyn Shape = sq
+    s.scale(2.0)
+}
+"#,
+        ],
+    ];
+    run_insta!("trait", tests);
+}
+
+#[test]
+fn test_enum() {
+    let tests = [[
+        "construct",
+        r#"
+enum Shape {
+    Circle(float),
+    Rect(float, float),
+    Empty
+}
+struct Canvas {
+    let shape: Shape
+}
+fn main() {
+    let s: Shape = Shape::Rect(1.0, 2.0)
+    s = Shape::Empty
+    let t: Shape
+    let c: Canvas
+}
+"#,
+    ]];
+    run_insta!("enum", tests);
+}
+
+#[test]
+fn test_match() {
+    let tests = [[
+        "arms",
+        r#"
+enum Shape {
+    Circle(float),
+    Empty
+}
+fn area(s: Shape) -> float {
+    match s {
+        Shape::Circle(r) if r > 0.0 => r * r,
+        Shape::Circle(_) => 0.0,
+        Shape::Empty => 0.0
+    }
+}
+fn main() {
+    let x: int = 3
+    x += match x {
+        -1 => 1,
+        0..=9 => 2,
+        n => n
+    }
+}
+"#,
+    ]];
+    run_insta!("match", tests);
+}
+
+#[test]
+fn test_tuple() {
+    let tests = [[
+        "basic",
+        r#"
+fn divmod(a: int, b: int) -> (int, int) {
+    (a / b, a - a / b * b)
+}
+fn main() {
+    let t: (int, (bool, float))
+    t.1.0 = true
+    let (q, _) = divmod(7, 2)
+    let r: int = divmod(9, 4).1 + q
+}
+"#,
+    ]];
+    run_insta!("tuple", tests);
+}
+
+#[test]
+fn test_closure() {
+    let tests = [[
+        "basic",
+        r#"
+fn main() {
+    let offset: int = 5
+    let count: int
+    let add = |x: int| -> int {
+        count += 1
+        x + offset
+    }
+    let scale = move |x: int| -> int { add(x) * offset }
+    let z = scale(2)
+}
+"#,
+    ]];
+    run_insta!("closure", tests);
+}
+
+#[test]
+fn test_fn_ptr() {
+    let tests = [[
+        "basic",
+        r#"
+extern fn atexit(cb: fn()) -> int
+struct Handler {
+    let cb: fn(int) -> int
+}
+fn bye() {}
+fn double(x: int) -> int { x * 2 }
+fn main() {
+    atexit(bye)
+    let h: Handler
+    h.cb = double
+    let f = h.cb
+    f(h.cb(1))
+}
+"#,
+    ]];
+    run_insta!("fn_ptr", tests);
+}
+
+#[test]
+fn test_cast() {
+    let tests = [[
+        "basic",
+        r#"
+fn main() {
+    let x = 'a' as int + 1
+    let y = x as double
+}
+"#,
+    ]];
+    run_insta!("cast", tests);
+}
+
+#[test]
+fn test_int_sizes() {
+    let tests = [[
+        "init",
+        r#"
+struct Foo {
+    let a: isize
+    let b: uint128
+}
+fn main() {
+    let f: Foo
+    let g: usize
+}
+"#,
+    ]];
+    run_insta!("int_sizes", tests);
+}
+
+#[test]
+fn test_assert() {
+    let tests = [[
+        "basic",
+        r#"
+fn main() {
+    let x: int = 1
+    assert(x == 1)
+    debug_assert(x > 0, "positive")
+}
+"#,
+    ]];
+    run_insta!("assert", tests);
+}
+
+#[test]
+fn test_struct_lit() {
+    let tests = [
+        [
+            "basic",
+            r#"
+struct Point {
+    let x: int
+    let y: int
+}
+fn main() {
+    let y = 2
+    let p = Point { y, x: 1 }
+}
+"#,
+        ],
+        [
+            "default_field",
+            r#"
+struct Point {
+    let x: int = 1
+    let y: int
+}
+fn main() {
+    let p: Point
+    let q = Point { y: 2 }
+}
+"#,
+        ],
+        [
+            "default_field_array",
+            r#"
+struct Point {
+    let x: int = 1
+    let y: int
+}
+fn main() {
+    let a: [Point; 2]
+}
+"#,
+        ],
+    ];
+    run_insta!("struct_lit", tests);
+}
+
+#[test]
+fn test_constructor() {
+    let tests = [[
+        "init",
+        r#"
+struct Point {
+    let x: int
+    let y: int = 2
+    fn init(x: int) {
+        self.x = x
+    }
+}
+fn main() {
+    let p = Point(1)
+}
+"#,
+    ]];
+    run_insta!("constructor", tests);
+}
+
+#[test]
+fn test_method_call() {
+    let tests = [
+        [
+            "temp_receiver",
+            r#"
+struct Point {
+    let x: int
+    fn get() -> int { self.x }
+}
+fn main() {
+    let x = Point().get()
+}
+"#,
+        ],
+        [
+            "element_receiver",
+            r#"
+struct Point {
+    let x: int
+    fn get() -> int { self.x }
+}
+fn main() {
+    let pts: [Point; 2]
+    let x = pts[1].get()
+}
+"#,
+        ],
+    ];
+    run_insta!("method_call", tests);
+}
@@ -1,6 +1,6 @@
 use serde::Serialize;
 
-use common::{Literal, Operator, Prototype, Type};
+use common::{Attribute, Literal, Loc, MatchArm, Operator, Prototype, Type};
 pub use node::Node;
 
 pub mod node;
@@ -51,25 +51,53 @@ pub trait Visitor {
     ) -> Self::Result;
     fn visit_loop(&mut self, body: Node) -> Self::Result;
     fn visit_while(&mut self, cond_expr: Node, body: Node) -> Self::Result;
-    fn visit_let(&mut self, name: String, antn: Type, init: Option<Node>) -> Self::Result;
+    fn visit_let(
+        &mut self, name: String, antn: Option<Type>, init: Option<Node>, attrs: Vec<Attribute>,
+    ) -> Self::Result;
+    fn visit_destructure(&mut self, names: Vec<String>, antn: Option<Type>, init: Node) -> Self::Result;
     fn visit_fn(&mut self, proto: Prototype, body: Option<Node>) -> Self::Result;
-    fn visit_struct(&mut self, name: String, fields: Vec<Node>, methods: Vec<Node>) -> Self::Result;
+    fn visit_struct(
+        &mut self, name: String, fields: Vec<Node>, methods: Vec<Node>, attrs: Vec<Attribute>,
+    ) -> Self::Result;
+    fn visit_trait(&mut self, name: String, methods: Vec<Node>) -> Self::Result;
+    fn visit_impl(&mut self, trait_name: String, struct_name: String, methods: Vec<Node>) -> Self::Result;
+    fn visit_enum(&mut self, name: String, variants: Vec<(String, Vec<Type>, i64)>) -> Self::Result;
     fn visit_break(&mut self) -> Self::Result;
     fn visit_next(&mut self) -> Self::Result;
+    fn visit_panic(&mut self, msg: String, loc: Loc) -> Self::Result;
+    fn visit_assert(&mut self, cond: Node, msg: String, debug: bool, loc: Loc) -> Self::Result;
+    fn visit_debug(&mut self, value: Node) -> Self::Result;
+    fn visit_print(&mut self, fmt: String, args: Vec<Node>, newline: bool) -> Self::Result;
     fn visit_lit(&mut self, value: Literal<Node>, ty: Option<Type>) -> Self::Result;
     fn visit_ident(&mut self, name: String, ty: Option<Type>) -> Self::Result;
-    fn visit_binop(&mut self, op: Operator, lhs: Node, rhs: Node, ty: Option<Type>) -> Self::Result;
+    fn visit_binop(&mut self, op: Operator, lhs: Node, rhs: Node, ty: Option<Type>, loc: Loc)
+        -> Self::Result;
     fn visit_unop(&mut self, op: Operator, rhs: Node, ty: Option<Type>) -> Self::Result;
     fn visit_call(&mut self, name: String, args: Vec<Node>, ty: Option<Type>) -> Self::Result;
+    fn visit_indirect_call(&mut self, callee: Node, args: Vec<Node>, ty: Option<Type>) -> Self::Result;
     fn visit_cond(
         &mut self, cond_expr: Node, then_block: Node, else_block: Option<Node>, ty: Option<Type>,
     ) -> Self::Result;
+    fn visit_match(
+        &mut self, scrutinee: Node, arms: Vec<MatchArm<Node>>, ty: Option<Type>, loc: Loc,
+    ) -> Self::Result;
     fn visit_block(&mut self, list: Vec<Node>, ty: Option<Type>) -> Self::Result;
-    fn visit_index(&mut self, binding: Node, idx: Node, ty: Option<Type>) -> Self::Result;
+    fn visit_index(&mut self, binding: Node, idx: Node, ty: Option<Type>, loc: Loc) -> Self::Result;
     fn visit_fselector(&mut self, comp: Node, field: String, ty: Option<Type>) -> Self::Result;
     fn visit_mselector(
         &mut self, comp: Node, name: String, args: Vec<Node>, ty: Option<Type>,
     ) -> Self::Result;
+    fn visit_closure(
+        &mut self, params: Vec<(String, Type)>, ret_ty: Type, body: Node, is_move: bool,
+        captures: Vec<(String, Type)>, ty: Option<Type>,
+    ) -> Self::Result;
+    fn visit_cast(&mut self, expr: Node, ty: Option<Type>) -> Self::Result;
+    fn visit_variant(
+        &mut self, enum_name: String, variant: String, args: Vec<Node>, ty: Option<Type>,
+    ) -> Self::Result;
+    fn visit_struct_lit(
+        &mut self, name: String, fields: Vec<(String, Node)>, ty: Option<Type>,
+    ) -> Self::Result;
 }
 
 pub trait VisitableNode {
@@ -2,7 +2,7 @@ use serde::Serialize;
 use std::fmt::Display;
 
 use super::VisitableNode;
-use common::{Literal, Operator, Prototype, Type};
+use common::{Attribute, Literal, Loc, MatchArm, Operator, Prototype, Type};
 
 #[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct Node {
@@ -34,16 +34,32 @@ impl Node {
         Self { kind: Kind::While { cond_expr: Box::new(cond_expr), body: Box::new(body) } }
     }
 
-    pub fn new_let(name: String, antn: Type, init: Option<Node>) -> Self {
-        Self { kind: Kind::Let { name, antn, init: init.map(Box::new) } }
+    pub fn new_let(name: String, antn: Option<Type>, init: Option<Node>, attrs: Vec<Attribute>) -> Self {
+        Self { kind: Kind::Let { name, antn, init: init.map(Box::new), attrs } }
+    }
+
+    pub fn new_destructure(names: Vec<String>, antn: Option<Type>, init: Node) -> Self {
+        Self { kind: Kind::Destructure { names, antn, init: Box::new(init) } }
     }
 
     pub fn new_fn(proto: Prototype, body: Option<Node>) -> Self {
         Self { kind: Kind::Fn { proto, body: body.map(Box::new) } }
     }
 
-    pub fn new_struct(name: String, fields: Vec<Node>, methods: Vec<Node>) -> Self {
-        Self { kind: Kind::Struct { name, fields, methods } }
+    pub fn new_struct(name: String, fields: Vec<Node>, methods: Vec<Node>, attrs: Vec<Attribute>) -> Self {
+        Self { kind: Kind::Struct { name, fields, methods, attrs } }
+    }
+
+    pub fn new_trait(name: String, methods: Vec<Node>) -> Self {
+        Self { kind: Kind::Trait { name, methods } }
+    }
+
+    pub fn new_impl(trait_name: String, struct_name: String, methods: Vec<Node>) -> Self {
+        Self { kind: Kind::Impl { trait_name, struct_name, methods } }
+    }
+
+    pub fn new_enum(name: String, variants: Vec<(String, Vec<Type>, i64)>) -> Self {
+        Self { kind: Kind::Enum { name, variants } }
     }
 
     pub fn new_break() -> Self {
@@ -54,6 +70,22 @@ impl Node {
         Self { kind: Kind::Next }
     }
 
+    pub fn new_panic(msg: String, loc: Loc) -> Self {
+        Self { kind: Kind::Panic { msg, loc } }
+    }
+
+    pub fn new_assert(cond: Node, msg: String, debug: bool, loc: Loc) -> Self {
+        Self { kind: Kind::Assert { cond: Box::new(cond), msg, debug, loc } }
+    }
+
+    pub fn new_debug(value: Node) -> Self {
+        Self { kind: Kind::Debug { value: Box::new(value) } }
+    }
+
+    pub fn new_print(fmt: String, args: Vec<Node>, newline: bool) -> Self {
+        Self { kind: Kind::Print { fmt, args, newline } }
+    }
+
     pub fn new_lit(value: Literal<Node>, ty: Option<Type>) -> Self {
         Self { kind: Kind::Lit { value, ty } }
     }
@@ -62,8 +94,8 @@ impl Node {
         Self { kind: Kind::Ident { name, ty } }
     }
 
-    pub fn new_binop(op: Operator, lhs: Node, rhs: Node, ty: Option<Type>) -> Self {
-        Self { kind: Kind::BinOp { op, lhs: Box::new(lhs), rhs: Box::new(rhs), ty } }
+    pub fn new_binop(op: Operator, lhs: Node, rhs: Node, ty: Option<Type>, loc: Loc) -> Self {
+        Self { kind: Kind::BinOp { op, lhs: Box::new(lhs), rhs: Box::new(rhs), ty, loc } }
     }
 
     pub fn new_unop(op: Operator, rhs: Node, ty: Option<Type>) -> Self {
@@ -74,6 +106,10 @@ impl Node {
         Self { kind: Kind::Call { name, args, ty } }
     }
 
+    pub fn new_indirect_call(callee: Node, args: Vec<Node>, ty: Option<Type>) -> Self {
+        Self { kind: Kind::IndirectCall { callee: Box::new(callee), args, ty } }
+    }
+
     pub fn new_cond(cond_expr: Node, then_block: Node, else_block: Option<Node>, ty: Option<Type>) -> Self {
         Self {
             kind: Kind::Cond {
@@ -85,22 +121,45 @@ impl Node {
         }
     }
 
+    pub fn new_match(scrutinee: Node, arms: Vec<MatchArm<Node>>, ty: Option<Type>, loc: Loc) -> Self {
+        Self { kind: Kind::Match { scrutinee: Box::new(scrutinee), arms, ty, loc } }
+    }
+
     pub fn new_block(list: Vec<Node>, ty: Option<Type>) -> Self {
         Self { kind: Kind::Block { list, ty } }
     }
 
-    pub fn new_index(array: Node, idx: Node, ty: Option<Type>) -> Self {
-        Self { kind: Kind::Index { array: Box::new(array), idx: Box::new(idx), ty } }
+    pub fn new_index(array: Node, idx: Node, ty: Option<Type>, loc: Loc) -> Self {
+        Self { kind: Kind::Index { array: Box::new(array), idx: Box::new(idx), ty, loc } }
     }
 
     pub fn new_fselector(comp: Node, field: String, ty: Option<Type>) -> Self {
         Self { kind: Kind::FSelector { comp: Box::new(comp), field, ty } }
     }
 
+    pub fn new_closure(
+        params: Vec<(String, Type)>, ret_ty: Type, body: Node, is_move: bool, captures: Vec<(String, Type)>,
+        ty: Option<Type>,
+    ) -> Self {
+        Self { kind: Kind::Closure { params, ret_ty, body: Box::new(body), is_move, captures, ty } }
+    }
+
     pub fn new_mselector(comp: Node, name: String, args: Vec<Node>, ty: Option<Type>) -> Self {
         Self { kind: Kind::MSelector { comp: Box::new(comp), name, args, ty } }
     }
 
+    pub fn new_cast(expr: Node, ty: Option<Type>) -> Self {
+        Self { kind: Kind::Cast { expr: Box::new(expr), ty } }
+    }
+
+    pub fn new_variant(enum_name: String, variant: String, args: Vec<Node>, ty: Option<Type>) -> Self {
+        Self { kind: Kind::Variant { enum_name, variant, args, ty } }
+    }
+
+    pub fn new_struct_lit(name: String, fields: Vec<(String, Node)>, ty: Option<Type>) -> Self {
+        Self { kind: Kind::StructLit { name, fields, ty } }
+    }
+
     pub fn new_blank() -> Self {
         Self { kind: Kind::Blank }
     }
@@ -114,11 +173,17 @@ impl Node {
             BinOp { ty, .. } => ty.as_ref(),
             UnOp { ty, .. } => ty.as_ref(),
             Call { ty, .. } => ty.as_ref(),
+            IndirectCall { ty, .. } => ty.as_ref(),
             Cond { ty, .. } => ty.as_ref(),
+            Match { ty, .. } => ty.as_ref(),
             Block { ty, .. } => ty.as_ref(),
             Index { ty, .. } => ty.as_ref(),
             FSelector { ty, .. } => ty.as_ref(),
             MSelector { ty, .. } => ty.as_ref(),
+            Closure { ty, .. } => ty.as_ref(),
+            Cast { ty, .. } => ty.as_ref(),
+            Variant { ty, .. } => ty.as_ref(),
+            StructLit { ty, .. } => ty.as_ref(),
             _ => None,
         }
     }
@@ -132,11 +197,17 @@ impl Node {
             BinOp { ty, .. } => *ty = Some(new_ty),
             UnOp { ty, .. } => *ty = Some(new_ty),
             Call { ty, .. } => *ty = Some(new_ty),
+            IndirectCall { ty, .. } => *ty = Some(new_ty),
             Cond { ty, .. } => *ty = Some(new_ty),
+            Match { ty, .. } => *ty = Some(new_ty),
             Block { ty, .. } => *ty = Some(new_ty),
             Index { ty, .. } => *ty = Some(new_ty),
             FSelector { ty, .. } => *ty = Some(new_ty),
             MSelector { ty, .. } => *ty = Some(new_ty),
+            Closure { ty, .. } => *ty = Some(new_ty),
+            Cast { ty, .. } => *ty = Some(new_ty),
+            Variant { ty, .. } => *ty = Some(new_ty),
+            StructLit { ty, .. } => *ty = Some(new_ty),
             _ => unreachable!("can't set type on statement"),
         }
     }
@@ -151,10 +222,14 @@ impl Node {
                     | Int16(_)
                     | Int32(_)
                     | Int64(_)
+                    | Int128(_)
+                    | ISize(_)
                     | UInt8(_)
                     | UInt16(_)
                     | UInt32(_)
                     | UInt64(_)
+                    | UInt128(_)
+                    | USize(_)
                     | Float(_)
                     | Double(_),
                 ..
@@ -187,8 +262,15 @@ pub enum Kind {
     },
     Let {
         name: String,
-        antn: Type,
+        antn: Option<Type>,
         init: Option<Box<Node>>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        attrs: Vec<Attribute>,
+    },
+    Destructure {
+        names: Vec<String>,
+        antn: Option<Type>,
+        init: Box<Node>,
     },
     Fn {
         proto: Prototype,
@@ -198,9 +280,44 @@ pub enum Kind {
         name: String,
         fields: Vec<Node>,
         methods: Vec<Node>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        attrs: Vec<Attribute>,
+    },
+    Trait {
+        name: String,
+        methods: Vec<Node>,
+    },
+    Impl {
+        trait_name: String,
+        struct_name: String,
+        methods: Vec<Node>,
+    },
+    Enum {
+        name: String,
+        variants: Vec<(String, Vec<Type>, i64)>,
     },
     Break,
     Next,
+    Panic {
+        msg: String,
+        #[serde(skip)]
+        loc: Loc,
+    },
+    Assert {
+        cond: Box<Node>,
+        msg: String,
+        debug: bool,
+        #[serde(skip)]
+        loc: Loc,
+    },
+    Debug {
+        value: Box<Node>,
+    },
+    Print {
+        fmt: String,
+        args: Vec<Node>,
+        newline: bool,
+    },
 
     // Expressions
     Lit {
@@ -216,6 +333,8 @@ pub enum Kind {
         lhs: Box<Node>,
         rhs: Box<Node>,
         ty: Option<Type>,
+        #[serde(skip)]
+        loc: Loc,
     },
     UnOp {
         op: Operator,
@@ -227,12 +346,26 @@ pub enum Kind {
         args: Vec<Node>,
         ty: Option<Type>,
     },
+    // A call through a function pointer. Only made by the tych, from calls on a variable
+    // or field that holds one
+    IndirectCall {
+        callee: Box<Node>,
+        args: Vec<Node>,
+        ty: Option<Type>,
+    },
     Cond {
         cond_expr: Box<Node>,
         then_block: Box<Node>,
         else_block: Option<Box<Node>>,
         ty: Option<Type>,
     },
+    Match {
+        scrutinee: Box<Node>,
+        arms: Vec<MatchArm<Node>>,
+        ty: Option<Type>,
+        #[serde(skip)]
+        loc: Loc,
+    },
     Block {
         list: Vec<Node>,
         ty: Option<Type>,
@@ -241,6 +374,8 @@ pub enum Kind {
         array: Box<Node>,
         idx: Box<Node>,
         ty: Option<Type>,
+        #[serde(skip)]
+        loc: Loc,
     },
     FSelector {
         comp: Box<Node>,
@@ -253,6 +388,30 @@ pub enum Kind {
         args: Vec<Node>,
         ty: Option<Type>,
     },
+    // `captures` is filled in by the tych with the closure's environment
+    Closure {
+        params: Vec<(String, Type)>,
+        ret_ty: Type,
+        body: Box<Node>,
+        is_move: bool,
+        captures: Vec<(String, Type)>,
+        ty: Option<Type>,
+    },
+    Cast {
+        expr: Box<Node>,
+        ty: Option<Type>,
+    },
+    Variant {
+        enum_name: String,
+        variant: String,
+        args: Vec<Node>,
+        ty: Option<Type>,
+    },
+    StructLit {
+        name: String,
+        fields: Vec<(String, Node)>,
+        ty: Option<Type>,
+    },
     Blank,
 }
 
@@ -266,23 +425,39 @@ impl VisitableNode for Node {
             },
             Loop { body } => v.visit_loop(*body),
             While { cond_expr, body } => v.visit_while(*cond_expr, *body),
-            Let { name, antn, init } => v.visit_let(name, antn, init.map(|x| *x)),
+            Let { name, antn, init, attrs } => v.visit_let(name, antn, init.map(|x| *x), attrs),
+            Destructure { names, antn, init } => v.visit_destructure(names, antn, *init),
             Fn { proto, body } => v.visit_fn(proto, body.map(|x| *x)),
-            Struct { name, fields, methods } => v.visit_struct(name, fields, methods),
+            Struct { name, fields, methods, attrs } => v.visit_struct(name, fields, methods, attrs),
+            Trait { name, methods } => v.visit_trait(name, methods),
+            Impl { trait_name, struct_name, methods } => v.visit_impl(trait_name, struct_name, methods),
+            Enum { name, variants } => v.visit_enum(name, variants),
             Break => v.visit_break(),
             Next => v.visit_next(),
+            Panic { msg, loc } => v.visit_panic(msg, loc),
+            Assert { cond, msg, debug, loc } => v.visit_assert(*cond, msg, debug, loc),
+            Debug { value } => v.visit_debug(*value),
+            Print { fmt, args, newline } => v.visit_print(fmt, args, newline),
             Lit { value, ty } => v.visit_lit(value, ty),
             Ident { name, ty } => v.visit_ident(name, ty),
-            BinOp { op, lhs, rhs, ty } => v.visit_binop(op, *lhs, *rhs, ty),
+            BinOp { op, lhs, rhs, ty, loc } => v.visit_binop(op, *lhs, *rhs, ty, loc),
             UnOp { op, rhs, ty } => v.visit_unop(op, *rhs, ty),
             Call { name, args, ty } => v.visit_call(name, args, ty),
+            IndirectCall { callee, args, ty } => v.visit_indirect_call(*callee, args, ty),
             Cond { cond_expr, then_block, else_block, ty } => {
                 v.visit_cond(*cond_expr, *then_block, else_block.map(|x| *x), ty)
             },
+            Match { scrutinee, arms, ty, loc } => v.visit_match(*scrutinee, arms, ty, loc),
             Block { list, ty } => v.visit_block(list, ty),
-            Index { array: binding, idx, ty } => v.visit_index(*binding, *idx, ty),
+            Index { array: binding, idx, ty, loc } => v.visit_index(*binding, *idx, ty, loc),
             FSelector { comp, field, ty } => v.visit_fselector(*comp, field, ty),
             MSelector { comp, name, args, ty } => v.visit_mselector(*comp, name, args, ty),
+            Closure { params, ret_ty, body, is_move, captures, ty } => {
+                v.visit_closure(params, ret_ty, *body, is_move, captures, ty)
+            },
+            Cast { expr, ty } => v.visit_cast(*expr, ty),
+            Variant { enum_name, variant, args, ty } => v.visit_variant(enum_name, variant, args, ty),
+            StructLit { name, fields, ty } => v.visit_struct_lit(name, fields, ty),
             Blank => unreachable!("invalid node kind visited"),
         }
     }
@@ -302,18 +477,26 @@ impl Display for Node {
             },
             Loop { body } => write!(f, "(loop {})", body),
             While { cond_expr, body } => write!(f, "(while {} {})", cond_expr, body),
-            Let { name, antn, init } => {
-                let mut s = format!("(let {}:{}", name, antn);
+            Let { name, antn, init, attrs } => {
+                let mut s = format!("(let {}", attrs_string(attrs));
+                s += &match antn {
+                    Some(antn) => format!("{}:{}", name, antn),
+                    None => name.to_owned(),
+                };
                 if let Some(body) = &init {
                     s += &format!(" {}", body);
                 }
                 write!(f, "{})", s)
             },
+            Destructure { names, antn, init } => match antn {
+                Some(antn) => write!(f, "(let ({}):{} {})", names.join(", "), antn, init),
+                None => write!(f, "(let ({}) {})", names.join(", "), init),
+            },
             Fn { proto, body } => match &body {
                 Some(body) => write!(f, "(define {} {})", proto, body),
                 _ => write!(f, "(define {})", proto),
             },
-            Struct { name, fields, methods } => {
+            Struct { name, fields, methods, attrs } => {
                 let mut attr_string = String::from("");
                 attr_string += &fields.iter().fold(String::new(), |mut acc, n| {
                     acc += &format!("{} ", n);
@@ -328,14 +511,51 @@ impl Display for Node {
 
                 write!(
                     f,
-                    "(struct {} '({}) '({}))",
+                    "(struct {}{} '({}) '({}))",
+                    attrs_string(attrs),
                     name,
                     attr_string.strip_suffix(' ').unwrap_or(""),
                     meth_string.strip_suffix(' ').unwrap_or("")
                 )
             },
+            Trait { name, methods } => {
+                let meth_string = methods.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(" ");
+                write!(f, "(trait {} '({}))", name, meth_string)
+            },
+            Impl { trait_name, struct_name, methods } => {
+                let meth_string = methods.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(" ");
+                write!(f, "(impl {} {} '({}))", trait_name, struct_name, meth_string)
+            },
+            Enum { name, variants } => {
+                let var_string = variants
+                    .iter()
+                    .map(|(name, tys, discriminant)| match tys.is_empty() {
+                        true => format!("{}={}", name, discriminant),
+                        false => format!(
+                            "{}({})={}",
+                            name,
+                            tys.iter().map(|ty| ty.to_string()).collect::<Vec<_>>().join(" "),
+                            discriminant
+                        ),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                write!(f, "(enum {} '({}))", name, var_string)
+            },
             Break => write!(f, "break"),
             Next => write!(f, "next"),
+            Panic { msg, .. } => write!(f, "panic(\"{}\")", msg),
+            Assert { cond, msg, debug, .. } => {
+                write!(f, "({} {} \"{}\")", if *debug { "debug_assert" } else { "assert" }, cond, msg)
+            },
+            Debug { value } => write!(f, "(debug {})", value),
+            Print { fmt, args, newline } => {
+                let mut s = format!("({} \"{}\"", if *newline { "println" } else { "print" }, fmt);
+                for arg in args {
+                    s += &format!(" {}", arg);
+                }
+                write!(f, "{})", s)
+            },
             Lit { value, .. } => write!(f, "{}", value),
             Ident { name, .. } => write!(f, "{}", name),
             BinOp { op, lhs, rhs, .. } => write!(f, "({} {} {})", op, lhs, rhs),
@@ -349,6 +569,13 @@ impl Display for Node {
                 }
                 write!(f, "{})", s)
             },
+            IndirectCall { callee, args, .. } => {
+                let mut s = format!("(*{}", callee);
+                for arg in args {
+                    s += &format!(" {}", arg);
+                }
+                write!(f, "{})", s)
+            },
             Cond { cond_expr, then_block, else_block, .. } => {
                 let mut s = format!("(if {} {}", cond_expr, then_block);
                 if let Some(alt) = &else_block {
@@ -356,6 +583,10 @@ impl Display for Node {
                 }
                 write!(f, "{})", s)
             },
+            Match { scrutinee, arms, .. } => {
+                let arm_string = arms.iter().map(|arm| arm.to_string()).collect::<Vec<_>>().join(" ");
+                write!(f, "(match {} '({}))", scrutinee, arm_string)
+            },
             Block { list, .. } => {
                 let mut s = "'(".to_string();
                 s += &list.iter().fold(String::new(), |mut acc, n| {
@@ -375,7 +606,39 @@ impl Display for Node {
                 }
                 write!(f, "{})", s)
             },
+            Closure { params, ret_ty, body, is_move, .. } => {
+                let param_string =
+                    params.iter().map(|(name, ty)| format!("{}:{}", name, ty)).collect::<Vec<_>>().join(" ");
+                let move_string = if *is_move { "move " } else { "" };
+                write!(f, "(closure {}({}) {} {})", move_string, param_string, ret_ty, body)
+            },
+            Cast { expr, ty } => match ty {
+                Some(ty) => write!(f, "(as {} {})", expr, ty),
+                None => write!(f, "(as {})", expr),
+            },
+            Variant { enum_name, variant, args, .. } => {
+                let mut s = format!("({}::{}", enum_name, variant);
+                for arg in args {
+                    s += &format!(" {}", arg);
+                }
+                write!(f, "{})", s)
+            },
+            StructLit { name, fields, .. } => {
+                let mut s = format!("({}", name);
+                for (field, value) in fields {
+                    s += &format!(" {}:{}", field, value);
+                }
+                write!(f, "{})", s)
+            },
             Blank => write!(f, "<blank_node>"),
         }
     }
 }
+
+// Attributes are shown ahead of the name they're on
+fn attrs_string(attrs: &[Attribute]) -> String {
+    attrs.iter().fold(String::new(), |mut acc, attr| {
+        acc += &format!("{} ", attr);
+        acc
+    })
+}
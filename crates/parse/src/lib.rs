@@ -4,7 +4,10 @@ use std::slice::Iter;
 
 use crate::ast::node;
 use ast::Ast;
-use common::{literal::Literal, Operator, Prototype, Symbol, SymbolTable, Type};
+use common::symbol_table::{StructRepr, Symbolic};
+use common::{
+    literal::Literal, Attribute, Loc, MatchArm, Operator, Pattern, Prototype, Symbol, SymbolTable, Type,
+};
 use errors::ParseError;
 use lex::{Token, TokenType};
 use precedence::OpPrec;
@@ -22,24 +25,41 @@ extern crate common;
 
 type ParseResult = Result<ast::Node, ParseError>;
 
+// The attributes a declaration can have
+const ATTRIBUTES: [&str; 8] =
+    ["repr", "align", "inline", "noinline", "cold", "export_name", "deprecated", "must_use"];
+
 pub struct Parse<'a> {
     tokens: Peekable<Iter<'a, Token>>,
     symbol_table: &'a mut SymbolTable<Symbol>,
+    file: String,
     module: String,
     current_struct: Option<String>,
     imports: Vec<String>,
+    impls: Vec<(String, String, Vec<String>, Token)>,
     errors: Vec<ParseError>,
+    no_struct_lit: bool,
+    attrs: Vec<Attribute>,
 }
 
 impl<'a> Parse<'a> {
     pub fn new(tokens: &'a [Token], symbol_table: &'a mut SymbolTable<Symbol>) -> Self {
+        Parse::with_file(tokens, symbol_table, "")
+    }
+
+    // The file name is only used to report runtime errors
+    pub fn with_file(tokens: &'a [Token], symbol_table: &'a mut SymbolTable<Symbol>, file: &str) -> Self {
         Parse {
             tokens: tokens.iter().peekable(),
             symbol_table,
+            file: file.to_owned(),
             module: String::new(),
             current_struct: None,
             imports: vec![],
+            impls: vec![],
             errors: vec![],
+            no_struct_lit: false,
+            attrs: vec![],
         }
     }
 
@@ -67,6 +87,10 @@ impl<'a> Parse<'a> {
                 _ => continue,
             };
         }
+
+        // Now that all structs are known, attach the trait implementations
+        self.add_impls_to_structs();
+
         if self.errors.is_empty() {
             Ok((ast, self.module, self.imports))
         } else {
@@ -77,7 +101,8 @@ impl<'a> Parse<'a> {
     /// Statement productions
 
     // Stmt ::= LetStmt | ForStmt | LoopStmt | WhileStmt | FnDecl | ExternDecl
-    //          | StructDecl | UseStmt | BreakStmt | NextStmt | Expr ;
+    //          | StructDecl | EnumDecl | TraitDecl | ImplDecl | UseStmt | BreakStmt
+    //          | NextStmt | PanicStmt | AssertStmt | DebugStmt | PrintStmt | Expr ;
 
     fn parse_stmt(&mut self) -> ParseResult {
         use TokenType::*;
@@ -92,9 +117,17 @@ impl<'a> Parse<'a> {
             Fn => self.parse_fn()?,
             Extern => self.parse_extern()?,
             Struct => self.parse_struct()?,
+            Hash => self.parse_attributed()?,
+            Enum => self.parse_enum()?,
+            Trait => self.parse_trait()?,
+            Impl => self.parse_impl()?,
             Use => self.parse_use()?,
             Break => self.parse_break()?,
             Next => self.parse_next()?,
+            Panic => self.parse_panic()?,
+            Assert(_) => self.parse_assert()?,
+            Debug => self.parse_debug()?,
+            Print(_) => self.parse_print()?,
             _ => self.parse_expr(0)?,
         };
 
@@ -106,8 +139,133 @@ impl<'a> Parse<'a> {
         Ok(stmt)
     }
 
-    // StructDecl ::= 'struct' ident '{' ( LetStmt ';' | FnDecl ';' )* '}' ;
+    // Attributes are held until the declaration after them takes them
+    fn parse_attributed(&mut self) -> ParseResult {
+        self.attrs = self.parse_attrs()?;
+        match self.tokens.peek() {
+            Some(Token { tt: TokenType::Fn, .. }) => self.parse_fn(),
+            Some(Token { tt: TokenType::Extern, .. }) => self.parse_extern(),
+            Some(Token { tt: TokenType::Struct, .. }) => self.parse_struct(),
+            Some(Token { tt: TokenType::Let, .. }) => self.parse_let(),
+            t => {
+                let t = t.cloned().cloned().unwrap_or_default();
+                Err(ParseError::from((
+                    format!("Expecting `fn`, `extern`, `struct`, or `let` after attributes. Got `{}`", t.tt),
+                    &t,
+                )))
+            },
+        }
+    }
+
+    // Attr    ::= '#' '[' ident ( '(' AttrArg ( ',' AttrArg )* ')' )? ']' ;
+    // AttrArg ::= ident | number | string ;
+    fn parse_attrs(&mut self) -> Result<Vec<Attribute>, ParseError> {
+        let mut attrs: Vec<Attribute> = vec![];
+        while let Some(Token { tt: TokenType::Hash, .. }) = self.tokens.peek() {
+            self.tokens.next(); // Eat #
+            expect_next_token!(self.tokens, TokenType::OpenBracket, "Expecting `[` to start attribute");
+            let (name, name_token) =
+                expect_next_token!(self.tokens, TokenType::Ident(_), "Expecting attribute name");
+            if !ATTRIBUTES.contains(&name.as_str()) {
+                return Err(ParseError::from((format!("Unknown attribute `{}`", name), name_token)));
+            }
+
+            let mut args = vec![];
+            if let Some(Token { tt: TokenType::OpenParen, .. }) = self.tokens.peek() {
+                self.tokens.next(); // Eat (
+                loop {
+                    match self.tokens.next() {
+                        Some(
+                            t @ Token {
+                                tt: TokenType::Ident(arg) | TokenType::Num(arg) | TokenType::Str(arg),
+                                ..
+                            },
+                        ) => args.push((arg.to_owned(), t)),
+                        t => {
+                            let t = t.cloned().unwrap_or_default();
+                            return Err(ParseError::from((
+                                format!(
+                                    "Expecting identifier, number, or string in `{}`. Got `{}`",
+                                    name, t.tt
+                                ),
+                                &t,
+                            )));
+                        },
+                    }
+                    match self.tokens.next() {
+                        Some(Token { tt: TokenType::Comma, .. }) => continue,
+                        Some(Token { tt: TokenType::CloseParen, .. }) => break,
+                        t => {
+                            let t = t.cloned().unwrap_or_default();
+                            return Err(ParseError::from((
+                                format!("Expecting `,` or `)` in `{}`. Got `{}`", name, t.tt),
+                                &t,
+                            )));
+                        },
+                    }
+                }
+            }
+            expect_next_token!(self.tokens, TokenType::CloseBracket, "Expecting `]` to end attribute");
+
+            Self::check_attr_args(name, &args, name_token)?;
+            attrs.push(Attribute::new(name, args.into_iter().map(|(arg, _)| arg).collect()));
+            let repr = StructRepr::from(attrs.as_slice());
+            if repr.packed && repr.align.is_some() {
+                return Err(ParseError::from((
+                    "A struct can't be both packed and aligned".to_string(),
+                    name_token,
+                )));
+            }
+
+            // Attributes usually end their line
+            token_is_and_then!(self.tokens.peek(), TokenType::Semicolon(true), {
+                self.tokens.next(); // Eat semicolon
+            });
+        }
+        Ok(attrs)
+    }
+
+    // Each attribute's args, checked against what it expects
+    fn check_attr_args(name: &str, args: &[(String, &Token)], name_token: &Token) -> Result<(), ParseError> {
+        let is_str = |(_, t): &(String, &Token)| matches!(t.tt, TokenType::Str(_));
+        match (name, args) {
+            ("repr", []) => {
+                Err(ParseError::from(("Expecting `C` or `packed` in `repr`".to_string(), name_token)))
+            },
+            ("repr", args) => match args.iter().find(|(hint, _)| hint != "C" && hint != "packed") {
+                Some((hint, hint_token)) => Err(ParseError::from((
+                    format!("Unknown `repr` hint `{}`. Expecting `C` or `packed`", hint),
+                    *hint_token,
+                ))),
+                None => Ok(()),
+            },
+            ("align", [(num, num_token)]) => match num.parse::<u32>() {
+                Ok(align) if align.is_power_of_two() => Ok(()),
+                _ => Err(ParseError::from((
+                    format!("Alignment must be a power of two. Got `{}`", num),
+                    *num_token,
+                ))),
+            },
+            ("align", _) => {
+                Err(ParseError::from(("Expecting alignment in bytes in `align`".to_string(), name_token)))
+            },
+            ("export_name", [arg]) if is_str(arg) => Ok(()),
+            ("export_name", _) => {
+                Err(ParseError::from(("Expecting a string name in `export_name`".to_string(), name_token)))
+            },
+            ("deprecated", []) => Ok(()),
+            ("deprecated", [arg]) if is_str(arg) => Ok(()),
+            ("deprecated", _) => {
+                Err(ParseError::from(("Expecting a string message in `deprecated`".to_string(), name_token)))
+            },
+            (_, []) => Ok(()),
+            (name, _) => Err(ParseError::from((format!("`{}` doesn't take arguments", name), name_token))),
+        }
+    }
+
+    // StructDecl ::= Attr* 'struct' ident '{' ( LetStmt ';' | 'static'? FnDecl ';' )* '}' ;
     fn parse_struct(&mut self) -> ParseResult {
+        let attrs = std::mem::take(&mut self.attrs);
         self.tokens.next(); // Eat struct
 
         let (name, token) =
@@ -127,7 +285,8 @@ impl<'a> Parse<'a> {
                     // Collect all fields for struct symbol table entry
                     let mut sym_fields = vec![];
                     for node in &fields {
-                        if let ast::Node { kind: ast::node::Kind::Let { name, antn, .. } } = node {
+                        if let ast::Node { kind: ast::node::Kind::Let { name, antn: Some(antn), .. } } = node
+                        {
                             sym_fields.push((name.to_owned(), antn.to_string()));
                         }
                     }
@@ -136,6 +295,10 @@ impl<'a> Parse<'a> {
                     let mut sym_methods = vec![];
                     for node in methods.iter_mut() {
                         if let ast::Node { kind: ast::node::Kind::Fn { proto, .. } } = node {
+                            // Associated functions are called through the struct's name
+                            if proto.member_of().is_none() {
+                                continue;
+                            }
                             // TODO: remove this when `orig_name` becomes part of Prototype
                             let simple_name = proto.name().split('_').nth(2).unwrap_or_else(|| {
                                 unreachable!("couldn't split prototype name in `parse_struct()`")
@@ -145,27 +308,31 @@ impl<'a> Parse<'a> {
                     }
 
                     // Insert struct into symbol table
-                    if self
-                        .symbol_table
-                        .insert(Symbol::new_struct(
-                            &full_name,
-                            Some(&sym_fields),
-                            Some(&sym_methods),
-                            &self.module,
-                            true,
-                        ))
-                        .is_some()
-                    {
+                    let mut sym = Symbol::new_struct(
+                        &full_name,
+                        Some(&sym_fields),
+                        Some(&sym_methods),
+                        &self.module,
+                        true,
+                    );
+                    sym.set_attrs(&attrs);
+                    if self.symbol_table.insert(sym).is_some() {
                         return Err(ParseError::from((
                             format!("struct `{}` already defined", full_name),
                             token,
                         )));
                     }
 
-                    return Ok(ast::Node::new_struct(full_name, fields, methods));
+                    return Ok(ast::Node::new_struct(full_name, fields, methods, attrs));
                 },
                 TokenType::Let => {
+                    let let_token = *t;
                     match self.parse_let() {
+                        Ok(ast::Node { kind: ast::node::Kind::Let { name, antn: None, .. } }) => self
+                            .push_err(ParseError::from((
+                                format!("Expecting type annotation for struct field `{}`", name),
+                                let_token,
+                            ))),
                         Ok(l) => fields.push(l),
                         Err(e) => self.push_err(e),
                     }
@@ -173,10 +340,15 @@ impl<'a> Parse<'a> {
                         self.tokens.next(); // Eat semicolon
                     });
                 },
-                TokenType::Fn => {
+                TokenType::Fn | TokenType::Static => {
+                    // A `static` without a `fn` is reported and skipped, so the member after
+                    // it still parses
+                    let stray_static = t.tt == TokenType::Static
+                        && !matches!(self.tokens.clone().nth(1), Some(Token { tt: TokenType::Fn, .. }));
                     self.current_struct = Some(full_name.to_owned());
                     match self.parse_fn() {
                         Ok(f) => methods.push(f),
+                        Err(e) if stray_static => self.errors.push(e),
                         Err(e) => self.push_err(e),
                     }
                     token_is_and_then!(self.tokens.peek(), TokenType::Semicolon(_), {
@@ -184,6 +356,10 @@ impl<'a> Parse<'a> {
                     });
                     self.current_struct = None;
                 },
+                TokenType::Hash => match self.parse_attrs() {
+                    Ok(attrs) => self.attrs = attrs,
+                    Err(e) => self.push_err(e),
+                },
                 tt => {
                     // Do not propagate a ParseError within a struct or the struct will
                     // not be parsed, causing incorrect errors
@@ -209,6 +385,293 @@ impl<'a> Parse<'a> {
         Err(ParseError::from("Expecting `}` to terminate struct definition".to_string()))
     }
 
+    // EnumDecl ::= 'enum' ident '{' ( Variant ( ',' Variant )* ','? )? '}' ;
+    // Variant  ::= ident ( '(' TypeAntn ( ',' TypeAntn )* ')' )? ( '=' '-'? number )? ;
+    fn parse_enum(&mut self) -> ParseResult {
+        self.tokens.next(); // Eat enum
+
+        let (name, token) =
+            expect_next_token!(self.tokens, TokenType::Ident(_), "Expecting enum name in declaration");
+
+        let full_name = format!("{}::{}", self.module, name);
+
+        expect_next_token!(self.tokens, TokenType::OpenBrace, "Expecting `{` to start enum block");
+
+        let mut variants: Vec<(String, Vec<Type>, i64)> = vec![];
+        let mut discriminant = 0;
+        loop {
+            let (variant, var_token) = match self.tokens.next() {
+                Some(Token { tt: TokenType::CloseBrace, .. }) => break,
+                Some(t @ Token { tt: TokenType::Ident(variant), .. }) => (variant.to_owned(), t),
+                Some(t) => {
+                    return Err(ParseError::from((
+                        format!("Expecting variant name in enum definition. Got `{}`", t.tt),
+                        t,
+                    )))
+                },
+                None => {
+                    return Err(ParseError::from("Expecting `}` to terminate enum definition".to_string()))
+                },
+            };
+            if variants.iter().any(|(name, ..)| name == &variant) {
+                return Err(ParseError::from((
+                    format!("variant `{}` is already defined in enum `{}`", variant, full_name),
+                    var_token,
+                )));
+            }
+
+            // Optional payload types
+            let mut tys = vec![];
+            if let Some(Token { tt: TokenType::OpenParen, .. }) = self.tokens.peek() {
+                self.tokens.next(); // Eat open paren
+                loop {
+                    tys.push(self.parse_type_antn("enum variant")?);
+                    match self.tokens.next() {
+                        Some(Token { tt: TokenType::Comma, .. }) => continue,
+                        Some(Token { tt: TokenType::CloseParen, .. }) => break,
+                        t => {
+                            let t = t.cloned().unwrap_or_default();
+                            return Err(ParseError::from((
+                                format!("Expecting `,` or `)` in variant `{}`. Got `{}`", variant, t.tt),
+                                &t,
+                            )));
+                        },
+                    }
+                }
+            }
+
+            // Optional explicit discriminant. Following variants count up from it
+            if let Some(Token { tt: TokenType::Op(Operator::Assign), .. }) = self.tokens.peek() {
+                self.tokens.next(); // Eat '='
+                discriminant = self.parse_discriminant(&variant)?;
+            }
+            if let Some((dup, ..)) = variants.iter().find(|(.., d)| *d == discriminant) {
+                return Err(ParseError::from((
+                    format!(
+                        "discriminant `{}` of variant `{}` is already used by `{}`",
+                        discriminant, variant, dup
+                    ),
+                    var_token,
+                )));
+            }
+            variants.push((variant, tys, discriminant));
+            discriminant += 1;
+
+            // Variants are separated by commas. Newlines without one get an implicit semicolon
+            match self.tokens.peek() {
+                Some(Token { tt: TokenType::Comma, .. }) => {
+                    self.tokens.next(); // Eat comma
+                    token_is_and_then!(self.tokens.peek(), TokenType::Semicolon(true), {
+                        self.tokens.next(); // Eat semicolon
+                    });
+                },
+                Some(Token { tt: TokenType::Semicolon(true), .. }) => {
+                    self.tokens.next(); // Eat semicolon
+                },
+                Some(Token { tt: TokenType::CloseBrace, .. }) => (),
+                Some(t) => {
+                    return Err(ParseError::from((
+                        format!("Expecting `,` or `}}` in enum definition. Got `{}`", t.tt),
+                        *t,
+                    )))
+                },
+                None => (),
+            }
+        }
+
+        if variants.is_empty() {
+            return Err(ParseError::from((
+                format!("enum `{}` must have at least one variant", full_name),
+                token,
+            )));
+        }
+
+        let sym_variants = variants
+            .iter()
+            .map(|(name, tys, discriminant)| {
+                (name.to_owned(), tys.iter().map(|ty| ty.to_string()).collect(), *discriminant)
+            })
+            .collect::<Vec<_>>();
+        if self.symbol_table.insert(Symbol::new_enum(&full_name, &sym_variants, &self.module, true)).is_some()
+        {
+            return Err(ParseError::from((format!("enum `{}` already defined", full_name), token)));
+        }
+
+        Ok(ast::Node::new_enum(full_name, variants))
+    }
+
+    // Discriminants are stored in an `int32` tag
+    fn parse_discriminant(&mut self, variant: &str) -> Result<i64, ParseError> {
+        let is_neg = matches!(self.tokens.peek(), Some(Token { tt: TokenType::Op(Operator::Sub), .. }));
+        if is_neg {
+            self.tokens.next(); // Eat '-'
+        }
+
+        let (num, token) = expect_next_token!(
+            self.tokens,
+            TokenType::Num(_),
+            format!("Expecting integer discriminant for variant `{}`", variant)
+        );
+        let discriminant = num
+            .parse::<i64>()
+            .ok()
+            .map(|n| if is_neg { -n } else { n })
+            .filter(|n| i32::try_from(*n).is_ok())
+            .ok_or(ParseError::from((
+                format!("Discriminant for variant `{}` isn't a valid `int32`: {}", variant, token),
+                token,
+            )))?;
+        Ok(discriminant)
+    }
+
+    // TraitDecl ::= 'trait' ident '{' ( Prototype ';' )* '}' ;
+    fn parse_trait(&mut self) -> ParseResult {
+        self.tokens.next(); // Eat trait
+
+        let (name, token) =
+            expect_next_token!(self.tokens, TokenType::Ident(_), "Expecting trait name in declaration");
+
+        let full_name = format!("{}::{}", self.module, name);
+
+        expect_next_token!(self.tokens, TokenType::OpenBrace, "Expecting `{` to start trait block");
+
+        let mut methods = vec![];
+        let mut sym_methods = vec![];
+        loop {
+            match self.tokens.peek() {
+                Some(Token { tt: TokenType::CloseBrace, .. }) => {
+                    self.tokens.next(); // Eat brace
+                    break;
+                },
+                Some(Token { tt: TokenType::Fn, .. }) => {
+                    let fn_token = self.tokens.next().unwrap(); // Eat fn
+
+                    // Set the current struct so the method name isn't prefixed with the module
+                    self.current_struct = Some(full_name.clone());
+                    let proto = self.parse_proto();
+                    self.current_struct = None;
+                    let proto = proto?;
+
+                    // Trait methods are only signatures
+                    if matches!(self.tokens.peek(), Some(Token { tt: TokenType::OpenBrace, .. })) {
+                        return Err(ParseError::from((
+                            format!("trait method `{}` can't have a body", proto.name()),
+                            fn_token,
+                        )));
+                    }
+
+                    // Keep the simple method name. `self` is injected when the trait is used
+                    let proto = Prototype::new(
+                        proto.name().to_owned(),
+                        proto.params().to_vec(),
+                        proto.ret_ty().clone(),
+                        false,
+                        self.module.clone(),
+                        Some(full_name.clone()),
+                    );
+                    if sym_methods.iter().any(|m: &Symbol| m.name == proto.name()) {
+                        return Err(ParseError::from((
+                            format!("method `{}` can't be redefined in trait `{}`", proto.name(), full_name),
+                            fn_token,
+                        )));
+                    }
+                    sym_methods.push(Symbol::new_fn(
+                        (proto.name(), proto.name()),
+                        proto.params(),
+                        proto.ret_ty(),
+                        false,
+                        &self.module,
+                        false,
+                        Some(&full_name),
+                    ));
+                    methods.push(ast::Node::new_fn(proto, None));
+
+                    token_is_and_then!(self.tokens.peek(), TokenType::Semicolon(_), {
+                        self.tokens.next(); // Eat semicolon
+                    });
+                },
+                Some(t) => {
+                    return Err(ParseError::from((
+                        format!("Expecting `fn` in trait definition. Got `{}`", t.tt),
+                        *t,
+                    )))
+                },
+                None => {
+                    return Err(ParseError::from("Expecting `}` to terminate trait definition".to_string()))
+                },
+            }
+        }
+
+        if self.symbol_table.insert(Symbol::new_trait(&full_name, &sym_methods, &self.module, true)).is_some()
+        {
+            return Err(ParseError::from((format!("trait `{}` already defined", full_name), token)));
+        }
+
+        Ok(ast::Node::new_trait(full_name, methods))
+    }
+
+    // ImplDecl ::= 'impl' ident 'for' ident '{' ( FnDecl ';' )* '}' ;
+    fn parse_impl(&mut self) -> ParseResult {
+        self.tokens.next(); // Eat impl
+
+        let (trait_name, token) =
+            expect_next_token!(self.tokens, TokenType::Ident(_), "Expecting trait name after `impl`");
+        expect_next_token!(self.tokens, TokenType::For, "Expecting `for` after trait name in impl");
+        let (struct_name, _) =
+            expect_next_token!(self.tokens, TokenType::Ident(_), "Expecting struct name in impl");
+
+        // Traits may be imported, but methods can only be added to local structs
+        let trait_name = match trait_name.contains("::") {
+            true => trait_name.to_owned(),
+            false => format!("{}::{}", self.module, trait_name),
+        };
+        let full_name = format!("{}::{}", self.module, struct_name);
+
+        expect_next_token!(self.tokens, TokenType::OpenBrace, "Expecting `{` to start impl block");
+
+        let mut methods = vec![];
+        loop {
+            match self.tokens.peek() {
+                Some(Token { tt: TokenType::CloseBrace, .. }) => {
+                    self.tokens.next(); // Eat brace
+                    break;
+                },
+                Some(Token { tt: TokenType::Fn, .. }) => {
+                    self.current_struct = Some(full_name.to_owned());
+                    match self.parse_fn() {
+                        Ok(f) => methods.push(f),
+                        Err(e) => self.push_err(e),
+                    }
+                    token_is_and_then!(self.tokens.peek(), TokenType::Semicolon(_), {
+                        self.tokens.next(); // Eat semicolon
+                    });
+                    self.current_struct = None;
+                },
+                Some(Token { tt: TokenType::Hash, .. }) => self.attrs = self.parse_attrs()?,
+                Some(t) => {
+                    return Err(ParseError::from((
+                        format!("Expecting `fn` in impl block. Got `{}`", t.tt),
+                        *t,
+                    )))
+                },
+                None => return Err(ParseError::from("Expecting `}` to terminate impl block".to_string())),
+            }
+        }
+
+        // Save the method names so they can be added to the struct symbol after parsing
+        let prefix = format!("_{}_", full_name);
+        let method_names = methods
+            .iter()
+            .filter_map(|node| match &node.kind {
+                ast::node::Kind::Fn { proto, .. } => proto.name().strip_prefix(&prefix).map(|n| n.to_owned()),
+                _ => None,
+            })
+            .collect();
+        self.impls.push((full_name.clone(), trait_name.clone(), method_names, token.clone()));
+
+        Ok(ast::Node::new_impl(trait_name, full_name, methods))
+    }
+
     // ForStmt ::= 'for' VarInit ';' Expr ';' number? Block ;
     fn parse_for(&mut self) -> ParseResult {
         self.tokens.next(); // Eat for
@@ -220,7 +683,7 @@ impl<'a> Parse<'a> {
         let cond_node = self.parse_expr(0)?;
         expect_explicit_semi!(self.tokens, "Expecting `;` after condition");
 
-        let step_node = self.parse_expr(0)?;
+        let step_node = self.parse_expr_before_block()?;
 
         Ok(ast::Node::new_for(name, antn, init, cond_node, step_node, self.parse_block()?))
     }
@@ -234,23 +697,120 @@ impl<'a> Parse<'a> {
     // WhileStmt ::= 'while' Expr Block ;
     fn parse_while(&mut self) -> ParseResult {
         self.tokens.next(); // Eat while
-        Ok(ast::Node::new_while(self.parse_expr(0)?, self.parse_block()?))
+        Ok(ast::Node::new_while(self.parse_expr_before_block()?, self.parse_block()?))
     }
 
-    // LetStmt ::= 'let' VarInit ;
+    // The annotation can be left off when there's an initializer to infer the type from
+    //
+    // LetStmt ::= 'let' ( VarInit | ident '=' Expr | Destructure ) ;
     fn parse_let(&mut self) -> ParseResult {
-        self.tokens.next(); // Eat let
-        let (name, antn, init) = self.parse_var_init("let")?;
+        let attrs = std::mem::take(&mut self.attrs);
+        let token = self.tokens.next().unwrap(); // Eat let
+        if let Some(Token { tt: TokenType::OpenParen, .. }) = self.tokens.peek() {
+            if !attrs.is_empty() {
+                return Err(ParseError::from((
+                    "Attributes can't be used on a destructuring `let`".to_string(),
+                    token,
+                )));
+            }
+            return self.parse_destructure();
+        }
+
+        let (name, _) = expect_next_token!(
+            self.tokens,
+            TokenType::Ident(_),
+            "Expecting identifier in `let` typed declaration"
+        );
+
+        let antn = token_is_and_then!(self.tokens.peek(), TokenType::Colon, {
+            self.tokens.next(); // Eat colon
+            self.parse_type_antn("let")?
+        });
 
-        Ok(ast::Node::new_let(name, antn, init))
+        let init = token_is_and_then!(self.tokens.peek(), TokenType::Op(Operator::Assign), {
+            self.tokens.next(); // Eat assign
+            self.parse_expr(0)?
+        });
+
+        if antn.is_none() && init.is_none() {
+            let next = self.tokens.next().cloned().filter(|t| !t.is_implicit_semi()).unwrap_or_default();
+            return Err(ParseError::from((
+                format!("Expecting `:` or `=` after identifier in `let` declaration. Got `{}`", next.tt),
+                &next,
+            )));
+        }
+
+        Ok(ast::Node::new_let(name.to_owned(), antn, init, attrs))
+    }
+
+    // Destructure  ::= '(' DestructName ( ',' DestructName )+ ')' ( ':' TypeAntn )? '=' Expr ;
+    // DestructName ::= '_' | ident ;
+    fn parse_destructure(&mut self) -> ParseResult {
+        let paren = self.tokens.next().cloned().unwrap_or_default(); // Eat '('
+
+        let mut names = vec![];
+        loop {
+            let token = self.tokens.next().cloned().unwrap_or_default();
+            match &token.tt {
+                TokenType::Ident(name) => names.push(name.to_owned()),
+                TokenType::Underscore => names.push("_".to_string()),
+                x => {
+                    return Err(ParseError::from((
+                        format!("Expecting name or `_` in `let` destructuring. Got `{}`", x),
+                        &token,
+                    )))
+                },
+            }
+
+            let token = self.tokens.next().cloned().unwrap_or_default();
+            match &token.tt {
+                TokenType::Comma => (),
+                TokenType::CloseParen => break,
+                x => {
+                    return Err(ParseError::from((
+                        format!("Expecting `,` or `)` in `let` destructuring. Got `{}`", x),
+                        &token,
+                    )))
+                },
+            }
+        }
+        if names.len() < 2 {
+            return Err(ParseError::from((
+                "`let` destructuring needs at least two names".to_string(),
+                &paren,
+            )));
+        }
+
+        let antn = token_is_and_then!(self.tokens.peek(), TokenType::Colon, {
+            self.tokens.next();
+            self.parse_type_antn("let")?
+        });
+        expect_next_token!(
+            self.tokens,
+            TokenType::Op(Operator::Assign),
+            "Expecting `=` after names in `let` destructuring"
+        );
+
+        Ok(ast::Node::new_destructure(names, antn, self.parse_expr(0)?))
     }
 
     // FnDecl ::= Prototype Block ;
     fn parse_fn(&mut self) -> ParseResult {
+        let attrs = std::mem::take(&mut self.attrs);
+
+        // Associated functions are marked `static`. They're only allowed in structs
+        let is_static =
+            token_is_and_then!(self.tokens.peek(), TokenType::Static, self.tokens.next()).is_some();
+        if is_static && !matches!(self.tokens.peek(), Some(Token { tt: TokenType::Fn, .. })) {
+            let t = self.tokens.peek().cloned().cloned().unwrap_or_default();
+            return Err(ParseError::from((format!("Expecting `fn` after `static`. Got `{}`", t.tt), &t)));
+        }
+
         // Eat 'fn'
         let token = self.tokens.next().unwrap();
 
         let mut proto = self.parse_proto()?;
+        proto.set_attrs(attrs);
 
         // No body for externs
         let body = if proto.is_extern() { None } else { Some(self.parse_block()?) };
@@ -260,15 +820,27 @@ impl<'a> Parse<'a> {
         // For methods use a "semi" lowered name. We do this here to allow for proper name
         // collision detection in the tych
         if let Some(struct_name) = &self.current_struct {
+            if proto.is_generic() {
+                return Err(ParseError::from((
+                    format!("method `{}` on `{}` can't be generic", proto.name(), struct_name),
+                    token,
+                )));
+            }
+
             let orig_name = proto.name().to_owned();
             let method_name = format!("_{}_{}", struct_name, proto.name());
             proto.set_name(method_name);
 
             // Insert `self` for methods. Do this early so we don't have to mess with the
-            // symbol table later. `self` will be passed as a pointer
-            let mut args = vec![(String::from("self"), pointer_wrap!(Type::Comp(struct_name.clone())))];
-            args.append(&mut proto.params().to_vec());
-            proto.set_params(args);
+            // symbol table later. `self` will be passed as a pointer. Associated functions
+            // keep the cooked name, but aren't members and don't get a `self`
+            if is_static {
+                proto.set_member_of(None);
+            } else {
+                let mut args = vec![(String::from("self"), pointer_wrap!(Type::Comp(struct_name.clone())))];
+                args.append(&mut proto.params().to_vec());
+                proto.set_params(args);
+            }
 
             if self.symbol_table.insert_with_name(proto.name(), Symbol::from(&proto)).is_some() {
                 return Err(ParseError::from((
@@ -277,7 +849,11 @@ impl<'a> Parse<'a> {
                 )));
             }
         } else {
-            let sym = self.symbol_table.insert_with_name(proto.name(), Symbol::from(&proto));
+            // Generic functions are instantiated by each module that calls them, so they
+            // can't be exported
+            let mut sym = Symbol::from(&proto);
+            sym.is_exportable = !proto.is_generic();
+            let sym = self.symbol_table.insert_with_name(proto.name(), sym);
             // Error on dups. Ignore for externs
             if sym.is_some() && body.is_some() {
                 return Err(ParseError::from((
@@ -339,8 +915,99 @@ impl<'a> Parse<'a> {
         Ok(ast::Node::new_next())
     }
 
+    // PanicStmt ::= 'panic' '(' string ')' ;
+    fn parse_panic(&mut self) -> ParseResult {
+        let token = self.tokens.next().unwrap(); // Eat panic
+        let loc = Loc::new(&self.file, token.line, token.column);
+
+        expect_next_token!(self.tokens, TokenType::OpenParen, "Expecting `(` after `panic`");
+        let (msg, _) =
+            expect_next_token!(self.tokens, TokenType::Str(_), "Expecting string message in `panic`");
+        expect_next_token!(self.tokens, TokenType::CloseParen, "Expecting `)` after `panic` message");
+
+        Ok(ast::Node::new_panic(msg.to_owned(), loc))
+    }
+
+    // AssertStmt ::= ( 'assert' | 'debug_assert' ) '(' Expr ( ',' string )? ')' ;
+    fn parse_assert(&mut self) -> ParseResult {
+        let token = self.tokens.next().unwrap(); // Eat assert
+        let loc = Loc::new(&self.file, token.line, token.column);
+        let debug = matches!(token.tt, TokenType::Assert(true));
+
+        expect_next_token!(self.tokens, TokenType::OpenParen, "Expecting `(` after `assert`");
+
+        // Keep the condition's tokens around so the failure message can quote it
+        let cond_tokens = self.tokens.clone();
+        let remaining = self.tokens.len();
+        let cond = self.parse_expr(0)?;
+        let cond_src = spell_tokens(cond_tokens.take(remaining - self.tokens.len()));
+
+        let mut msg = format!("assertion failed: `{}`", cond_src);
+        if token_is_and_then!(self.tokens.peek(), TokenType::Comma, self.tokens.next()).is_some() {
+            let (user_msg, _) =
+                expect_next_token!(self.tokens, TokenType::Str(_), "Expecting string message in `assert`");
+            msg = format!("{}: {}", msg, user_msg);
+        }
+        expect_next_token!(self.tokens, TokenType::CloseParen, "Expecting `)` to close `assert`");
+
+        Ok(ast::Node::new_assert(cond, msg, debug, loc))
+    }
+
+    // DebugStmt ::= 'debug' '(' Expr ')' ;
+    fn parse_debug(&mut self) -> ParseResult {
+        self.tokens.next(); // Eat debug
+
+        expect_next_token!(self.tokens, TokenType::OpenParen, "Expecting `(` after `debug`");
+        let value = self.parse_expr(0)?;
+        expect_next_token!(self.tokens, TokenType::CloseParen, "Expecting `)` to close `debug`");
+
+        Ok(ast::Node::new_debug(value))
+    }
+
+    // PrintStmt ::= ( 'print' | 'println' ) '(' string ( ',' Expr )* ')' ;
+    fn parse_print(&mut self) -> ParseResult {
+        let token = self.tokens.next().unwrap(); // Eat print
+        let newline = matches!(token.tt, TokenType::Print(true));
+        let keyword = if newline { "println" } else { "print" };
+
+        expect_next_token!(self.tokens, TokenType::OpenParen, format!("Expecting `(` after `{}`", keyword));
+        let (fmt, _) = expect_next_token!(
+            self.tokens,
+            TokenType::Str(_),
+            format!("Expecting format string in `{}`", keyword)
+        );
+
+        // The format string is checked against the args in the tych
+        let mut args = vec![];
+        while token_is_and_then!(self.tokens.peek(), TokenType::Comma, self.tokens.next()).is_some() {
+            args.push(self.parse_expr(0)?);
+        }
+        expect_next_token!(
+            self.tokens,
+            TokenType::CloseParen,
+            format!("Expecting `,` or `)` in `{}`", keyword)
+        );
+
+        Ok(ast::Node::new_print(fmt.to_owned(), args, newline))
+    }
+
     /// Expression productions
 
+    // Parse the expression in front of a block, like an `if` condition. Struct literals
+    // aren't allowed here since `x {` would start the block
+    fn parse_expr_before_block(&mut self) -> ParseResult {
+        self.with_struct_lits(false, |p| p.parse_expr(0))
+    }
+
+    // Run `f` with struct literals allowed or not. Brackets re-allow them, e.g., in
+    // `if (p == Point { x, y }) { ... }`
+    fn with_struct_lits<T>(&mut self, allowed: bool, f: impl FnOnce(&mut Self) -> T) -> T {
+        let no_struct_lit = std::mem::replace(&mut self.no_struct_lit, !allowed);
+        let res = f(self);
+        self.no_struct_lit = no_struct_lit;
+        res
+    }
+
     // Parses arbitrary length binary expressions. Uses Pratt with operator
     // precedence parsing.
     //
@@ -378,13 +1045,20 @@ impl<'a> Parse<'a> {
             };
 
             // Advance past op
+            let loc = Loc::new(&self.file, next.line, next.column);
             self.tokens.next();
 
+            // The rhs of a cast is a type
+            if op == Operator::As {
+                lhs = ast::Node::new_cast(lhs, Some(self.parse_type_antn("cast")?));
+                continue;
+            }
+
             // Descend for rhs with the current precedence as min_p
             let rhs = self.parse_expr(p)?;
 
             // Make a new lhs and continue loop
-            lhs = ast::Node::new_binop(op, lhs, rhs, None);
+            lhs = ast::Node::new_binop(op, lhs, rhs, None, loc);
         }
         Ok(lhs)
     }
@@ -398,9 +1072,9 @@ impl<'a> Parse<'a> {
         Ok(ast::Node::new_unop(op, rhs, None))
     }
 
-    // PrimaryExpr ::= CondExpr | LitExpr | IdentExpr | CallExpr | Block
-    //               | ParenExpr | IndexExpr | SelfExpr | FieldSelectorExpr
-    //               | MethodExpr ;
+    // PrimaryExpr ::= CondExpr | MatchExpr | LitExpr | IdentExpr | CallExpr | Block
+    //               | ParenExpr | TupleExpr | ClosureExpr | IndexExpr | SelfExpr
+    //               | FieldSelectorExpr | TupleFieldExpr | MethodExpr ;
     fn parse_primary(&mut self) -> ParseResult {
         use TokenType::*;
 
@@ -408,15 +1082,17 @@ impl<'a> Parse<'a> {
 
         let expr = match &token.tt {
             If => self.parse_cond()?,
+            Match => self.parse_match()?,
             Ident(id) => self.parse_ident(id)?,
             OpenBrace => self.parse_block()?,
-            OpenParen => self.parse_paren()?,
+            OpenParen => self.with_struct_lits(true, Self::parse_paren)?,
+            Move | Op(Operator::BitOr | Operator::Or) => self.parse_closure()?,
             Op(sym) => self.parse_unop(*sym)?,
             Bool(b) => self.parse_lit_bool(*b)?,
             Char(c) => self.parse_lit_char(c, token)?,
             Num(num) => self.parse_lit_num(num, token)?,
             Str(s) => self.parse_lit_string(s)?,
-            OpenBracket => self.parse_lit_array()?,
+            OpenBracket => self.with_struct_lits(true, Self::parse_lit_array)?,
             x => return Err(ParseError::from((format!("Expecting primary expression. Got `{}`", x), token))),
         };
 
@@ -440,15 +1116,20 @@ impl<'a> Parse<'a> {
     //
     // IdentExpr ::= ident ;
     // CallExpr  ::= ident '(' ExprList? ')' ;
+    //
+    // Struct literals also start with an ident
     fn parse_ident(&mut self, id: &str) -> ParseResult {
         self.tokens.next(); // Eat ident
 
         match self.tokens.peek() {
+            Some(Token { tt: TokenType::OpenBrace, .. }) if !self.no_struct_lit => self.parse_struct_lit(id),
             Some(Token { tt: TokenType::OpenParen, .. }) => {
                 // Eat open paren
                 self.tokens.next();
                 // Parse argument list
-                let args = self.parse_expr_list(TokenType::CloseParen, "function call argument list")?;
+                let args = self.with_struct_lits(true, |p| {
+                    p.parse_expr_list(TokenType::CloseParen, "function call argument list")
+                })?;
                 // Eat close paren
                 expect_next_token!(self.tokens, TokenType::CloseParen, "Expecting `)` in function call");
                 Ok(ast::Node::new_call(id.to_owned(), args, None))
@@ -457,13 +1138,78 @@ impl<'a> Parse<'a> {
         }
     }
 
+    // StructLitExpr ::= ident '{' ( FieldInit ( ',' FieldInit )* ','? )? '}' ;
+    // FieldInit     ::= ident ( ':' Expr )? ;
+    fn parse_struct_lit(&mut self, name: &str) -> ParseResult {
+        self.tokens.next(); // Eat brace
+
+        let mut fields = vec![];
+        loop {
+            if let Some(Token { tt: TokenType::CloseBrace, .. }) = self.tokens.peek() {
+                self.tokens.next(); // Eat brace
+                break;
+            }
+
+            let (field, _) = expect_next_token!(
+                self.tokens,
+                TokenType::Ident(_),
+                "Expecting field name in struct literal"
+            );
+
+            // `Point { x }` is shorthand for `Point { x: x }`
+            let value = match token_is_and_then!(self.tokens.peek(), TokenType::Colon, self.tokens.next()) {
+                Some(_) => self.parse_expr(0)?,
+                None => ast::Node::new_ident(field.to_owned(), None),
+            };
+            fields.push((field.to_owned(), value));
+
+            // Fields are separated by commas. Newlines without one get an implicit semicolon
+            match self.tokens.peek() {
+                Some(Token { tt: TokenType::Comma, .. }) => {
+                    self.tokens.next(); // Eat comma
+                    token_is_and_then!(self.tokens.peek(), TokenType::Semicolon(true), {
+                        self.tokens.next(); // Eat semicolon
+                    });
+                },
+                Some(Token { tt: TokenType::Semicolon(true), .. }) => {
+                    self.tokens.next(); // Eat semicolon
+                },
+                Some(Token { tt: TokenType::CloseBrace, .. }) => (),
+                Some(t) => {
+                    return Err(ParseError::from((
+                        format!("Expecting `,` or `}}` after struct literal field. Got `{}`", t.tt),
+                        *t,
+                    )))
+                },
+                None => {
+                    return Err(ParseError::from("Expecting `}` to terminate struct literal".to_string()))
+                },
+            }
+        }
+
+        Ok(ast::Node::new_struct_lit(name.to_owned(), fields, None))
+    }
+
     // Entry point for field and method selectors
     //
     // FieldSelectorExpr  ::= PrimaryExpr '.' IdentExpr ;
+    // TupleFieldExpr     ::= PrimaryExpr '.' integer ;
     // MethodSelectorExpr ::= PrimaryExpr '.' CallExpr ;
     fn parse_selector(&mut self, target: ast::Node) -> ParseResult {
         self.tokens.next(); // Eat dot
 
+        // Tuple fields are selected by their index
+        if let Some(token @ Token { tt: TokenType::Num(idx), .. }) = self.tokens.peek().cloned() {
+            self.tokens.next();
+            if !idx.chars().all(|c| c.is_ascii_digit()) {
+                return Err(ParseError::from((
+                    format!("Expecting tuple index after `.`. Got `{}`", idx),
+                    token,
+                )));
+            }
+            return Ok(ast::Node::new_fselector(target, idx.to_owned(), None));
+        }
+
         let (ident, _) = expect_next_token!(
             self.tokens,
             TokenType::Ident(_),
@@ -485,18 +1231,88 @@ impl<'a> Parse<'a> {
     }
 
     // ParenExpr ::= '(' Expr ')' ;
+    // TupleExpr ::= '(' Expr ( ',' Expr )+ ','? ')' ;
     fn parse_paren(&mut self) -> ParseResult {
-        self.tokens.next(); // Eat '('
+        let paren = self.tokens.next().cloned().unwrap_or_default(); // Eat '('
         let lhs = self.parse_expr(0);
-        expect_next_token!(self.tokens, TokenType::CloseParen, "Expecting `)` to close paren expression");
-        lhs
+
+        // A comma after the first expression makes it a tuple
+        if token_is_and_then!(self.tokens.peek(), TokenType::Comma, self.tokens.next()).is_none() {
+            expect_next_token!(self.tokens, TokenType::CloseParen, "Expecting `)` to close paren expression");
+            return lhs;
+        }
+
+        let mut elements = vec![lhs?];
+        elements.extend(self.parse_expr_list(TokenType::CloseParen, "tuple literal")?);
+        expect_next_token!(self.tokens, TokenType::CloseParen, "Expecting `)` to close tuple literal");
+        if elements.len() < 2 {
+            return Err(ParseError::from(("Tuples need at least two elements".to_string(), &paren)));
+        }
+        Ok(ast::Node::new_lit(Literal::Tuple(elements), None))
+    }
+
+    // `||` is lexed as a single operator, so it's also the start of a closure without
+    // params
+    //
+    // ClosureExpr ::= 'move'? ( '||' | '|' ( TypedDecl ( ',' TypedDecl )* ','? )? '|' )
+    //                 ( '->' TypeAntn )? Block ;
+    fn parse_closure(&mut self) -> ParseResult {
+        let is_move = token_is_and_then!(self.tokens.peek(), TokenType::Move, self.tokens.next()).is_some();
+
+        let mut params = vec![];
+        let token = self.tokens.next().cloned().unwrap_or_default(); // Eat `|` or `||`
+        match &token.tt {
+            TokenType::Op(Operator::Or) => (),
+            TokenType::Op(Operator::BitOr) => loop {
+                // Matches immediate `|`
+                if token_is_and_then!(self.tokens.peek(), TokenType::Op(Operator::BitOr), self.tokens.next())
+                    .is_some()
+                {
+                    break;
+                }
+
+                params.push(self.parse_typed_decl("closure")?);
+
+                let next = self.tokens.next().cloned().filter(|t| !t.is_implicit_semi()).unwrap_or_default();
+                match &next.tt {
+                    TokenType::Op(Operator::BitOr) => break,
+                    TokenType::Comma => (),
+                    x => {
+                        return Err(ParseError::from((
+                            format!("Expecting `,` or `|` in closure parameter list. Got `{}`", x),
+                            &next,
+                        )))
+                    },
+                }
+            },
+            x => {
+                return Err(ParseError::from((
+                    format!("Expecting `|` to start closure parameters after `move`. Got `{}`", x),
+                    &token,
+                )))
+            },
+        }
+
+        let ret_ty = token_is_and_then!(self.tokens.peek(), TokenType::Op(Operator::RetType), {
+            self.tokens.next(); // Eat `->`
+            self.parse_type_antn("closure")?
+        });
+
+        Ok(ast::Node::new_closure(
+            params,
+            ret_ty.unwrap_or_default(),
+            self.parse_block()?,
+            is_move,
+            vec![],
+            None,
+        ))
     }
 
     // CondExpr ::= 'if' Expr Block ( 'else' (CondExpr | Block ) )? ;
     fn parse_cond(&mut self) -> ParseResult {
         self.tokens.next(); // Eat if
 
-        let cond_expr = self.parse_expr(0)?;
+        let cond_expr = self.parse_expr_before_block()?;
         let then_block = self.parse_block()?;
 
         let else_block = token_is_and_then!(self.tokens.peek(), TokenType::Else, {
@@ -523,6 +1339,157 @@ impl<'a> Parse<'a> {
         Ok(ast::Node::new_cond(cond_expr, then_block, else_block, None))
     }
 
+    // MatchExpr ::= 'match' Expr '{' ( MatchArm ( ',' MatchArm )* ','? )? '}' ;
+    // MatchArm  ::= Pattern ( 'if' Expr )? '=>' Expr ;
+    fn parse_match(&mut self) -> ParseResult {
+        let token = self.tokens.next().cloned().unwrap_or_default(); // Eat match
+        let loc = Loc::new(&self.file, token.line, token.column);
+
+        let scrutinee = self.parse_expr_before_block()?;
+
+        expect_next_token!(self.tokens, TokenType::OpenBrace, "Expecting `{` to start match arms");
+
+        let mut arms = vec![];
+        loop {
+            if let Some(Token { tt: TokenType::CloseBrace, .. }) = self.tokens.peek() {
+                self.tokens.next(); // Eat brace
+                break;
+            }
+
+            let pattern = self.parse_pattern()?;
+            let guard = token_is_and_then!(self.tokens.peek(), TokenType::If, {
+                self.tokens.next(); // Eat if
+                self.parse_expr(0)?
+            });
+            expect_next_token!(self.tokens, TokenType::FatArrow, "Expecting `=>` after match pattern");
+            let body = self.parse_expr(0)?;
+            arms.push(MatchArm::new(pattern, guard, body));
+
+            // Arms are separated by commas. Newlines without one get an implicit semicolon
+            match self.tokens.peek() {
+                Some(Token { tt: TokenType::Comma, .. }) => {
+                    self.tokens.next(); // Eat comma
+                    token_is_and_then!(self.tokens.peek(), TokenType::Semicolon(true), {
+                        self.tokens.next(); // Eat semicolon
+                    });
+                },
+                Some(Token { tt: TokenType::Semicolon(true), .. }) => {
+                    self.tokens.next(); // Eat semicolon
+                },
+                Some(Token { tt: TokenType::CloseBrace, .. }) => (),
+                Some(t) => {
+                    return Err(ParseError::from((
+                        format!("Expecting `,` or `}}` after match arm. Got `{}`", t.tt),
+                        *t,
+                    )))
+                },
+                None => return Err(ParseError::from("Expecting `}` to terminate match".to_string())),
+            }
+        }
+
+        if arms.is_empty() {
+            return Err(ParseError::from(("`match` must have at least one arm".to_string(), &token)));
+        }
+
+        Ok(ast::Node::new_match(scrutinee, arms, None, loc))
+    }
+
+    // Pattern        ::= '_' | ident | LitPattern ( ( '..' | '..=' ) LitPattern )?
+    //                  | VariantPattern ;
+    // VariantPattern ::= ident '::' ident ( '(' FieldPattern ( ',' FieldPattern )* ')' )? ;
+    // FieldPattern   ::= '_' | ident ;
+    fn parse_pattern(&mut self) -> Result<Pattern<ast::Node>, ParseError> {
+        let token = self.tokens.peek().cloned().ok_or_else(|| "Premature end of pattern".to_string())?;
+
+        let path = match &token.tt {
+            TokenType::Underscore => {
+                self.tokens.next(); // Eat underscore
+                return Ok(Pattern::Wildcard);
+            },
+            TokenType::Ident(id) => id,
+            _ => {
+                let start = self.parse_lit_pattern()?;
+                let inclusive = match self.tokens.peek() {
+                    Some(Token { tt: TokenType::DotDot, .. }) => false,
+                    Some(Token { tt: TokenType::DotDotEq, .. }) => true,
+                    _ => return Ok(Pattern::Lit(start)),
+                };
+                self.tokens.next(); // Eat range operator
+                let end = self.parse_lit_pattern()?;
+                return Ok(Pattern::Range { start, end, inclusive });
+            },
+        };
+        self.tokens.next(); // Eat ident
+
+        // Plain identifiers bind the value. Paths name enum variants
+        let (enum_name, variant) = match path.rsplit_once("::") {
+            Some((enum_name, variant)) => (enum_name.to_owned(), variant.to_owned()),
+            None => return Ok(Pattern::Binding(path.to_owned())),
+        };
+
+        let mut fields = vec![];
+        if let Some(Token { tt: TokenType::OpenParen, .. }) = self.tokens.peek() {
+            self.tokens.next(); // Eat open paren
+            loop {
+                fields.push(match self.tokens.next() {
+                    Some(Token { tt: TokenType::Underscore, .. }) => Pattern::Wildcard,
+                    Some(Token { tt: TokenType::Ident(name), .. }) if !name.contains("::") => {
+                        Pattern::Binding(name.to_owned())
+                    },
+                    t => {
+                        let t = t.cloned().unwrap_or_default();
+                        return Err(ParseError::from((
+                            format!("Expecting binding or `_` in pattern `{}`. Got `{}`", path, t.tt),
+                            &t,
+                        )));
+                    },
+                });
+                match self.tokens.next() {
+                    Some(Token { tt: TokenType::Comma, .. }) => continue,
+                    Some(Token { tt: TokenType::CloseParen, .. }) => break,
+                    t => {
+                        let t = t.cloned().unwrap_or_default();
+                        return Err(ParseError::from((
+                            format!("Expecting `,` or `)` in pattern `{}`. Got `{}`", path, t.tt),
+                            &t,
+                        )));
+                    },
+                }
+            }
+        }
+
+        Ok(Pattern::Variant { enum_name, variant, fields })
+    }
+
+    // LitPattern ::= '-'? number | CharLit | bool ;
+    fn parse_lit_pattern(&mut self) -> ParseResult {
+        use TokenType::*;
+
+        let token = self.tokens.peek().cloned().ok_or_else(|| "Premature end of pattern".to_string())?;
+
+        match &token.tt {
+            Op(Operator::Sub) => {
+                self.tokens.next(); // Eat '-'
+                match self.tokens.peek().cloned() {
+                    Some(token @ Token { tt: Num(num), .. }) => {
+                        Ok(ast::Node::new_unop(Operator::Sub, self.parse_lit_num(num, token)?, None))
+                    },
+                    t => {
+                        let t = t.cloned().unwrap_or_default();
+                        Err(ParseError::from((
+                            format!("Expecting number after `-` in pattern. Got `{}`", t.tt),
+                            &t,
+                        )))
+                    },
+                }
+            },
+            Num(num) => self.parse_lit_num(num, token),
+            Char(c) => self.parse_lit_char(c, token),
+            Bool(b) => self.parse_lit_bool(*b),
+            tt => Err(ParseError::from((format!("Expecting pattern in match arm. Got `{}`", tt), token))),
+        }
+    }
+
     // Block ::= '{' StmtList? '}' ;
     fn parse_block(&mut self) -> ParseResult {
         let mut block: Vec<ast::Node> = vec![];
@@ -551,13 +1518,14 @@ impl<'a> Parse<'a> {
 
     // IndexExpr ::= PrimaryExpr '[' Expr ']' ;
     fn parse_index(&mut self, binding: ast::Node) -> ParseResult {
-        self.tokens.next(); // Eat open bracket
+        let open_bracket = self.tokens.next().unwrap(); // Eat open bracket
+        let loc = Loc::new(&self.file, open_bracket.line, open_bracket.column);
 
         let idx = self.parse_expr(0)?;
 
         expect_next_token!(self.tokens, TokenType::CloseBracket, "Expecting `]` after expression in index");
 
-        Ok(ast::Node::new_index(binding, idx, None))
+        Ok(ast::Node::new_index(binding, idx, None, loc))
     }
 
     /// Literals
@@ -594,11 +1562,18 @@ impl<'a> Parse<'a> {
 
         match n.parse::<u64>() {
             Ok(n) => Ok(ast::Node::new_lit(Literal::UInt64(n), None)),
-            Err(e) if e.kind() == &IntErrorKind::PosOverflow || e.kind() == &IntErrorKind::NegOverflow => {
-                Err(ParseError::from((format!("Numeric literal out of integer range: {}", token), token)))
+            // Wider magnitudes are kept for 128-bit integers
+            Err(e) if !n.contains('.') && e.kind() == &IntErrorKind::PosOverflow => match n.parse::<u128>() {
+                Ok(n) => Ok(ast::Node::new_lit(Literal::UInt128(n), None)),
+                Err(_) => {
+                    Err(ParseError::from((format!("Numeric literal out of integer range: {}", token), token)))
+                },
             },
-            _ => match n.parse::<f32>() {
-                Ok(n) => Ok(ast::Node::new_lit(Literal::Float(n), None)),
+            _ => match n.parse::<f64>() {
+                Ok(n) if n.is_infinite() => {
+                    Err(ParseError::from((format!("Numeric literal out of float range: {}", token), token)))
+                },
+                Ok(n) => Ok(ast::Node::new_lit(Literal::Double(n), None)),
                 Err(_) => Err(ParseError::from((format!("Invalid numeric literal: {}", token), token))),
             },
         }
@@ -626,21 +1601,47 @@ impl<'a> Parse<'a> {
 
     /// Misc productions
 
-    // Prototype ::= 'fn' ident '(' ( TypedDecl ( ',' TypedDecl )* )* ')' ( '->' TypeAntn )? ;
+    // Prototype ::= 'fn' ident Generics? '(' ( TypedDecl ( ',' TypedDecl )* ( ',' '...' )? )? ')' ( '->' TypeAntn )? ;
     fn parse_proto(&mut self) -> Result<Prototype, ParseError> {
         let (name, _) =
             expect_next_token!(self.tokens, TokenType::Ident(_), "Expecting function name in prototype");
 
+        let generics =
+            token_is_and_then!(self.tokens.peek(), TokenType::Op(Operator::Lt), self.parse_generics()?);
+
         expect_next_token!(self.tokens, TokenType::OpenParen, "Expecting `(` in prototype");
 
         // Parse parameter list
         let mut params = vec![];
+        let mut ellipsis = None;
         while let Some(&next) = self.tokens.peek() {
             // Matches immediate ')'
             if next.tt == TokenType::CloseParen {
                 break;
             }
 
+            // `...` comes after the last param of a variadic extern
+            if next.tt == TokenType::Ellipsis {
+                if params.is_empty() {
+                    return Err(ParseError::from((
+                        String::from("Expecting a parameter before `...` in prototype"),
+                        next,
+                    )));
+                }
+                self.tokens.next(); // Eat ...
+                ellipsis = Some(next);
+                match self.tokens.peek() {
+                    Some(Token { tt: TokenType::CloseParen, .. }) => break,
+                    t => {
+                        let t = t.cloned().cloned().filter(|t| !t.is_implicit_semi()).unwrap_or_default();
+                        return Err(ParseError::from((
+                            format!("Expecting `)` after `...` in prototype. Got `{}`", t.tt),
+                            &t,
+                        )));
+                    },
+                }
+            }
+
             // Get the name of the parameter and its type annotation
             let (name, antn) = self.parse_typed_decl("prototype")?;
 
@@ -691,15 +1692,74 @@ impl<'a> Parse<'a> {
 
         // If the next token is a ';', this is an extern
         let is_extern = matches!(&self.tokens.peek(), Some(Token { tt: TokenType::Semicolon(..), .. }));
+        if let (Some(token), false) = (ellipsis, is_extern) {
+            return Err(ParseError::from((
+                format!("Only externs can be variadic. `{}` has a body", name),
+                token,
+            )));
+        }
 
-        Ok(Prototype::new(
+        let mut proto = Prototype::new(
             name.to_owned(),
             params,
             ret_type.unwrap_or_default(),
             is_extern,
             self.module.clone(),
             self.current_struct.clone(),
-        ))
+        );
+        proto.set_generics(generics.unwrap_or_default());
+        proto.set_variadic(ellipsis.is_some());
+        Ok(proto)
+    }
+
+    // Generics     ::= '<' GenericParam ( ',' GenericParam )* '>' ;
+    // GenericParam ::= ident ( ':' ident ( '+' ident )* )? ;
+    fn parse_generics(&mut self) -> Result<Vec<(String, Vec<String>)>, ParseError> {
+        self.tokens.next(); // Eat '<'
+
+        let mut generics = vec![];
+        loop {
+            let (name, token) =
+                expect_next_token!(self.tokens, TokenType::Ident(_), "Expecting generic parameter name");
+            if generics.iter().any(|(n, _)| n == name) {
+                return Err(ParseError::from((
+                    format!("generic parameter `{}` is already defined", name),
+                    token,
+                )));
+            }
+
+            // Parse optional trait bounds
+            let mut bounds = vec![];
+            if let Some(Token { tt: TokenType::Colon, .. }) = self.tokens.peek() {
+                self.tokens.next(); // Eat colon
+                loop {
+                    let (bound, _) = expect_next_token!(
+                        self.tokens,
+                        TokenType::Ident(_),
+                        format!("Expecting trait bound for generic parameter `{}`", name)
+                    );
+                    bounds.push(bound.to_owned());
+                    match self.tokens.peek() {
+                        Some(Token { tt: TokenType::Op(Operator::Add), .. }) => self.tokens.next(), // Eat '+'
+                        _ => break,
+                    };
+                }
+            }
+            generics.push((name.to_owned(), bounds));
+
+            match self.tokens.next() {
+                Some(Token { tt: TokenType::Comma, .. }) => continue,
+                Some(Token { tt: TokenType::Op(Operator::Gt), .. }) => break,
+                t => {
+                    let t = t.cloned().unwrap_or_default();
+                    return Err(ParseError::from((
+                        format!("Expecting `,` or `>` in generic parameter list. Got `{}`", t.tt),
+                        &t,
+                    )));
+                },
+            }
+        }
+        Ok(generics)
     }
 
     // VarInit ::= TypedDecl ( '=' Expr  )? ;
@@ -716,20 +1776,48 @@ impl<'a> Parse<'a> {
         Ok((name, antn, init))
     }
 
-    // TypeAntn ::= type | '[' type ']' ;
+    // TypeAntn  ::= type | DynType | TupleType | FnType | PtrType | '[' ( type | DynType ) ']' ;
+    // TupleType ::= '(' TypeAntn ( ',' TypeAntn )+ ')' ;
+    // PtrType   ::= '*' TypeAntn ;
     fn parse_type_antn(&mut self, caller: &str) -> Result<Type, ParseError> {
         let token = self.tokens.next();
         let ty = match token {
-            Some(Token { tt: TokenType::OpenBracket, .. }) => {
-                let (ty, _) = expect_next_token!(
+            Some(paren @ Token { tt: TokenType::OpenParen, .. }) => {
+                let mut tys = vec![self.parse_type_antn(caller)?];
+                while token_is_and_then!(self.tokens.peek(), TokenType::Comma, self.tokens.next()).is_some() {
+                    tys.push(self.parse_type_antn(caller)?);
+                }
+                expect_next_token!(
                     self.tokens,
-                    TokenType::Ident(_),
-                    format!("Expecting identifier after `[` in `{}` type annotation", caller)
+                    TokenType::CloseParen,
+                    format!("Expecting `,` or `)` in `{}` tuple type annotation", caller)
                 );
+                if tys.len() < 2 {
+                    return Err(ParseError::from((
+                        format!("Tuple type in `{}` annotation needs at least two elements", caller),
+                        paren,
+                    )));
+                }
+                Type::Tuple(tys)
+            },
+            Some(Token { tt: TokenType::OpenBracket, .. }) => {
+                let (ty, ty_name) = if let Some(Token { tt: TokenType::Dyn, .. }) = self.tokens.peek() {
+                    self.tokens.next();
+                    let ty = self.parse_dyn_type(caller)?;
+                    let ty_name = ty.to_string();
+                    (ty, ty_name)
+                } else {
+                    let (ty_name, _) = expect_next_token!(
+                        self.tokens,
+                        TokenType::Ident(_),
+                        format!("Expecting identifier after `[` in `{}` type annotation", caller)
+                    );
+                    (ty_name.as_str().into(), ty_name.to_owned())
+                };
                 expect_next_token!(
                     self.tokens,
                     TokenType::Semicolon(_),
-                    format!("Expecting semicolon after `{}` in `{}` type annotation", ty, caller)
+                    format!("Expecting semicolon after `{}` in `{}` type annotation", ty_name, caller)
                 );
 
                 let size = match self.parse_expr(0)? {
@@ -746,9 +1834,18 @@ impl<'a> Parse<'a> {
                     TokenType::CloseBracket,
                     format!("Missing `]` in `{}` type annotation", caller)
                 );
-                Type::SArray(Box::new(ty.as_str().into()), size.try_into().unwrap())
+                Type::SArray(Box::new(ty), size.try_into().unwrap())
             },
             Some(Token { tt: TokenType::Ident(ty), .. }) => ty.as_str().into(),
+            Some(Token { tt: TokenType::Dyn, .. }) => self.parse_dyn_type(caller)?,
+            Some(Token { tt: TokenType::Fn, .. }) => self.parse_fn_type(caller)?,
+            // `**` is lexed as a single operator
+            Some(Token { tt: TokenType::Op(Operator::Mul), .. }) => {
+                Type::Ptr(Box::new(self.parse_type_antn(caller)?))
+            },
+            Some(Token { tt: TokenType::Op(Operator::Pow), .. }) => {
+                Type::Ptr(Box::new(Type::Ptr(Box::new(self.parse_type_antn(caller)?))))
+            },
             Some(next) => {
                 return Err(ParseError::from((
                     format!("Expecting {} type annotation. Got `{}`", caller, next),
@@ -765,6 +1862,45 @@ impl<'a> Parse<'a> {
         Ok(ty)
     }
 
+    // DynType ::= 'dyn' ident ;
+    fn parse_dyn_type(&mut self, caller: &str) -> Result<Type, ParseError> {
+        let (trait_name, _) = expect_next_token!(
+            self.tokens,
+            TokenType::Ident(_),
+            format!("Expecting trait name after `dyn` in `{}` type annotation", caller)
+        );
+        Ok(Type::Dyn(trait_name.to_owned()))
+    }
+
+    // FnType ::= 'fn' '(' ( TypeAntn ( ',' TypeAntn )* )? ')' ( '->' TypeAntn )? ;
+    fn parse_fn_type(&mut self, caller: &str) -> Result<Type, ParseError> {
+        expect_next_token!(
+            self.tokens,
+            TokenType::OpenParen,
+            format!("Expecting `(` after `fn` in `{}` type annotation", caller)
+        );
+
+        let mut params = vec![];
+        if token_is_and_then!(self.tokens.peek(), TokenType::CloseParen, self.tokens.next()).is_none() {
+            params.push(self.parse_type_antn(caller)?);
+            while token_is_and_then!(self.tokens.peek(), TokenType::Comma, self.tokens.next()).is_some() {
+                params.push(self.parse_type_antn(caller)?);
+            }
+            expect_next_token!(
+                self.tokens,
+                TokenType::CloseParen,
+                format!("Expecting `,` or `)` in `{}` function type annotation", caller)
+            );
+        }
+
+        let ret_ty = token_is_and_then!(self.tokens.peek(), TokenType::Op(Operator::RetType), {
+            self.tokens.next(); // Eat `->`
+            self.parse_type_antn(caller)?
+        });
+
+        Ok(Type::Fn(params, Box::new(ret_ty.unwrap_or_default())))
+    }
+
     // TypedDecl ::= ident ':' TypeAntn ;
     fn parse_typed_decl(&mut self, caller: &str) -> Result<(String, Type), ParseError> {
         let err = match caller {
@@ -807,9 +1943,34 @@ impl<'a> Parse<'a> {
         Ok(args)
     }
 
+    // Add methods and trait names from `impl` blocks to the struct symbols
+    fn add_impls_to_structs(&mut self) {
+        for (struct_name, trait_name, methods, token) in std::mem::take(&mut self.impls) {
+            match self.symbol_table.get(&struct_name).cloned() {
+                Some(mut sym) if sym.kind() == "Struct" => {
+                    if sym.impls().contains(&trait_name) {
+                        self.errors.push(ParseError::from((
+                            format!("trait `{}` is already implemented for `{}`", trait_name, struct_name),
+                            &token,
+                        )));
+                        continue;
+                    }
+                    sym.add_impl(&trait_name, &methods);
+                    self.symbol_table.insert(sym);
+                },
+                _ => self.errors.push(ParseError::from((
+                    format!("unknown struct `{}` in impl of `{}`", struct_name, trait_name),
+                    &token,
+                ))),
+            }
+        }
+    }
+
     // Add error to parse errors and try to recover
     fn push_err(&mut self, e: ParseError) {
         self.errors.push(e);
+        // Attributes waiting on a declaration that failed to parse are dropped
+        self.attrs.clear();
         self.recover();
     }
 
@@ -845,3 +2006,44 @@ impl<'a> Parse<'a> {
         }
     }
 }
+
+// Rebuild source text from a run of tokens. Used to quote expressions in runtime messages,
+// so only the spacing is approximate.
+fn spell_tokens<'t>(tokens: impl Iterator<Item = &'t Token>) -> String {
+    use TokenType::*;
+
+    let mut src = String::new();
+    let mut prev: Option<&TokenType> = None;
+    for token in tokens {
+        let spelling = match &token.tt {
+            Bool(b) => b.to_string(),
+            Char(c) => format!("'{}'", c),
+            Str(s) => format!("\"{}\"", s),
+            Num(n) | Ident(n) => n.to_owned(),
+            Op(op) => op.to_string(),
+            OpenParen => "(".to_string(),
+            CloseParen => ")".to_string(),
+            OpenBracket => "[".to_string(),
+            CloseBracket => "]".to_string(),
+            Comma => ",".to_string(),
+            Colon => ":".to_string(),
+            tt => tt.to_string().to_lowercase(),
+        };
+
+        // No space inside brackets, before separators, after a unary `!`, or between a
+        // callee and its arguments
+        let tight = matches!(
+            (prev, &token.tt),
+            (None, _)
+                | (Some(OpenParen | OpenBracket | Dot | Op(Operator::Not)), _)
+                | (_, CloseParen | CloseBracket | Comma | Colon | Dot)
+                | (Some(Ident(_) | CloseParen | CloseBracket), OpenParen | OpenBracket)
+        );
+        if !tight {
+            src.push(' ');
+        }
+        src += &spelling;
+        prev = Some(&token.tt);
+    }
+    src
+}
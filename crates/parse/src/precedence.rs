@@ -11,6 +11,7 @@ impl OpPrec {
         use Operator::*;
         match op {
             Pow => Ok(OpPrec::Right(12)),
+            As => Ok(OpPrec::Left(11)),
             Mul | Div => Ok(OpPrec::Left(10)),
             Add | Sub => Ok(OpPrec::Left(9)),
             Gt | GtEq | Lt | LtEq => Ok(OpPrec::Left(8)),
@@ -88,7 +88,13 @@ fn test_string() {
 
 #[test]
 fn test_extern() {
-    let tests = [["basic", "extern fn cos(x: float)"], ["err", "extern cos(x: float)"]];
+    let tests = [
+        ["basic", "extern fn cos(x: float)"],
+        ["err", "extern cos(x: float)"],
+        ["variadic", "extern fn printf(fmt: *char, ...) -> int"],
+        ["variadic_no_params", "extern fn printf(...)"],
+        ["variadic_not_last", "extern fn printf(fmt: *char, ..., x: int)"],
+    ];
     run_insta!("extern", tests);
 }
 
@@ -226,6 +232,11 @@ fn test_let() {
         ["bad_3", "let x:"],
         ["bad_4", "let x"],
         ["bad_5", "let"],
+        ["inferred", "let x = 1"],
+        ["ptr", "let x: *int"],
+        ["ptr_ptr", "let x: **Foo"],
+        ["wide_int", "let x: uint128 = 18446744073709551616"],
+        ["wide_int_over", "let x: uint128 = 340282366920938463463374607431768211456"],
     ];
     run_insta!("let", tests);
 }
@@ -362,10 +373,86 @@ x.y().z
         ["empty", "struct Foo {}"],
         ["single_line_struct_with_field", "struct Foo { let x: int }"],
         ["single_line_struct_with_method", "struct Foo { fn foo() {} }"],
+        [
+            "static_fn",
+            r#"
+struct Foo {
+    static fn new(a: int) -> int {
+        a
+    }
+}
+"#,
+        ],
+        ["static_not_fn", "struct Foo { static let x: int }"],
+        ["static_eof", "struct Foo { static"],
     ];
     run_insta!("struct", tests)
 }
 
+#[test]
+fn test_struct_attrs() {
+    let tests = [
+        [
+            "repr",
+            r#"
+#[repr(C, packed)]
+struct Foo {
+    let a: int
+}
+"#,
+        ],
+        [
+            "align",
+            r#"
+#[repr(C)]
+#[align(16)]
+struct Foo {}
+"#,
+        ],
+        [
+            "unknown",
+            r#"
+#[foo(1)]
+struct Foo {}
+"#,
+        ],
+        [
+            "packed_align",
+            r#"
+#[repr(packed)]
+#[align(8)]
+struct Foo {}
+"#,
+        ],
+        [
+            "align_not_pow2",
+            r#"
+#[align(3)]
+struct Foo {}
+"#,
+        ],
+        [
+            "bad_target",
+            r#"
+#[repr(C)]
+enum Foo {}
+"#,
+        ],
+    ];
+    run_insta!("struct_attr", tests)
+}
+
+#[test]
+fn test_attrs() {
+    let tests = [
+        ["fn", "#[inline] #[cold] extern fn cos(x: float)"],
+        ["args", r#"#[export_name("c_cos")] #[deprecated] extern fn cos(x: float)"#],
+        ["let", r#"#[deprecated("old")] let x: int = 1"#],
+        ["bad_args", "#[inline(1)] extern fn cos(x: float)"],
+    ];
+    run_insta!("attrs", tests)
+}
+
 #[test]
 fn test_error_recovery() {
     let tests = [
@@ -447,3 +534,404 @@ fn test_while() {
     let tests = [["basic", "while x < 7 { i += 1 }"]];
     run_insta!("while", tests);
 }
+
+#[test]
+fn test_trait() {
+    let tests = [
+        [
+            "decl",
+            r#"
+trait Len {
+    fn len() -> int
+    fn is_empty() -> bool
+}
+"#,
+        ],
+        [
+            "impl",
+            r#"
+struct Foo {
+    let size: int
+}
+trait Len {
+    fn len() -> int
+}
+impl Len for Foo {
+    fn len() -> int { self.size }
+}
+"#,
+        ],
+        [
+            "impl_struct_method_clash",
+            r#"
+struct Foo {
+    let size: int
+    fn len() -> int { 0 }
+}
+trait Len {
+    fn len() -> int
+}
+impl Len for Foo {
+    fn len() -> int { self.size }
+}
+"#,
+        ],
+        [
+            "impl_trait_method_clash",
+            r#"
+struct Foo {
+    let size: int
+}
+trait Len {
+    fn len() -> int
+}
+trait Size {
+    fn len() -> int
+}
+impl Len for Foo {
+    fn len() -> int { self.size }
+}
+impl Size for Foo {
+    fn len() -> int { 0 }
+}
+"#,
+        ],
+        [
+            "generic_bounds",
+            r#"
+fn total<T: Len + Show, U>(x: T, y: U) -> int {
+    x.len()
+}
+"#,
+        ],
+        [
+            "body_in_decl",
+            r#"
+trait Len {
+    fn len() -> int { 1 }
+}
+"#,
+        ],
+        [
+            "impl_unknown_struct",
+            r#"
+trait Len {
+    fn len() -> int
+}
+impl Len for Foo {
+    fn len() -> int { 1 }
+}
+"#,
+        ],
+        [
+            "generic_method",
+            r#"
+struct Foo {
+    fn bar<T>(x: T) {}
+}
+"#,
+        ],
+        [
+            "dyn",
+            r#"
+fn area_of(s: dyn Shape) -> float {
+    s.area()
+}
+let shapes: [dyn Shape; 2]
+"#,
+        ],
+        [
+            "dyn_missing_trait",
+            r#"
+let s: dyn = foo
+"#,
+        ],
+    ];
+    run_insta!("trait", tests);
+}
+
+#[test]
+fn test_enum() {
+    let tests = [
+        [
+            "decl",
+            r#"
+enum Shape {
+    Circle(float),
+    Rect(float, float),
+    Empty
+}
+"#,
+        ],
+        [
+            "c_like",
+            r#"
+enum Color { Red, Green = 5, Blue, Black = -1 }
+"#,
+        ],
+        [
+            "construct",
+            r#"
+fn main() {
+    let s: Shape = Shape::Rect(1.0, 2.0)
+    let c: Color = Color::Red
+}
+"#,
+        ],
+        [
+            "dup_variant",
+            r#"
+enum Color { Red, Red }
+"#,
+        ],
+        [
+            "dup_discriminant",
+            r#"
+enum Color { Red = 1, Green = 0, Blue }
+"#,
+        ],
+        [
+            "bad_discriminant",
+            r#"
+enum Color { Red = 3000000000 }
+"#,
+        ],
+        [
+            "empty",
+            r#"
+enum Color {}
+"#,
+        ],
+        [
+            "missing_comma",
+            r#"
+enum Color { Red Green }
+"#,
+        ],
+    ];
+    run_insta!("enum", tests);
+}
+
+#[test]
+fn test_match() {
+    let tests = [
+        [
+            "lits",
+            r#"
+fn main() {
+    let x: int = match y {
+        0 => 1,
+        -1 => 2
+        'a' => 3,
+        true => { 4 }
+        _ => 5,
+    }
+}
+"#,
+        ],
+        [
+            "ranges",
+            r#"
+fn main() {
+    match c {
+        'a'..='z' => 1,
+        0..10 => 2,
+        _ => 3
+    }
+}
+"#,
+        ],
+        [
+            "bindings",
+            r#"
+fn main() {
+    match s {
+        Shape::Circle(r) => r,
+        Shape::Rect(w, _) if w > 1.0 => w,
+        Shape::Empty => 0.0,
+        x => 1.0
+    }
+}
+"#,
+        ],
+        [
+            "no_arms",
+            r#"
+fn main() {
+    match x {}
+}
+"#,
+        ],
+        [
+            "missing_arrow",
+            r#"
+fn main() {
+    match x {
+        0 1
+    }
+}
+"#,
+        ],
+        [
+            "missing_comma",
+            r#"
+fn main() {
+    match x { 0 => 1 _ => 2 }
+}
+"#,
+        ],
+        [
+            "bad_field",
+            r#"
+fn main() {
+    match s {
+        Shape::Circle(1) => 1
+    }
+}
+"#,
+        ],
+    ];
+    run_insta!("match", tests);
+}
+
+#[test]
+fn test_tuple() {
+    let tests = [
+        [
+            "basic",
+            r#"
+fn divmod(a: int, b: int) -> (int, int) {
+    (a / b, a - a / b * b,)
+}
+
+fn main() {
+    let t: (int, (bool, float)) = (1, (true, 2.5))
+    t.1.0 = false
+    let (q, _) = divmod(7, 2)
+    let (x, y): (int, int) = (q, t.0)
+}
+"#,
+        ],
+        [
+            "paren",
+            r#"
+fn main() {
+    let x: int = (1 + 2) * 3
+}
+"#,
+        ],
+        ["one_name", "let (q) = divmod(7, 2)"],
+        ["missing_assign", "let (q, r) divmod(7, 2)"],
+        ["one_type", "let x: (int) = 1"],
+        ["bad_index", "t.1.5e"],
+    ];
+    run_insta!("tuple", tests);
+}
+
+#[test]
+fn test_closure() {
+    let tests = [
+        [
+            "basic",
+            r#"
+fn main() {
+    let offset: int = 5
+    let add = |x: int, y: int| -> int { x + y + offset }
+    let z: int = add(1, 2)
+}
+"#,
+        ],
+        ["move", "let f = move |x: int,| { offset = x }"],
+        ["no_params", "let f = || -> bool { true }"],
+        ["bad_param", "let f = |x| { x }"],
+        ["bad_param_list", "let f = |x: int y: int| { x }"],
+        ["bad_move", "let f = move x"],
+        ["no_body", "let f = |x: int| x + 1"],
+    ];
+    run_insta!("closure", tests);
+}
+
+#[test]
+fn test_fn_ptr() {
+    let tests = [
+        [
+            "types",
+            r#"
+extern fn atexit(cb: fn()) -> int
+fn apply(f: fn(int, int) -> bool, x: int) -> bool { f(x, x) }
+struct Handler {
+    let on_event: fn((int, bool)) -> fn(int)
+}
+"#,
+        ],
+        ["let", "let f: fn(int) -> int = plusOne"],
+        ["bad_no_paren", "let f: fn int"],
+        ["bad_param_list", "let f: fn(int bool)"],
+    ];
+    run_insta!("fn_ptr", tests);
+}
+
+#[test]
+fn test_cast() {
+    let tests = [
+        ["basic", "let x = y as float"],
+        ["unary", "let x = -y as uint8"],
+        ["mul", "let x = a * b as float"],
+        ["chained", "let x = c as int8 as char"],
+        ["call", "let x = foo(y as int64) as double"],
+        ["bad_type", "let x = y as 1"],
+    ];
+    run_insta!("cast", tests);
+}
+
+#[test]
+fn test_panic() {
+    let tests = [
+        ["basic", r#"panic("boom")"#],
+        ["in_cond", r#"if x > 1 { panic("too big") }"#],
+        ["no_message", "panic()"],
+    ];
+    run_insta!("panic", tests);
+}
+
+#[test]
+fn test_assert() {
+    let tests = [
+        ["basic", "assert(x > 1)"],
+        ["message", r#"assert(foo(a, b) == x[0], "bad foo")"#],
+        ["debug", "debug_assert(!done)"],
+        ["no_paren", "assert x > 1"],
+        ["bad_message", "assert(x, 1)"],
+    ];
+    run_insta!("assert", tests);
+}
+
+#[test]
+fn test_debug() {
+    let tests = [["basic", "debug(p)"], ["expr", "debug(foo(a) + 1)"], ["no_paren", "debug p"]];
+    run_insta!("debug", tests);
+}
+
+#[test]
+fn test_print() {
+    let tests = [
+        ["basic", "println(\"x = {}, y = {}\", x, foo(1))"],
+        ["no_args", "print(\"hello\")"],
+        ["no_fmt", "println(x)"],
+    ];
+    run_insta!("print", tests);
+}
+
+#[test]
+fn test_struct_lit() {
+    let tests = [
+        ["basic", "let p = Point { x: 1, y: 2 }"],
+        ["shorthand", "Point { x, y: 2, }"],
+        ["multi_line", "let p = Point {\n    x: 1,\n    y: 2\n}"],
+        ["nested", "Line { a: Point { x, y }, b: p }"],
+        ["in_cond", "if p == (Point { x, y }) { foo }"],
+        ["bad_field", "Point { 1 }"],
+        ["missing_comma", "Point { x: 1 y: 2 }"],
+    ];
+    run_insta!("struct_lit", tests);
+}
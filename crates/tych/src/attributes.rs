@@ -0,0 +1,102 @@
+use super::*;
+
+// The parser only checks attribute syntax and args. Which declarations an attribute can be
+// used on is checked here, along with the warnings for:
+//   - uses of `#[deprecated]` functions, structs, and variables
+//   - discarded results of `#[must_use]` functions and of `#[must_use]` struct types
+
+pub(super) const FN_ATTRS: &[&str] = &["inline", "noinline", "cold", "export_name", "deprecated", "must_use"];
+pub(super) const STRUCT_ATTRS: &[&str] = &["repr", "align", "deprecated", "must_use"];
+pub(super) const LET_ATTRS: &[&str] = &["deprecated"];
+
+impl<'a> Tych<'a> {
+    pub(super) fn check_attrs(
+        &self, attrs: &[Attribute], allowed: &[&str], target: &str,
+    ) -> Result<(), String> {
+        let mut seen = HashSet::new();
+        for attr in attrs {
+            if !allowed.contains(&attr.name.as_str()) {
+                return Err(format!("`#[{}]` can't be used on {}", attr.name, target));
+            }
+            if !seen.insert(attr.name.as_str()) {
+                return Err(format!("Duplicate `#[{}]` on {}", attr.name, target));
+            }
+        }
+
+        if seen.contains("inline") && seen.contains("noinline") {
+            return Err(format!("`#[inline]` and `#[noinline]` can't be used together on {}", target));
+        }
+
+        Ok(())
+    }
+
+    // Externs are named by the library that defines them and generic functions are only
+    // templates for their instances, so neither can be renamed
+    pub(super) fn check_fn_attrs(&self, proto: &Prototype, is_extern: bool) -> Result<(), String> {
+        let target = match is_extern {
+            true => format!("extern `{}`", proto.name()),
+            false => format!("function `{}`", proto.name()),
+        };
+        self.check_attrs(proto.attrs(), FN_ATTRS, &target)?;
+
+        if proto.attr("export_name").is_some() && (is_extern || proto.is_generic()) {
+            return Err(format!("`#[export_name]` can't be used on {}", target));
+        }
+
+        Ok(())
+    }
+
+    // Warn if `sym` is deprecated. `what` describes the symbol, like "function `main::foo`"
+    pub(super) fn warn_deprecated(&mut self, sym: &Symbol, what: &str) {
+        if let Some(attr) = sym.attr("deprecated") {
+            let warning = match attr.arg() {
+                Some(note) => format!("{} is deprecated: {}", what, note),
+                None => format!("{} is deprecated", what),
+            };
+            self.warnings.push(warning);
+        }
+    }
+
+    // Warn if a block statement throws away a value that must be used. Either the
+    // function that produced it or the value's struct type can ask for this
+    pub(super) fn warn_unused(&mut self, node: &ast::Node) {
+        // The tych names struct method calls by their `_{struct}_{method}` symbol. Calls on
+        // trait objects keep the bare method name, which isn't a symbol of its own
+        let fn_name = match &node.kind {
+            ast::node::Kind::Call { name, .. } => Some(name),
+            ast::node::Kind::MSelector { comp, name, .. } => match comp.ty() {
+                Some(Type::Dyn(_)) => None,
+                _ => Some(name),
+            },
+            _ => None,
+        };
+        if let Some(fn_sym) = fn_name.and_then(|name| self.symbol_table.get(name)) {
+            if fn_sym.kind() == "Fn" && fn_sym.attr("must_use").is_some() {
+                let warning = format!("unused result of `{}` which must be used", fn_display_name(fn_sym));
+                self.warnings.push(warning);
+                return;
+            }
+        }
+
+        if let Some(Type::Comp(struct_name)) = node.ty() {
+            if let Some(struct_sym) = self.symbol_table.get(struct_name) {
+                if struct_sym.attr("must_use").is_some() {
+                    self.warnings
+                        .push(format!("unused value of struct `{}` which must be used", struct_name));
+                }
+            }
+        }
+    }
+}
+
+// Methods are named `_{struct}_{method}` in the symbol table. Show them as `struct.method`
+pub(super) fn fn_display_name(fn_sym: &Symbol) -> String {
+    let fq_name = fn_sym.fq_name().unwrap_or_default();
+    match fn_sym.member_of() {
+        Some(struct_name) => match fq_name.strip_prefix(&format!("_{}_", struct_name)) {
+            Some(method) => format!("{}.{}", struct_name, method),
+            None => fq_name.to_owned(),
+        },
+        None => fq_name.to_owned(),
+    }
+}
@@ -0,0 +1,139 @@
+use super::*;
+
+// A closure's type is a struct holding its environment: one field per captured variable.
+// By default captures are by reference, so each field is a pointer to the variable.
+// `move` captures copies instead. The body becomes the struct's `call` method, with the
+// environment as `self`, and calling a closure variable calls that method.
+//
+// Captures are found while checking the body. Any variable it uses that was declared
+// outside of the closure, but not at the global level, is captured.
+
+// Name of the environment parameter in the closure's `call` method. Not a valid
+// identifier, so it can't clash with a capture
+const ENV_PARAM: &str = "@env";
+
+impl<'a> Tych<'a> {
+    pub(super) fn check_closure(
+        &mut self, params: Vec<(String, Type)>, ret_ty: Type, body: ast::Node, is_move: bool,
+    ) -> Result<ast::Node, String> {
+        let mut resolved_params = vec![];
+        for (name, ty) in params {
+            let param_ty = match self.resolve_type(&ty) {
+                Some(ty) => ty,
+                None => return Err(format!("Unknown type for closure parameter `{}`: `{}`", name, ty)),
+            };
+            resolved_params.push((name, param_ty));
+        }
+
+        let ret_ty = match self.resolve_type(&ret_ty) {
+            Some(Type::Comp(name)) => return Err(format!("Closures can't return structs. Found `{}`", name)),
+            Some(ty) => ty,
+            None => return Err(format!("Unknown return type for closure: `{}`", ret_ty)),
+        };
+
+        // Params live in the closure's own scope so they're never mistaken for captures
+        self.symbol_table.enter_scope();
+        for (name, ty) in &resolved_params {
+            self.symbol_table.insert(Symbol::new_var(name, ty, &self.module));
+        }
+        self.closure_scopes.push((self.symbol_table.scope_depth(), vec![]));
+        let res = self.check_node(body, None);
+        let (_, captures) =
+            self.closure_scopes.pop().unwrap_or_else(|| unreachable!("missing closure scope"));
+        self.symbol_table.leave_scope();
+
+        let body_node = res?;
+        let body_ty = body_node.ty().cloned().unwrap_or_default();
+        if ret_ty != Type::Void && ret_ty != body_ty {
+            return Err(format!(
+                "closure should return type `{}` but last statement is `{}`",
+                ret_ty, body_ty
+            ));
+        }
+
+        // A `move` closure can outlive the scope it was made in, so it can't hold on to a
+        // pointer into that scope
+        let mut fields = Vec::with_capacity(captures.len());
+        for (name, ty) in &captures {
+            let field_ty = match (is_move, ty) {
+                (true, Type::Ptr(_)) => {
+                    return Err(format!("`move` closures can't capture `{}` of type `{}` by value", name, ty))
+                },
+                (true, _) => ty.clone(),
+                (false, _) => pointer_wrap!(ty),
+            };
+            fields.push((name.to_owned(), field_ty));
+        }
+
+        let env_name = format!("{}::{}", self.module, self.symbol_table.uniq_ident(Some("closure")));
+        let env_ty = Type::Comp(env_name.clone());
+
+        let sym_fields: Vec<_> = fields.iter().map(|(name, ty)| (name.to_owned(), ty.to_string())).collect();
+        let env_sym = Symbol::new_struct(
+            &env_name,
+            Some(&sym_fields),
+            Some(&[String::from("call")]),
+            &self.module,
+            false,
+        );
+        self.insert_closure_symbol(&env_name, env_sym);
+        self.types.push(env_name.clone());
+
+        let mut call_params = vec![(ENV_PARAM.to_owned(), pointer_wrap!(env_ty))];
+        call_params.extend(resolved_params.clone());
+        let call_proto = Prototype::new(
+            format!("_{}_call", env_name),
+            call_params,
+            ret_ty.clone(),
+            false,
+            self.module.clone(),
+            Some(env_name.clone()),
+        );
+        let mut call_sym = Symbol::from(&call_proto);
+        call_sym.is_exportable = false;
+        self.insert_closure_symbol(call_proto.name(), call_sym);
+        self.closure_envs.insert(env_name);
+
+        Ok(ast::Node::new_closure(resolved_params, ret_ty, body_node, is_move, fields, Some(env_ty)))
+    }
+
+    // Calling a closure variable calls its `call` method
+    pub(super) fn check_closure_call(
+        &mut self, name: String, args: Vec<ast::Node>, ty: Option<Type>,
+    ) -> Result<ast::Node, String> {
+        let env_name = match self.symbol_table.get(&name).map(|sym| sym.ty()) {
+            Some(Type::Comp(env_name)) if self.closure_envs.contains(env_name) => env_name.to_owned(),
+            Some(ty) => return Err(format!("`{}` of type `{}` isn't a function or closure", name, ty)),
+            None => unreachable!("missing symbol for `{}` in `check_closure_call()`", name),
+        };
+
+        // Keep errors about the variable, rather than the method
+        self.visit_mselector(ast::Node::new_ident(name.clone(), None), String::from("call"), args, ty)
+            .map_err(|e| e.replace(&format!("{}.call", env_name), &name))
+    }
+
+    // Called for every variable that's used. Records it as a capture of each closure it's
+    // declared outside of
+    pub(super) fn capture(&mut self, name: &str, ty: &Type) {
+        let depth = match self.symbol_table.depth_of(name) {
+            Some(depth) if depth > 0 => depth,
+            _ => return,
+        };
+        for (closure_depth, captures) in self.closure_scopes.iter_mut() {
+            if depth < *closure_depth && !captures.iter().any(|(n, _)| n == name) {
+                captures.push((name.to_owned(), ty.clone()));
+            }
+        }
+    }
+
+    // The environment and `call` method outlive the scope the closure is in. Closures in
+    // generic templates only have placeholder types, so those are dropped with the
+    // template
+    fn insert_closure_symbol(&mut self, name: &str, sym: Symbol) {
+        if self.template_params.is_empty() {
+            self.symbol_table.insert_global(name, sym);
+        } else {
+            self.symbol_table.insert_with_name(name, sym);
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+
+use super::*;
+
+// Generic functions are monomorphized. A template is checked once against its trait
+// bounds, using a placeholder struct for each generic parameter. Every call then creates
+// (or reuses) an instance of the template with the concrete types substituted, so method
+// calls through a bound dispatch statically to `_{struct}_{method}`.
+//
+// Only structs can satisfy a bound, since traits can't be implemented for primitives.
+// Templates aren't exported, so a generic function can only be called from the module
+// that defines it.
+
+impl<'a> Tych<'a> {
+    // Check a generic function using its bounds. Each generic parameter becomes a
+    // placeholder struct that only has the methods of its traits
+    pub(super) fn check_template(&mut self, node: ast::Node) -> Result<(), String> {
+        let (proto, body) = match node.kind {
+            ast::node::Kind::Fn { proto, body } => (proto, body),
+            _ => unreachable!("expected generic function in `check_template()`"),
+        };
+
+        // Placeholders are dropped with the scope
+        self.symbol_table.enter_scope();
+
+        for (param, bounds) in proto.generics() {
+            if self.resolve_type(&Type::Comp(param.to_owned())).is_some() {
+                return Err(format!("generic parameter `{}` of `{}()` shadows a type", param, proto.name()));
+            }
+
+            let self_ty = pointer_wrap!(Type::Comp(param.to_owned()));
+            let mut placeholder = Symbol::new_struct(param, Some(&[]), Some(&[]), &self.module, false);
+            for bound in bounds {
+                let trait_sym = self.resolve_trait(bound)?;
+                let mut methods = vec![];
+                for method in trait_sym.trait_methods() {
+                    let (params, ret_ty) = self.resolve_method_sig(&trait_sym.name, method)?;
+                    let mut params_with_self = vec![(String::from("self"), self_ty.clone())];
+                    params_with_self.extend(params);
+
+                    // A call to a method that's in two bounds would be ambiguous
+                    let method_name = format!("_{}_{}", param, method.name);
+                    let prev = self.symbol_table.insert(Symbol::new_fn(
+                        (&method_name, &method_name),
+                        &params_with_self,
+                        &ret_ty,
+                        false,
+                        &self.module,
+                        false,
+                        Some(param),
+                    ));
+                    if prev.is_some() {
+                        return Err(format!(
+                            "method `{}` of generic parameter `{}` is in more than one of its bounds",
+                            method.name, param
+                        ));
+                    }
+                    methods.push(method.name.clone());
+                }
+                placeholder.add_impl(&trait_sym.name, &methods);
+            }
+            self.symbol_table.insert(placeholder);
+            self.types.push(param.to_owned());
+            self.template_params.push(param.to_owned());
+        }
+
+        let res = self.visit_fn(proto, body.map(|b| *b));
+
+        let template_params = std::mem::take(&mut self.template_params);
+        self.types.retain(|ty| !template_params.contains(ty));
+        self.symbol_table.leave_scope();
+
+        res.map(|_| ())
+    }
+
+    // Infer the generic parameters of a call from its args, check them against the trait
+    // bounds, and queue an instance of the template. Returns the instance's symbol and the
+    // args that were checked to infer the parameters, so they aren't checked again.
+    pub(super) fn instantiate(
+        &mut self, template: &ast::Node, args: &[ast::Node],
+    ) -> Result<(Symbol, Vec<Option<ast::Node>>), String> {
+        let (proto, body) = match &template.kind {
+            ast::node::Kind::Fn { proto, body } => (proto, body),
+            _ => unreachable!("expected generic function in `instantiate()`"),
+        };
+
+        if proto.params().len() != args.len() {
+            return Err(format!(
+                "call to `{}()` takes {} args and {} were given",
+                proto.name(),
+                proto.params().len(),
+                args.len()
+            ));
+        }
+
+        // Bind each generic parameter to the type of the args that use it
+        let mut bindings: HashMap<String, Type> = HashMap::new();
+        let mut chkd_args = Vec::with_capacity(args.len());
+        for ((_, param_ty), arg) in proto.params().iter().zip(args) {
+            let name = match param_ty {
+                Type::Comp(name) if proto.generics().iter().any(|(g, _)| g == name) => name,
+                _ => {
+                    chkd_args.push(None);
+                    continue;
+                },
+            };
+
+            let chkd_arg = self.check_node(arg.clone(), None)?;
+            let arg_ty = match chkd_arg.ty().unwrap_or_default() {
+                Type::Ptr(ty) => *ty.clone(),
+                ty => ty.clone(),
+            };
+            chkd_args.push(Some(chkd_arg));
+
+            match bindings.get(name) {
+                Some(ty) if ty != &arg_ty => {
+                    return Err(format!(
+                        "conflicting types for generic parameter `{}` in call to `{}()`: `{}` and `{}`",
+                        name,
+                        proto.name(),
+                        ty,
+                        arg_ty
+                    ))
+                },
+                _ => {
+                    bindings.insert(name.to_owned(), arg_ty);
+                },
+            }
+        }
+
+        // Every generic parameter must be known and satisfy its bounds
+        let mut concrete_tys = vec![];
+        for (param, bounds) in proto.generics() {
+            let ty = bindings.get(param).ok_or(format!(
+                "can't infer the type of generic parameter `{}` in call to `{}()`",
+                param,
+                proto.name()
+            ))?;
+
+            for bound in bounds {
+                let trait_name = self.resolve_trait(bound)?.name;
+                let is_implemented = match ty {
+                    Type::Comp(name) => self
+                        .symbol_table
+                        .resolve_symbol(name, &self.module)
+                        .is_some_and(|sym| sym.impls().contains(&trait_name)),
+                    _ => false,
+                };
+                if !is_implemented {
+                    return Err(format!(
+                        "`{}` doesn't implement trait `{}` required by `{}()`",
+                        ty,
+                        trait_name,
+                        proto.name()
+                    ));
+                }
+            }
+            concrete_tys.push(ty.to_string());
+        }
+
+        let mut inst_proto = proto.clone();
+        inst_proto.set_name(format!("{}<{}>", proto.name(), concrete_tys.join(",")));
+        inst_proto.set_generics(vec![]);
+        inst_proto.set_params(
+            proto
+                .params()
+                .iter()
+                .map(|(name, ty)| (name.to_owned(), substitute_type(ty, &bindings)))
+                .collect(),
+        );
+        inst_proto.set_ret_ty(substitute_type(proto.ret_ty(), &bindings));
+
+        // Queue the instance the first time it's used. Nothing is instantiated while
+        // checking a template since its types are only placeholders
+        if self.template_params.is_empty() && self.instantiated.insert(inst_proto.name().to_owned()) {
+            let mut substitute = Substitute { bindings: &bindings };
+            let body = body.as_ref().map(|body| substitute.visit_node(*body.clone()));
+            self.instances.push(ast::Node::new_fn(inst_proto.clone(), body));
+        }
+
+        Ok((Symbol::from(&inst_proto), chkd_args))
+    }
+}
+
+fn substitute_type(ty: &Type, bindings: &HashMap<String, Type>) -> Type {
+    match ty {
+        Type::Comp(name) => bindings.get(name).cloned().unwrap_or_else(|| ty.clone()),
+        Type::SArray(inner, size) => Type::SArray(Box::new(substitute_type(inner, bindings)), *size),
+        Type::Ptr(inner) => Type::Ptr(Box::new(substitute_type(inner, bindings))),
+        Type::Tuple(tys) => Type::Tuple(tys.iter().map(|ty| substitute_type(ty, bindings)).collect()),
+        Type::Fn(params, ret_ty) => Type::Fn(
+            params.iter().map(|ty| substitute_type(ty, bindings)).collect(),
+            Box::new(substitute_type(ret_ty, bindings)),
+        ),
+        _ => ty.clone(),
+    }
+}
+
+// Replaces generic parameters with concrete types in the annotations of an unchecked
+// function body
+struct Substitute<'b> {
+    bindings: &'b HashMap<String, Type>,
+}
+
+impl<'b> Substitute<'b> {
+    fn visit_nodes(&mut self, nodes: Vec<ast::Node>) -> Vec<ast::Node> {
+        nodes.into_iter().map(|node| self.visit_node(node)).collect()
+    }
+}
+
+impl<'b> ast::Visitor for Substitute<'b> {
+    type AstNode = ast::Node;
+    type Result = ast::Node;
+
+    fn visit_node(&mut self, node: Self::AstNode) -> Self::Result {
+        node.accept(self)
+    }
+
+    fn visit_for(
+        &mut self, start_name: String, start_antn: Type, start_expr: Option<ast::Node>, cond_expr: ast::Node,
+        step_expr: ast::Node, body: ast::Node,
+    ) -> Self::Result {
+        ast::Node::new_for(
+            start_name,
+            substitute_type(&start_antn, self.bindings),
+            start_expr.map(|n| self.visit_node(n)),
+            self.visit_node(cond_expr),
+            self.visit_node(step_expr),
+            self.visit_node(body),
+        )
+    }
+
+    fn visit_loop(&mut self, body: ast::Node) -> Self::Result {
+        ast::Node::new_loop(self.visit_node(body))
+    }
+
+    fn visit_while(&mut self, cond_expr: ast::Node, body: ast::Node) -> Self::Result {
+        ast::Node::new_while(self.visit_node(cond_expr), self.visit_node(body))
+    }
+
+    fn visit_let(
+        &mut self, name: String, antn: Option<Type>, init: Option<ast::Node>, attrs: Vec<Attribute>,
+    ) -> Self::Result {
+        ast::Node::new_let(
+            name,
+            antn.map(|antn| substitute_type(&antn, self.bindings)),
+            init.map(|n| self.visit_node(n)),
+            attrs,
+        )
+    }
+
+    fn visit_destructure(&mut self, names: Vec<String>, antn: Option<Type>, init: ast::Node) -> Self::Result {
+        ast::Node::new_destructure(
+            names,
+            antn.map(|antn| substitute_type(&antn, self.bindings)),
+            self.visit_node(init),
+        )
+    }
+
+    fn visit_fn(&mut self, proto: Prototype, body: Option<ast::Node>) -> Self::Result {
+        ast::Node::new_fn(proto, body.map(|n| self.visit_node(n)))
+    }
+
+    fn visit_struct(
+        &mut self, name: String, fields: Vec<ast::Node>, methods: Vec<ast::Node>, attrs: Vec<Attribute>,
+    ) -> Self::Result {
+        ast::Node::new_struct(name, fields, methods, attrs)
+    }
+
+    fn visit_trait(&mut self, name: String, methods: Vec<ast::Node>) -> Self::Result {
+        ast::Node::new_trait(name, methods)
+    }
+
+    fn visit_impl(
+        &mut self, trait_name: String, struct_name: String, methods: Vec<ast::Node>,
+    ) -> Self::Result {
+        ast::Node::new_impl(trait_name, struct_name, methods)
+    }
+
+    fn visit_enum(&mut self, name: String, variants: Vec<(String, Vec<Type>, i64)>) -> Self::Result {
+        ast::Node::new_enum(name, variants)
+    }
+
+    fn visit_break(&mut self) -> Self::Result {
+        ast::Node::new_break()
+    }
+
+    fn visit_next(&mut self) -> Self::Result {
+        ast::Node::new_next()
+    }
+
+    fn visit_panic(&mut self, msg: String, loc: Loc) -> Self::Result {
+        ast::Node::new_panic(msg, loc)
+    }
+
+    fn visit_assert(&mut self, cond: ast::Node, msg: String, debug: bool, loc: Loc) -> Self::Result {
+        ast::Node::new_assert(self.visit_node(cond), msg, debug, loc)
+    }
+
+    fn visit_debug(&mut self, value: ast::Node) -> Self::Result {
+        ast::Node::new_debug(self.visit_node(value))
+    }
+
+    fn visit_print(&mut self, fmt: String, args: Vec<ast::Node>, newline: bool) -> Self::Result {
+        ast::Node::new_print(fmt, self.visit_nodes(args), newline)
+    }
+
+    fn visit_lit(&mut self, value: Literal<ast::Node>, ty: Option<Type>) -> Self::Result {
+        let value = match value {
+            Literal::Array { elements, inner_ty } => {
+                Literal::Array { elements: self.visit_nodes(elements), inner_ty }
+            },
+            Literal::Tuple(elements) => Literal::Tuple(self.visit_nodes(elements)),
+            value => value,
+        };
+        ast::Node::new_lit(value, ty)
+    }
+
+    fn visit_ident(&mut self, name: String, ty: Option<Type>) -> Self::Result {
+        ast::Node::new_ident(name, ty)
+    }
+
+    fn visit_binop(
+        &mut self, op: Operator, lhs: ast::Node, rhs: ast::Node, ty: Option<Type>, loc: Loc,
+    ) -> Self::Result {
+        ast::Node::new_binop(op, self.visit_node(lhs), self.visit_node(rhs), ty, loc)
+    }
+
+    fn visit_unop(&mut self, op: Operator, rhs: ast::Node, ty: Option<Type>) -> Self::Result {
+        ast::Node::new_unop(op, self.visit_node(rhs), ty)
+    }
+
+    fn visit_call(&mut self, name: String, args: Vec<ast::Node>, ty: Option<Type>) -> Self::Result {
+        ast::Node::new_call(name, self.visit_nodes(args), ty)
+    }
+
+    fn visit_indirect_call(
+        &mut self, callee: ast::Node, args: Vec<ast::Node>, ty: Option<Type>,
+    ) -> Self::Result {
+        ast::Node::new_indirect_call(self.visit_node(callee), self.visit_nodes(args), ty)
+    }
+
+    fn visit_cond(
+        &mut self, cond_expr: ast::Node, then_block: ast::Node, else_block: Option<ast::Node>,
+        ty: Option<Type>,
+    ) -> Self::Result {
+        ast::Node::new_cond(
+            self.visit_node(cond_expr),
+            self.visit_node(then_block),
+            else_block.map(|n| self.visit_node(n)),
+            ty,
+        )
+    }
+
+    fn visit_match(
+        &mut self, scrutinee: ast::Node, arms: Vec<MatchArm<ast::Node>>, ty: Option<Type>, loc: Loc,
+    ) -> Self::Result {
+        let arms = arms
+            .into_iter()
+            .map(|arm| {
+                MatchArm::new(arm.pattern, arm.guard.map(|n| self.visit_node(n)), self.visit_node(arm.body))
+            })
+            .collect();
+        ast::Node::new_match(self.visit_node(scrutinee), arms, ty, loc)
+    }
+
+    fn visit_block(&mut self, list: Vec<ast::Node>, ty: Option<Type>) -> Self::Result {
+        ast::Node::new_block(self.visit_nodes(list), ty)
+    }
+
+    fn visit_index(
+        &mut self, binding: ast::Node, idx: ast::Node, ty: Option<Type>, loc: Loc,
+    ) -> Self::Result {
+        ast::Node::new_index(self.visit_node(binding), self.visit_node(idx), ty, loc)
+    }
+
+    fn visit_fselector(&mut self, comp: ast::Node, field: String, ty: Option<Type>) -> Self::Result {
+        ast::Node::new_fselector(self.visit_node(comp), field, ty)
+    }
+
+    fn visit_mselector(
+        &mut self, comp: ast::Node, name: String, args: Vec<ast::Node>, ty: Option<Type>,
+    ) -> Self::Result {
+        ast::Node::new_mselector(self.visit_node(comp), name, self.visit_nodes(args), ty)
+    }
+
+    fn visit_closure(
+        &mut self, params: Vec<(String, Type)>, ret_ty: Type, body: ast::Node, is_move: bool,
+        captures: Vec<(String, Type)>, ty: Option<Type>,
+    ) -> Self::Result {
+        ast::Node::new_closure(
+            params.into_iter().map(|(name, ty)| (name, substitute_type(&ty, self.bindings))).collect(),
+            substitute_type(&ret_ty, self.bindings),
+            self.visit_node(body),
+            is_move,
+            captures,
+            ty,
+        )
+    }
+
+    fn visit_cast(&mut self, expr: ast::Node, ty: Option<Type>) -> Self::Result {
+        ast::Node::new_cast(self.visit_node(expr), ty.map(|ty| substitute_type(&ty, self.bindings)))
+    }
+
+    fn visit_variant(
+        &mut self, enum_name: String, variant: String, args: Vec<ast::Node>, ty: Option<Type>,
+    ) -> Self::Result {
+        ast::Node::new_variant(enum_name, variant, self.visit_nodes(args), ty)
+    }
+
+    fn visit_struct_lit(
+        &mut self, name: String, fields: Vec<(String, ast::Node)>, ty: Option<Type>,
+    ) -> Self::Result {
+        let fields = fields.into_iter().map(|(field, value)| (field, self.visit_node(value))).collect();
+        ast::Node::new_struct_lit(name, fields, ty)
+    }
+}
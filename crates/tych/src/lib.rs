@@ -1,10 +1,21 @@
-use common::{Literal, Operator, Prototype, Symbol, SymbolTable, Type};
+use std::collections::{HashMap, HashSet};
+
+use common::literal::unescape;
+use common::symbol_table::Symbolic;
+use common::{
+    split_format, Attribute, Literal, Loc, MatchArm, Operator, Pattern, Prototype, Symbol, SymbolTable, Type,
+};
 use parse::ast::{self, Ast, VisitableNode, Visitor};
 
 #[macro_use]
 extern crate common;
 
+mod attributes;
+mod closures;
+mod generics;
+mod literals;
 mod macros;
+mod patterns;
 #[cfg(test)]
 mod tests;
 
@@ -19,8 +30,24 @@ mod tests;
  *   - checks for unknown functions, variables, and types
  *   - resolves type, function, and struct names
  *   - inserts temporary `self` value into methods
- *   - wraps structs in pointers when passing or returning from functions
- *   - wraps structs in pointers when declared as struct members
+ *   - keeps structs by value when passing or returning from functions
+ *   - keeps structs by value when declared as struct members or enum payloads
+ *   - passes `self` to methods as a pointer to its struct
+ *   - checks trait impls against their trait
+ *   - coerces structs into trait objects where a `dyn` type is expected
+ *   - checks enum payload types and variant constructors
+ *   - checks match patterns for exhaustiveness and unreachable arms
+ *   - checks tuple field indexes and destructuring `let`s against their tuple
+ *   - checks generic functions against their trait bounds and instantiates them for
+ *     each set of concrete types they're called with
+ *   - infers the type of `let` statements without an annotation from their initializer
+ *   - finds the variables captured by closures and creates their environment structs
+ *   - checks numeric literals against the range of their type
+ *   - checks numeric `as` casts
+ *   - turns functions used as values into function pointers and checks calls through them
+ *   - checks attributes against their declaration and warns about deprecated items and
+ *     unused `#[must_use]` results
+ *   - checks `print` format strings against their args
  */
 
 pub struct Tych<'a> {
@@ -29,21 +56,91 @@ pub struct Tych<'a> {
     hint: Option<Type>,
     current_struct: Option<String>,
     module: String,
+    generic_fns: HashMap<String, ast::Node>,
+    template_params: Vec<String>,
+    instances: Vec<ast::Node>,
+    instantiated: HashSet<String>,
+    closure_scopes: Vec<(u32, Vec<(String, Type)>)>,
+    closure_envs: HashSet<String>,
+    warnings: Vec<String>,
 }
 
 impl<'a> Tych<'a> {
     pub fn new(module: &str, symbol_table: &'a mut SymbolTable<Symbol>) -> Self {
         let types = symbol_table.types();
-        Tych { module: module.to_owned(), symbol_table, types, hint: None, current_struct: None }
+        Tych {
+            module: module.to_owned(),
+            symbol_table,
+            types,
+            hint: None,
+            current_struct: None,
+            generic_fns: HashMap::new(),
+            template_params: vec![],
+            instances: vec![],
+            instantiated: HashSet::new(),
+            closure_scopes: vec![],
+            closure_envs: HashSet::new(),
+            warnings: vec![],
+        }
+    }
+
+    pub fn walk(self, ast: Ast<ast::Node>) -> Result<Ast<ast::Node>, String> {
+        self.walk_with_warnings(ast).map(|(typed_ast, _)| typed_ast)
     }
 
-    pub fn walk(mut self, ast: Ast<ast::Node>) -> Result<Ast<ast::Node>, String> {
+    // Same as `walk()`, but also returns the warnings found along the way
+    pub fn walk_with_warnings(
+        mut self, ast: Ast<ast::Node>,
+    ) -> Result<(Ast<ast::Node>, Vec<String>), String> {
+        // Generic functions are only templates and don't make it into the typed AST. Save
+        // them first so calls can be checked in any order
+        let (templates, nodes): (Vec<_>, Vec<_>) = ast
+            .into_nodes()
+            .into_iter()
+            .partition(|node| matches!(&node.kind, ast::node::Kind::Fn { proto, .. } if proto.is_generic()));
+        for node in &templates {
+            if let ast::node::Kind::Fn { proto, .. } = &node.kind {
+                self.generic_fns.insert(proto.name().to_owned(), node.clone());
+            }
+        }
+
+        // Struct literals can come before the struct is declared, so record field defaults
+        // ahead of everything else
+        for node in &nodes {
+            if let ast::node::Kind::Struct { name, fields, .. } = &node.kind {
+                self.record_field_defaults(name, fields)?;
+            }
+        }
+
         let mut typed_ast = Ast::new();
-        for node in ast.into_nodes() {
+        for node in nodes {
             let typed_node = node.accept(&mut self)?;
             typed_ast.add(typed_node)
         }
-        Ok(typed_ast)
+
+        for node in templates {
+            self.check_template(node)?;
+        }
+
+        // Check the instances requested so far. Instances can request more instances
+        loop {
+            let instances = std::mem::take(&mut self.instances);
+            if instances.is_empty() {
+                break;
+            }
+            for node in instances {
+                if let ast::node::Kind::Fn { proto, .. } = &node.kind {
+                    self.symbol_table.insert_with_name(proto.name(), Symbol::from(proto));
+                }
+                typed_ast.add(node.accept(&mut self)?);
+            }
+        }
+
+        // Templates and their instances check the same body, so drop repeated warnings
+        let mut seen = HashSet::new();
+        self.warnings.retain(|warning| seen.insert(warning.clone()));
+
+        Ok((typed_ast, self.warnings))
     }
 
     // Wrapper for `visit_node()` to handle hint updates
@@ -62,11 +159,11 @@ impl<'a> Tych<'a> {
             _ => unreachable!("expected array literal"),
         };
 
-        // Clone the inner type hint
-        // TODO: Could ty_hint be None?
-        let (ty, size) = match ty_hint.unwrap() {
-            Type::SArray(ty, sz) => (ty, sz),
-            err => unreachable!("array literal has invalid type hint `{}`", err),
+        // Clone the inner type hint. There's none when a `let` is inferring its type
+        let (ty, size) = match ty_hint {
+            Some(Type::SArray(ty, sz)) => (ty, sz),
+            Some(err) => unreachable!("array literal has invalid type hint `{}`", err),
+            None => return Err("Can't infer the type of an array literal. Add a type annotation".to_string()),
         };
 
         // Make sure array is big enough
@@ -78,6 +175,7 @@ impl<'a> Tych<'a> {
         let mut chkd_elements = Vec::with_capacity(elements.len());
         for el in elements {
             let el_node = self.check_node(el, Some(&ty))?;
+            let el_node = self.coerce(el_node, &ty)?;
             let el_ty = el_node.ty().unwrap_or_default();
             if el_ty != ty.as_ref() {
                 return Err(format!("Array literal's element wrong type: `{}` isn't a `{}`", el_node, ty));
@@ -89,6 +187,33 @@ impl<'a> Tych<'a> {
         Ok((Literal::Array { elements: chkd_elements, inner_ty: Some(*ty.clone()) }, Type::SArray(ty, size)))
     }
 
+    // Elements are checked against the matching element of a tuple hint, if there is one
+    fn check_lit_tuple(
+        &mut self, lit: Literal<ast::Node>, ty_hint: Option<Type>,
+    ) -> Result<(Literal<ast::Node>, Type), String> {
+        let elements = match lit {
+            Literal::Tuple(elements) => elements,
+            _ => unreachable!("expected tuple literal"),
+        };
+        let hints = match ty_hint {
+            Some(Type::Tuple(tys)) if tys.len() == elements.len() => tys.into_iter().map(Some).collect(),
+            _ => vec![None; elements.len()],
+        };
+
+        let mut chkd_elements = Vec::with_capacity(elements.len());
+        let mut tys = Vec::with_capacity(elements.len());
+        for (el, hint) in elements.into_iter().zip(hints) {
+            let mut el_node = self.check_node(el, hint.as_ref())?;
+            if let Some(hint) = &hint {
+                el_node = self.coerce(el_node, hint)?;
+            }
+            tys.push(el_node.ty().cloned().unwrap_or_default());
+            chkd_elements.push(el_node);
+        }
+
+        Ok((Literal::Tuple(chkd_elements), Type::Tuple(tys)))
+    }
+
     // Helper for variable initializations
     fn check_var_init(
         &mut self, name: &str, init: Option<&ast::Node>, antn: &Type, caller: &str,
@@ -96,6 +221,7 @@ impl<'a> Tych<'a> {
         // If init exists, make sure it matches the variable's annotation
         if let Some(init) = init {
             let init_node = self.check_node(init.clone(), Some(antn))?;
+            let init_node = self.coerce(init_node, antn)?;
             let init_ty = init_node.ty().unwrap_or_default();
             if antn != init_ty {
                 return Err(format!(
@@ -111,10 +237,30 @@ impl<'a> Tych<'a> {
 
     // Resolve composites to Module::Ty if needed
     fn resolve_type(&self, ty: &Type) -> Option<Type> {
-        if ty.is_primitive() {
-            return Some(ty.to_owned());
-        } else if let Type::SArray(_, _) | Type::Ptr(_) = ty {
-            return Some(ty.to_owned());
+        match ty {
+            ty if ty.is_primitive() => return Some(ty.to_owned()),
+            Type::SArray(inner, size) => {
+                return Some(Type::SArray(Box::new(self.resolve_type(inner)?), *size))
+            },
+            Type::Ptr(inner) => return Some(pointer_wrap!(self.resolve_type(inner)?)),
+            Type::Dyn(trait_name) => {
+                return self.resolve_trait(trait_name).ok().map(|sym| Type::Dyn(sym.name))
+            },
+            Type::Enum(enum_name) => {
+                return self.resolve_enum(enum_name).ok().map(|sym| Type::Enum(sym.name))
+            },
+            Type::Tuple(tys) => {
+                return tys
+                    .iter()
+                    .map(|ty| self.resolve_type(ty))
+                    .collect::<Option<Vec<_>>>()
+                    .map(Type::Tuple)
+            },
+            Type::Fn(params, ret_ty) => {
+                let params = params.iter().map(|ty| self.resolve_type(ty)).collect::<Option<Vec<_>>>()?;
+                return Some(Type::Fn(params, Box::new(self.resolve_type(ret_ty)?)));
+            },
+            _ => (),
         }
 
         let types = [ty.to_string(), format!("{}::{}", self.module, ty)];
@@ -123,7 +269,79 @@ impl<'a> Tych<'a> {
                 return Some(Type::Comp(ty_str));
             }
         }
-        None
+        self.resolve_enum(&ty.to_string()).ok().map(|sym| Type::Enum(sym.name))
+    }
+
+    // A struct can't hold itself by value, directly or through other structs, or it would
+    // have no size. Pointer fields don't count since they break the cycle
+    fn check_struct_cycle(&self, name: &str) -> Result<(), String> {
+        let mut path = vec![];
+        if self.find_struct_cycle(name, name, &mut path, &mut HashSet::new()) {
+            return Err(format!("circular struct definition: {} -> {}", path.join(" -> "), name));
+        }
+        Ok(())
+    }
+
+    // Depth first search from `name` back to `target`. On success, `path` has the fields
+    // that make up the cycle
+    fn find_struct_cycle(
+        &self, target: &str, name: &str, path: &mut Vec<String>, visited: &mut HashSet<String>,
+    ) -> bool {
+        if !visited.insert(name.to_owned()) {
+            return false;
+        }
+
+        let fields = self.symbol_table.get(name).and_then(|sym| sym.fields()).unwrap_or_default();
+        for (field, ty) in fields {
+            path.push(format!("{}.{}", name, field));
+            let inline =
+                self.resolve_type(&ty.into()).map(|ty| Self::inline_structs(&ty)).unwrap_or_default();
+            if inline.iter().any(|comp| comp == target || self.find_struct_cycle(target, comp, path, visited))
+            {
+                return true;
+            }
+            path.pop();
+        }
+        false
+    }
+
+    // The resolved types of a struct's fields
+    fn struct_field_types(&self, name: &str) -> Vec<Type> {
+        let fields = self.symbol_table.get(name).and_then(|sym| sym.fields()).unwrap_or_default();
+        fields.into_iter().flat_map(|(_, ty)| self.resolve_type(&ty.into())).collect()
+    }
+
+    // Types that `==` and `!=` accept. Structs and fixed arrays are equal when all of their
+    // fields or elements are, so those must be comparable too
+    fn is_equatable(&self, ty: &Type) -> bool {
+        match ty {
+            numeric_types!() | Type::Bool | Type::Char => true,
+            Type::SArray(inner, _) => self.is_equatable(inner),
+            Type::Comp(name) => self.struct_field_types(name).iter().all(|ty| self.is_equatable(ty)),
+            _ => false,
+        }
+    }
+
+    // Types that `debug()` can print. Pointers print as addresses and enums as the name of
+    // their variant
+    fn is_debuggable(&self, ty: &Type) -> bool {
+        match ty {
+            numeric_types!() | Type::Bool | Type::Char | Type::Ptr(_) | Type::Fn(..) | Type::Enum(_) => true,
+            Type::SArray(inner, _) => self.is_debuggable(inner),
+            Type::Tuple(tys) => tys.iter().all(|ty| self.is_debuggable(ty)),
+            Type::Comp(name) => self.struct_field_types(name).iter().all(|ty| self.is_debuggable(ty)),
+            _ => false,
+        }
+    }
+
+    // The structs stored inline in a value of type `ty`
+    fn inline_structs(ty: &Type) -> Vec<String> {
+        match ty {
+            Type::Comp(name) => vec![name.to_owned()],
+            Type::SArray(inner, _) => Self::inline_structs(inner),
+            Type::Tuple(tys) => tys.iter().flat_map(Self::inline_structs).collect(),
+            _ => vec![],
+        }
     }
 
     // Helper to get composite name and symbol for selector checking
@@ -143,6 +361,434 @@ impl<'a> Tych<'a> {
             .ok_or(format!("Unknown composite type: `{}`", comp_name))?;
         Ok(comp_sym)
     }
+
+    fn resolve_trait(&self, name: &str) -> Result<Symbol, String> {
+        match self.symbol_table.resolve_symbol(name, &self.module) {
+            Some(sym) if sym.kind() == "Trait" => Ok(sym.clone()),
+            _ => Err(format!("Unknown trait: `{}`", name)),
+        }
+    }
+
+    fn resolve_enum(&self, name: &str) -> Result<Symbol, String> {
+        match self.symbol_table.resolve_symbol(name, &self.module) {
+            Some(sym) if sym.kind() == "Enum" => Ok(sym.clone()),
+            _ => Err(format!("Unknown enum: `{}`", name)),
+        }
+    }
+
+    // Split a path like `Shape::Circle` into its enum symbol and variant name, if the path
+    // names an enum
+    fn resolve_variant_path(&self, path: &str) -> Option<(Symbol, String)> {
+        let (enum_name, variant) = path.rsplit_once("::")?;
+        self.resolve_enum(enum_name).ok().map(|sym| (sym, variant.to_owned()))
+    }
+
+    // Split a path like `Array::new` into a struct's name and the name of one of its
+    // associated functions, if the path names a struct
+    fn resolve_assoc_path(&self, path: &str) -> Option<(String, String)> {
+        let (struct_name, fn_name) = path.rsplit_once("::")?;
+        match self.resolve_type(&Type::Comp(struct_name.to_owned()))? {
+            Type::Comp(struct_name) => Some((struct_name, fn_name.to_owned())),
+            _ => None,
+        }
+    }
+
+    // Associated functions are plain functions under the struct's cooked method name
+    fn check_assoc_call(
+        &mut self, struct_name: &str, fn_name: &str, args: Vec<ast::Node>,
+    ) -> Result<ast::Node, String> {
+        let cooked_name = format!("_{}_{}", struct_name, fn_name);
+        match self.symbol_table.get(&cooked_name) {
+            Some(sym) if sym.member_of().is_some() => {
+                return Err(format!("method `{}` of `{}` must be called on a value", fn_name, struct_name))
+            },
+            Some(_) => (),
+            None => {
+                return Err(format!("struct `{}` has no associated function: `{}`", struct_name, fn_name))
+            },
+        }
+
+        self.check_call(cooked_name, Some(format!("{}::{}", struct_name, fn_name)), args)
+    }
+
+    // Check a call to a named function. Errors name the function by `display_name` when the
+    // symbol's own name isn't what was written at the call
+    fn check_call(
+        &mut self, name: String, display_name: Option<String>, args: Vec<ast::Node>,
+    ) -> Result<ast::Node, String> {
+        // Pull the function for the call from the table
+        let fn_entry = self
+            .symbol_table
+            .resolve_symbol(&name, &self.module)
+            .ok_or_else(|| {
+                format!("Call to undefined function: `{}`", display_name.as_ref().unwrap_or(&name))
+            })?
+            .clone();
+
+        // Calls to generic functions are redirected to an instance for the arg types
+        let template = fn_entry.fq_name().and_then(|fq_name| self.generic_fns.get(fq_name)).cloned();
+        let (fn_entry, mut chkd_generic_args) = match template {
+            Some(template) => self.instantiate(&template, &args)?,
+            None => (fn_entry, vec![]),
+        };
+
+        self.warn_deprecated(&fn_entry, &format!("function `{}`", attributes::fn_display_name(&fn_entry)));
+
+        // Now that we have the FQN, use it in the AST
+        let name = fn_entry
+            .fq_name()
+            .unwrap_or_else(|| unreachable!("non-function symbol in `check_call()`"))
+            .to_owned();
+        let display_name = display_name.unwrap_or_else(|| name.clone());
+
+        // Inject a temporary `self` into the call's arguments and the symbol table. Will
+        // be replaced in the lower. Always pass `self` as a pointer
+        let mut args = args;
+        if let Some(struct_name) = fn_entry.member_of() {
+            let self_ty = pointer_wrap!(Type::Comp(struct_name.to_owned()));
+            self.symbol_table.insert(Symbol::new_var("self", &self_ty, &self.module));
+            args.insert(0, ast::Node::new_ident(String::from("self"), Some(self_ty)));
+        }
+
+        // Pull out the function arg types
+        let fe_param_tys = fn_entry.param_tys().to_vec();
+
+        // Check arg length. Account for injected self if method
+        let (fe_params_len, args_len) = match fn_entry.member_of().is_some() {
+            true => (fe_param_tys.len() - 1, args.len() - 1),
+            false => (fe_param_tys.len(), args.len()),
+        };
+        if fn_entry.is_variadic() && args.len() < fe_param_tys.len() {
+            return Err(format!(
+                "call to `{}()` takes at least {} args and {} were given",
+                display_name, fe_params_len, args_len
+            ));
+        }
+        if !fn_entry.is_variadic() && fe_param_tys.len() != args.len() {
+            return Err(format!(
+                "call to `{}()` takes {} args and {} were given",
+                display_name, fe_params_len, args_len
+            ));
+        }
+
+        // Resolve the call's return type.
+        let ret_ty = match self.resolve_type(fn_entry.ret_ty()) {
+            Some(ty) => ty,
+            None => unreachable!("unknown return type in `check_call()`"),
+        };
+
+        // The args past the params of a variadic extern are checked on their own
+        let extra_args = args.split_off(fe_param_tys.len());
+
+        // Check all args and record their types. Use the function entry arg types as type
+        // hints.
+        let mut chkd_args = Vec::with_capacity(args_len);
+        let mut arg_tys = Vec::with_capacity(args_len);
+        for (idx, expr) in args.into_iter().enumerate() {
+            // Args of generic params were already checked when the call was instantiated
+            let chkd_arg = match chkd_generic_args.get_mut(idx).and_then(Option::take) {
+                Some(chkd_arg) => chkd_arg,
+                None => self.check_node(expr, Some(fe_param_tys[idx]))?,
+            };
+            let chkd_arg = self.coerce(chkd_arg, fe_param_tys[idx])?;
+            arg_tys.push((idx, chkd_arg.ty().unwrap_or_default().clone()));
+            chkd_args.push(chkd_arg);
+        }
+
+        // Make sure the function params and the call args jive
+        fe_param_tys.iter().zip(arg_tys).try_for_each(|(fp_ty, (idx, ca_ty))| {
+            // Resolve param type first
+            let fp_ty = match self.resolve_type(fp_ty) {
+                Some(ty) => ty,
+                None => unreachable!("bad arg type in `check_call()`"),
+            };
+            if fp_ty != ca_ty {
+                Err(format!(
+                    "type mismatch in arg {} of call to `{}()`: `{}` != `{}`",
+                    idx + 1,
+                    display_name,
+                    fp_ty,
+                    ca_ty
+                ))
+            } else {
+                Ok(())
+            }
+        })?;
+
+        for (idx, expr) in extra_args.into_iter().enumerate() {
+            // Float literals are doubles, like in C
+            let hint = matches!(&expr.kind, ast::node::Kind::Lit { value: Literal::Double(_), .. })
+                .then_some(&Type::Double);
+            let chkd_arg = self.check_node(expr, hint)?;
+            chkd_args.push(self.promote_variadic_arg(chkd_arg, &display_name, fe_param_tys.len() + idx)?);
+        }
+
+        Ok(ast::Node::new_call(name, chkd_args, Some(ret_ty)))
+    }
+
+    // `Type(args)` is checked as a block that declares a value of the struct, which gets
+    // the field defaults, runs the struct's `init` method on it, and evaluates to it
+    fn check_constructor(&mut self, struct_name: &str, args: Vec<ast::Node>) -> Result<ast::Node, String> {
+        if let Some(struct_sym) = self.symbol_table.get(struct_name).cloned() {
+            self.warn_deprecated(&struct_sym, &format!("struct `{}`", struct_name));
+        }
+
+        let has_init = self
+            .symbol_table
+            .get(struct_name)
+            .and_then(|sym| sym.methods())
+            .unwrap_or_default()
+            .contains(&"init");
+        if !has_init && !args.is_empty() {
+            return Err(format!(
+                "struct `{}` has no `init` method to construct it with {} args",
+                struct_name,
+                args.len()
+            ));
+        }
+
+        let value = self.symbol_table.uniq_ident(None);
+        let mut list =
+            vec![ast::Node::new_let(value.clone(), Some(Type::Comp(struct_name.to_owned())), None, vec![])];
+        if has_init {
+            let comp = ast::Node::new_ident(value.clone(), None);
+            list.push(ast::Node::new_mselector(comp, String::from("init"), args, None));
+        }
+        list.push(ast::Node::new_ident(value, None));
+
+        self.check_node(ast::Node::new_block(list, None), None)
+    }
+
+    // Resolve the payload types of a variant
+    fn resolve_variant_fields(&self, enum_sym: &Symbol, variant: &str) -> Result<Vec<Type>, String> {
+        let field_tys = enum_sym
+            .variants()
+            .iter()
+            .find(|(name, ..)| name == variant)
+            .map(|(_, field_tys, _)| field_tys)
+            .ok_or(format!("enum `{}` has no variant: `{}`", enum_sym.name, variant))?;
+
+        field_tys
+            .iter()
+            .map(|field_ty| match self.resolve_type(&Type::from(field_ty.as_str())) {
+                Some(ty) => Ok(ty),
+                None => {
+                    Err(format!("Unknown type in variant `{}::{}`: `{}`", enum_sym.name, variant, field_ty))
+                },
+            })
+            .collect()
+    }
+
+    // Check a variant's args against its payload types
+    fn check_variant(
+        &mut self, enum_sym: &Symbol, variant: &str, args: Vec<ast::Node>,
+    ) -> Result<ast::Node, String> {
+        let full_name = format!("{}::{}", enum_sym.name, variant);
+        let field_tys = self.resolve_variant_fields(enum_sym, variant)?;
+
+        if field_tys.len() != args.len() {
+            return Err(format!(
+                "variant `{}` takes {} args and {} were given",
+                full_name,
+                field_tys.len(),
+                args.len()
+            ));
+        }
+
+        let mut chkd_args = Vec::with_capacity(args.len());
+        for (idx, (field_ty, arg)) in field_tys.into_iter().zip(args).enumerate() {
+            let chkd_arg = self.check_node(arg, Some(&field_ty))?;
+            let chkd_arg = self.coerce(chkd_arg, &field_ty)?;
+            let arg_ty = chkd_arg.ty().unwrap_or_default();
+            if arg_ty != &field_ty {
+                return Err(format!(
+                    "type mismatch in arg {} of variant `{}`: `{}` != `{}`",
+                    idx + 1,
+                    full_name,
+                    field_ty,
+                    arg_ty
+                ));
+            }
+            chkd_args.push(chkd_arg);
+        }
+
+        Ok(ast::Node::new_variant(
+            enum_sym.name.clone(),
+            variant.to_owned(),
+            chkd_args,
+            Some(Type::Enum(enum_sym.name.clone())),
+        ))
+    }
+
+    // Wrap a struct, or a pointer to one, in a cast to a trait object if that's what's
+    // expected. Anything else is returned as is and left to the caller to check
+    fn coerce(&self, node: ast::Node, expected: &Type) -> Result<ast::Node, String> {
+        let trait_name = match self.resolve_type(expected) {
+            Some(Type::Dyn(trait_name)) => trait_name,
+            _ => return Ok(node),
+        };
+        let struct_name = match node.ty() {
+            Some(Type::Comp(name)) => name,
+            Some(Type::Ptr(boxed)) => match &**boxed {
+                Type::Comp(name) => name,
+                _ => return Ok(node),
+            },
+            _ => return Ok(node),
+        };
+
+        let is_implemented = self
+            .symbol_table
+            .resolve_symbol(struct_name, &self.module)
+            .is_some_and(|sym| sym.kind() == "Struct" && sym.impls().contains(&trait_name));
+        if !is_implemented {
+            return Err(format!("`{}` doesn't implement trait `{}`", struct_name, trait_name));
+        }
+
+        Ok(ast::Node::new_cast(node, Some(Type::Dyn(trait_name))))
+    }
+
+    // Check a method call on a trait object against the trait's signature. `self` isn't
+    // injected. It comes from the trait object's data pointer
+    fn check_dyn_call(
+        &mut self, chkd_comp: ast::Node, trait_name: &str, method_name: String, args: Vec<ast::Node>,
+    ) -> Result<ast::Node, String> {
+        let trait_sym = self.resolve_trait(trait_name)?;
+        let method = trait_sym
+            .trait_methods()
+            .iter()
+            .find(|m| m.name == method_name)
+            .ok_or(format!("trait `{}` has no method: `{}`", trait_name, method_name))?;
+        let (params, ret_ty) = self.resolve_method_sig(trait_name, method)?;
+
+        if params.len() != args.len() {
+            return Err(format!(
+                "call to `{}.{}()` takes {} args and {} were given",
+                trait_name,
+                method_name,
+                params.len(),
+                args.len()
+            ));
+        }
+
+        let mut chkd_args = Vec::with_capacity(args.len());
+        for (idx, ((_, param_ty), arg)) in params.iter().zip(args).enumerate() {
+            let chkd_arg = self.check_node(arg, Some(param_ty))?;
+            let chkd_arg = self.coerce(chkd_arg, param_ty)?;
+            let arg_ty = chkd_arg.ty().unwrap_or_default();
+            if arg_ty != param_ty {
+                return Err(format!(
+                    "type mismatch in arg {} of call to `{}.{}()`: `{}` != `{}`",
+                    idx + 1,
+                    trait_name,
+                    method_name,
+                    param_ty,
+                    arg_ty
+                ));
+            }
+            chkd_args.push(chkd_arg);
+        }
+
+        Ok(ast::Node::new_mselector(chkd_comp, method_name, chkd_args, Some(ret_ty)))
+    }
+
+    // Check a call through a function pointer against the pointer's type. `name` is what
+    // holds the pointer, for errors
+    fn check_indirect_call(
+        &mut self, callee: ast::Node, name: &str, args: Vec<ast::Node>,
+    ) -> Result<ast::Node, String> {
+        let (params, ret_ty) = match callee.ty() {
+            Some(Type::Fn(params, ret_ty)) => (params.clone(), *ret_ty.clone()),
+            _ => unreachable!("callee isn't a function pointer in `check_indirect_call()`"),
+        };
+
+        if params.len() != args.len() {
+            return Err(format!(
+                "call to `{}()` takes {} args and {} were given",
+                name,
+                params.len(),
+                args.len()
+            ));
+        }
+
+        let mut chkd_args = Vec::with_capacity(args.len());
+        for (idx, (param_ty, arg)) in params.iter().zip(args).enumerate() {
+            let chkd_arg = self.check_node(arg, Some(param_ty))?;
+            let chkd_arg = self.coerce(chkd_arg, param_ty)?;
+            let arg_ty = chkd_arg.ty().unwrap_or_default();
+            if arg_ty != param_ty {
+                return Err(format!(
+                    "type mismatch in arg {} of call to `{}()`: `{}` != `{}`",
+                    idx + 1,
+                    name,
+                    param_ty,
+                    arg_ty
+                ));
+            }
+            chkd_args.push(chkd_arg);
+        }
+
+        Ok(ast::Node::new_indirect_call(callee, chkd_args, Some(ret_ty)))
+    }
+
+    // A function used as a value is a pointer to it. Generic functions are only templates,
+    // so there's nothing to point to
+    fn check_fn_ref(&self, fn_sym: &Symbol) -> Result<ast::Node, String> {
+        let fq_name = fn_sym
+            .fq_name()
+            .unwrap_or_else(|| unreachable!("non-function symbol in `check_fn_ref()`"))
+            .to_owned();
+        if self.generic_fns.contains_key(&fq_name) {
+            return Err(format!("Generic function `{}` can't be used as a value", fq_name));
+        }
+        // Function pointer types have a fixed number of params
+        if fn_sym.is_variadic() {
+            return Err(format!("Variadic extern `{}` can't be used as a value", fq_name));
+        }
+
+        let fn_ty =
+            Type::Fn(fn_sym.param_tys().into_iter().cloned().collect(), Box::new(fn_sym.ret_ty().clone()));
+        let fn_ty = self
+            .resolve_type(&fn_ty)
+            .ok_or(format!("Unknown type in signature of `{}`: `{}`", fq_name, fn_ty))?;
+        Ok(ast::Node::new_ident(fq_name, Some(fn_ty)))
+    }
+
+    // Args after the params of a variadic extern get C's default argument promotions.
+    // Small integers, bools, and chars become `int32` and floats become doubles. Only
+    // scalars and pointers can be passed this way
+    fn promote_variadic_arg(&self, node: ast::Node, name: &str, idx: usize) -> Result<ast::Node, String> {
+        let arg_ty = node.ty().cloned().unwrap_or_default();
+        match arg_ty {
+            Type::Int8 | Type::Int16 | Type::UInt8 | Type::UInt16 | Type::Bool | Type::Char => {
+                Ok(ast::Node::new_cast(node, Some(Type::Int32)))
+            },
+            Type::Float => Ok(ast::Node::new_cast(node, Some(Type::Double))),
+            int_types!() | Type::Double | Type::Ptr(_) | Type::Fn(..) => Ok(node),
+            ty => Err(format!("Can't pass `{}` as variadic arg {} of call to `{}()`", ty, idx + 1, name)),
+        }
+    }
+
+    // Resolve the types in a trait method signature
+    fn resolve_method_sig(
+        &self, trait_name: &str, method: &Symbol,
+    ) -> Result<(Vec<(String, Type)>, Type), String> {
+        let mut params = vec![];
+        for (name, ty) in method.params() {
+            let param_ty = self.resolve_type(ty).ok_or(format!(
+                "Unknown argument type in trait method `{}.{}` for `{}`: `{}`",
+                trait_name, method.name, name, ty
+            ))?;
+            params.push((name.to_owned(), param_ty));
+        }
+        let ret_ty = self.resolve_type(method.ret_ty()).ok_or(format!(
+            "Unknown return type in trait method `{}.{}`: `{}`",
+            trait_name,
+            method.name,
+            method.ret_ty()
+        ))?;
+
+        Ok((params, ret_ty))
+    }
 }
 
 impl<'a> ast::Visitor for Tych<'a> {
@@ -202,29 +848,100 @@ impl<'a> ast::Visitor for Tych<'a> {
         Ok(ast::Node::new_while(self.check_node(cond_expr, None)?, self.check_node(body, None)?))
     }
 
-    fn visit_let(&mut self, name: String, antn: Type, init: Option<ast::Node>) -> Self::Result {
-        let mut antn = match self.resolve_type(&antn) {
+    fn visit_let(
+        &mut self, name: String, antn: Option<Type>, init: Option<ast::Node>, attrs: Vec<Attribute>,
+    ) -> Self::Result {
+        // Struct fields can't have any attributes
+        match self.current_struct {
+            Some(_) => self.check_attrs(&attrs, &[], "a struct field")?,
+            None => self.check_attrs(&attrs, attributes::LET_ATTRS, "a variable")?,
+        }
+
+        // Without an annotation, the variable takes the type of its initializer
+        let antn = match antn {
+            Some(antn) => antn,
+            None => {
+                let init =
+                    init.unwrap_or_else(|| unreachable!("`let {}` has no annotation or initializer", name));
+                let init_node = self.check_node(init, None)?;
+                let init_ty = init_node.ty().cloned().unwrap_or_default();
+                if init_ty == Type::Void {
+                    return Err(format!("Can't infer the type of `{}` from a void initial value", name));
+                }
+                let mut sym = Symbol::new_var(&name, &init_ty, &self.module);
+                sym.set_attrs(&attrs);
+                self.symbol_table.insert(sym);
+                return Ok(ast::Node::new_let(name, Some(init_ty), Some(init_node), attrs));
+            },
+        };
+
+        let antn = match self.resolve_type(&antn) {
             Some(ty) => ty,
             None => return Err(format!("unknown type in let declaration: `{}`", antn)),
         };
 
-        // Hack to ensure let declarations in structs are always pointers
-        // TODO: make cleaner
-        if self.current_struct.is_some() && matches!(antn, Type::Comp(_)) {
-            antn = pointer_wrap!(antn);
-        }
-
-        // Don't process initization values for struct fields
+        // Struct field defaults are kept in the struct's symbol, so they must be constants
         let init_node = if self.current_struct.is_none() {
-            self.symbol_table.insert(Symbol::new_var(&name, &antn, &self.module));
+            let mut sym = Symbol::new_var(&name, &antn, &self.module);
+            sym.set_attrs(&attrs);
+            self.symbol_table.insert(sym);
             self.check_var_init(&name, init.as_ref(), &antn, "let statement")?
-        } else if self.current_struct.is_some() && init.is_some() {
-            return Err(format!("initializers aren't supported for struct fields at `{}`", name));
         } else {
-            None
+            let init_node = self.check_var_init(&name, init.as_ref(), &antn, "struct field default")?;
+            match &init_node {
+                Some(ast::Node { kind: ast::node::Kind::Lit { value, .. } })
+                    if value.to_scalar::<()>().is_some() => {},
+                Some(_) => return Err(format!("default for struct field `{}` should be a constant", name)),
+                None => (),
+            }
+            init_node
         };
 
-        Ok(ast::Node::new_let(name, antn, init_node))
+        Ok(ast::Node::new_let(name, Some(antn), init_node, attrs))
+    }
+
+    // The names are bound to the elements of the tuple, which must have as many elements
+    // as there are names. `_` skips an element.
+    fn visit_destructure(&mut self, names: Vec<String>, antn: Option<Type>, init: ast::Node) -> Self::Result {
+        if self.current_struct.is_some() {
+            return Err(format!(
+                "destructuring isn't supported for struct fields at `({})`",
+                names.join(", ")
+            ));
+        }
+
+        let antn = match antn {
+            Some(antn) => match self.resolve_type(&antn) {
+                Some(ty) => Some(ty),
+                None => return Err(format!("unknown type in let declaration: `{}`", antn)),
+            },
+            None => None,
+        };
+
+        let init_node = self.check_node(init, antn.as_ref())?;
+        let init_ty = init_node.ty().cloned().unwrap_or_default();
+        if let Some(antn) = &antn {
+            if antn != &init_ty {
+                return Err(format!(
+                    "Types don't match in let statement. `({})` annotated with `{}` but initial value is `{}`",
+                    names.join(", "),
+                    antn,
+                    init_ty
+                ));
+            }
+        }
+
+        let tys = match &init_ty {
+            Type::Tuple(tys) if tys.len() == names.len() => tys,
+            ty => return Err(format!("Can't destructure `{}` into `({})`", ty, names.join(", "))),
+        };
+        for (name, ty) in names.iter().zip(tys) {
+            if name != "_" {
+                self.symbol_table.insert(Symbol::new_var(name, ty, &self.module));
+            }
+        }
+
+        Ok(ast::Node::new_destructure(names, Some(init_ty), init_node))
     }
 
     fn visit_fn(&mut self, proto: Prototype, body: Option<ast::Node>) -> Self::Result {
@@ -234,17 +951,10 @@ impl<'a> ast::Visitor for Tych<'a> {
             Some(sym) => sym,
             None => unreachable!("missing symbol table entry for function: `{}`", proto.name()),
         };
+        self.check_fn_attrs(&proto, body.is_none())?;
 
         let ret_ty = match self.resolve_type(proto.ret_ty()) {
-            Some(ty) => {
-                // Returned structs should always be pointers
-                // TODO: This is very wrong without memory allocation
-                if let Type::Comp(_) = ty {
-                    pointer_wrap!(ty)
-                } else {
-                    ty
-                }
-            },
+            Some(ty) => ty,
             None => {
                 return Err(format!(
                     "Unknown return type in prototype for `{}`: `{}`",
@@ -254,10 +964,25 @@ impl<'a> ast::Visitor for Tych<'a> {
             },
         };
 
-        // If body is None, this is an extern and no checking is needed
+        // If body is None, this is an extern and no checking is needed. Function pointer
+        // and struct types are resolved so codegen can give the extern their C signature
         let body = match body {
             Some(body) => body,
-            None => return Ok(ast::Node::new_fn(proto, None)),
+            None => {
+                let mut resolved_params = vec![];
+                for (name, ty) in proto.params() {
+                    let param_ty = self.resolve_type(ty).ok_or(format!(
+                        "Unknown argument type in prototype `{}` for `{}`: `{}`",
+                        proto.name(),
+                        name,
+                        ty
+                    ))?;
+                    resolved_params.push((name.clone(), param_ty));
+                }
+                proto.set_params(resolved_params);
+                proto.set_ret_ty(ret_ty);
+                return Ok(ast::Node::new_fn(proto, None));
+            },
         };
 
         // Creates interstitial scope for the arguments in the function definition
@@ -267,7 +992,6 @@ impl<'a> ast::Visitor for Tych<'a> {
         let mut resolved_params = vec![];
         for param in proto.params() {
             let param_ty = match self.resolve_type(&param.1) {
-                Some(Type::Comp(ty)) => pointer_wrap!(Type::Comp(ty)),
                 Some(ty) => ty,
                 None => {
                     return Err(format!(
@@ -285,19 +1009,8 @@ impl<'a> ast::Visitor for Tych<'a> {
 
         // Hack to allow methods to use `let` initializers
         let current_struct = self.current_struct.take();
-        let mut body_node = self.check_node(body, None)?;
-        let mut body_ty = body_node.ty().unwrap_or_default().clone();
-
-        // Make sure the body returns a pointer if a struct is used
-        if let Type::Comp(_) = body_ty {
-            body_ty = pointer_wrap!(body_ty);
-            if let ast::node::Kind::Block { mut list, .. } = body_node.kind {
-                let last =
-                    list.last_mut().unwrap_or_else(|| unreachable!("no last body node in `visit_fn()`"));
-                last.set_ty(body_ty.clone());
-                body_node = ast::Node::new_block(list, Some(body_ty.clone()));
-            }
-        }
+        let body_node = self.check_node(body, None)?;
+        let body_ty = body_node.ty().unwrap_or_default().clone();
         self.current_struct = current_struct;
 
         // Make sure these are in sync since there's no `check_proto()`
@@ -311,8 +1024,14 @@ impl<'a> ast::Visitor for Tych<'a> {
         }
 
         // Make sure function return type and the last statement match. Ignore
-        // body type when proto is void.
-        if ret_ty != body_ty && ret_ty != Type::Void && proto.name() != "main" {
+        // body type when proto is void or the body ends in a panic.
+        let diverges = match &body_node.kind {
+            ast::node::Kind::Block { list, .. } => {
+                matches!(list.last().map(|n| &n.kind), Some(ast::node::Kind::Panic { .. }))
+            },
+            _ => false,
+        };
+        if ret_ty != body_ty && ret_ty != Type::Void && proto.name() != "main" && !diverges {
             return Err(format!(
                 "function `{}` should return type `{}` but last statement is `{}`",
                 // TODO: proto.name() will be the butchered name. Use the original name
@@ -331,13 +1050,14 @@ impl<'a> ast::Visitor for Tych<'a> {
         Ok(ast::Node::new_fn(proto, Some(body_node)))
     }
 
-    // TODO: Check for circular struct definitions
     fn visit_struct(
-        &mut self, name: String, fields: Vec<ast::Node>, methods: Vec<ast::Node>,
+        &mut self, name: String, fields: Vec<ast::Node>, methods: Vec<ast::Node>, attrs: Vec<Attribute>,
     ) -> Self::Result {
         if self.symbol_table.scope_depth() != 0 {
             return Err("structs can only be defined at the global level".to_string());
         }
+        self.check_attrs(&attrs, attributes::STRUCT_ATTRS, &format!("struct `{}`", name))?;
+        self.check_struct_cycle(&name)?;
 
         self.current_struct = Some(name.clone());
         let chkd_fields =
@@ -350,7 +1070,7 @@ impl<'a> ast::Visitor for Tych<'a> {
         // the symbol table with the fully resolved type names
         let mut sym_fields = vec![];
         for node in &chkd_fields {
-            if let ast::Node { kind: ast::node::Kind::Let { name, antn, .. } } = node {
+            if let ast::Node { kind: ast::node::Kind::Let { name, antn: Some(antn), .. } } = node {
                 sym_fields.push((name.to_owned(), antn.to_string()));
             }
         }
@@ -365,15 +1085,150 @@ impl<'a> ast::Visitor for Tych<'a> {
             .into_iter()
             .map(|m| m.to_owned())
             .collect();
-        self.symbol_table.insert(Symbol::new_struct(
-            &name,
-            Some(&sym_fields),
-            Some(methods.as_slice()),
-            &self.module,
-            true,
-        ));
+        let parsed_sym = self
+            .symbol_table
+            .get(&name)
+            .unwrap_or_else(|| unreachable!("missing symbol table entry for `{}` in `visit_struct()`", name));
+        let impls = parsed_sym.impls().to_vec();
+        let mut sym =
+            Symbol::new_struct(&name, Some(&sym_fields), Some(methods.as_slice()), &self.module, true);
+        for trait_name in impls {
+            sym.add_impl(&trait_name, &[]);
+        }
+        sym.set_attrs(&attrs);
+        sym.set_field_defaults(&Self::field_defaults(&chkd_fields));
+        self.symbol_table.insert(sym);
 
-        Ok(ast::Node::new_struct(name, chkd_fields, chkd_methods))
+        Ok(ast::Node::new_struct(name, chkd_fields, chkd_methods, attrs))
+    }
+
+    fn visit_trait(&mut self, name: String, methods: Vec<ast::Node>) -> Self::Result {
+        if self.symbol_table.scope_depth() != 0 {
+            return Err("traits can only be defined at the global level".to_string());
+        }
+
+        let trait_sym = self.resolve_trait(&name)?;
+
+        // Resolve the method signatures and update the symbol table with them
+        let mut chkd_methods = Vec::with_capacity(methods.len());
+        let mut sym_methods = Vec::with_capacity(methods.len());
+        for (node, method) in methods.into_iter().zip(trait_sym.trait_methods()) {
+            let mut proto = match node.kind {
+                ast::node::Kind::Fn { proto, .. } => proto,
+                _ => unreachable!("non-method node in trait `{}`", name),
+            };
+            let (params, ret_ty) = self.resolve_method_sig(&name, method)?;
+            proto.set_params(params);
+            proto.set_ret_ty(ret_ty);
+            sym_methods.push(Symbol::new_fn(
+                (proto.name(), proto.name()),
+                proto.params(),
+                proto.ret_ty(),
+                false,
+                &self.module,
+                false,
+                Some(&name),
+            ));
+            chkd_methods.push(ast::Node::new_fn(proto, None));
+        }
+        self.symbol_table.insert(Symbol::new_trait(&name, &sym_methods, &self.module, true));
+
+        Ok(ast::Node::new_trait(name, chkd_methods))
+    }
+
+    fn visit_impl(
+        &mut self, trait_name: String, struct_name: String, methods: Vec<ast::Node>,
+    ) -> Self::Result {
+        if self.symbol_table.scope_depth() != 0 {
+            return Err("traits can only be implemented at the global level".to_string());
+        }
+
+        let trait_sym = self.resolve_trait(&trait_name)?;
+        let trait_name = trait_sym.name.clone();
+
+        // Methods in an impl are checked the same as those declared in the struct
+        self.current_struct = Some(struct_name.clone());
+        let chkd_methods =
+            methods.into_iter().map(|n| self.check_node(n, None)).collect::<Result<Vec<_>, String>>()?;
+        self.current_struct = None;
+
+        // Make sure every method in the trait is implemented with a matching signature
+        let prefix = format!("_{}_", struct_name);
+        for method in trait_sym.trait_methods() {
+            let proto = chkd_methods
+                .iter()
+                .find_map(|node| match &node.kind {
+                    ast::node::Kind::Fn { proto, .. }
+                        if proto.name().strip_prefix(&prefix) == Some(&method.name) =>
+                    {
+                        Some(proto)
+                    },
+                    _ => None,
+                })
+                .ok_or(format!(
+                    "impl of `{}` for `{}` is missing method `{}`",
+                    trait_name, struct_name, method.name
+                ))?;
+
+            // Skip the injected `self`
+            let (params, ret_ty) = self.resolve_method_sig(&trait_name, method)?;
+            let trait_tys = params.iter().map(|(_, ty)| ty).collect::<Vec<_>>();
+            let impl_tys = proto.params()[1..].iter().map(|(_, ty)| ty).collect::<Vec<_>>();
+            if trait_tys != impl_tys || &ret_ty != proto.ret_ty() {
+                return Err(format!(
+                    "method `{}` in impl of `{}` for `{}` doesn't match the trait's signature",
+                    method.name, trait_name, struct_name
+                ));
+            }
+        }
+
+        // Make sure nothing extra is implemented
+        for node in &chkd_methods {
+            if let ast::node::Kind::Fn { proto, .. } = &node.kind {
+                let name = proto.name().strip_prefix(&prefix).unwrap_or_default();
+                if !trait_sym.trait_methods().iter().any(|m| m.name == name) {
+                    return Err(format!("method `{}` is not a member of trait `{}`", name, trait_name));
+                }
+            }
+        }
+
+        Ok(ast::Node::new_impl(trait_name, struct_name, chkd_methods))
+    }
+
+    fn visit_enum(&mut self, name: String, variants: Vec<(String, Vec<Type>, i64)>) -> Self::Result {
+        if self.symbol_table.scope_depth() != 0 {
+            return Err("enums can only be defined at the global level".to_string());
+        }
+
+        // Resolve the payload types. Structs are stored by value in the payload
+        let mut chkd_variants = Vec::with_capacity(variants.len());
+        for (variant, tys, discriminant) in variants {
+            let mut chkd_tys = Vec::with_capacity(tys.len());
+            for ty in tys {
+                let chkd_ty = match self.resolve_type(&ty) {
+                    Some(Type::Enum(enum_name)) if enum_name == name => {
+                        return Err(format!("recursive enum `{}` has infinite size", name))
+                    },
+                    Some(Type::Void) | None => {
+                        return Err(format!("Unknown type in variant `{}::{}`: `{}`", name, variant, ty))
+                    },
+                    Some(ty) => ty,
+                };
+                chkd_tys.push(chkd_ty);
+            }
+            chkd_variants.push((variant, chkd_tys, discriminant));
+        }
+
+        // Update the symbol table with the fully resolved type names
+        let sym_variants = chkd_variants
+            .iter()
+            .map(|(variant, tys, discriminant)| {
+                (variant.to_owned(), tys.iter().map(|ty| ty.to_string()).collect(), *discriminant)
+            })
+            .collect::<Vec<_>>();
+        self.symbol_table.insert(Symbol::new_enum(&name, &sym_variants, &self.module, true));
+
+        Ok(ast::Node::new_enum(name, chkd_variants))
     }
 
     fn visit_break(&mut self) -> Self::Result {
@@ -384,80 +1239,118 @@ impl<'a> ast::Visitor for Tych<'a> {
         Ok(ast::Node::new_next())
     }
 
-    // If there's a type hint (in `self.hint`), use it or fail. If not, use the literal's
-    // type. Update `lit` with the result and return the type.
-    fn visit_lit(&mut self, value: Literal<ast::Node>, _ty: Option<Type>) -> Self::Result {
+    fn visit_panic(&mut self, msg: String, loc: Loc) -> Self::Result {
+        unescape(&msg)?;
+        Ok(ast::Node::new_panic(msg, loc))
+    }
+
+    fn visit_assert(&mut self, cond: ast::Node, msg: String, debug: bool, loc: Loc) -> Self::Result {
+        let chkd_cond = self.check_node(cond, None)?;
+        let cond_ty = chkd_cond.ty().unwrap_or_default();
+        if cond_ty != &Type::Bool {
+            return Err(format!("`assert` condition should be a bool, found `{}`", cond_ty));
+        }
+        unescape(&msg)?;
+        Ok(ast::Node::new_assert(chkd_cond, msg, debug, loc))
+    }
+
+    fn visit_debug(&mut self, value: ast::Node) -> Self::Result {
+        let chkd_value = self.check_node(value, None)?;
+        let value_ty = chkd_value.ty().unwrap_or_default();
+        if !self.is_debuggable(value_ty) {
+            return Err(format!("`debug` can't print a value of type `{}`", value_ty));
+        }
+        Ok(ast::Node::new_debug(chkd_value))
+    }
+
+    // Each `{}` in the format string takes one arg. Args print the way `debug()` prints
+    // them, except `*char`s which print as strings
+    fn visit_print(&mut self, fmt: String, args: Vec<ast::Node>, newline: bool) -> Self::Result {
+        let keyword = if newline { "println" } else { "print" };
+        unescape(&fmt)?;
+        let pieces = split_format(&fmt).map_err(|e| format!("Invalid `{}` format string. {}", keyword, e))?;
+        if pieces.len() - 1 != args.len() {
+            return Err(format!(
+                "`{}` format string has {} placeholders and {} args were given",
+                keyword,
+                pieces.len() - 1,
+                args.len()
+            ));
+        }
+
+        let mut chkd_args = vec![];
+        for (idx, arg) in args.into_iter().enumerate() {
+            let chkd_arg = self.check_node(arg, None)?;
+            let arg_ty = chkd_arg.ty().unwrap_or_default();
+            if !self.is_debuggable(arg_ty) {
+                return Err(format!("`{}` can't print arg {} of type `{}`", keyword, idx + 1, arg_ty));
+            }
+            chkd_args.push(chkd_arg);
+        }
+        Ok(ast::Node::new_print(fmt, chkd_args, newline))
+    }
+
+    // Numeric literals from the parser take their type from the hint in `self.hint` and
+    // are range checked against it. Literals that already have a type keep it
+    fn visit_lit(&mut self, value: Literal<ast::Node>, ty: Option<Type>) -> Self::Result {
         use Literal::*;
 
-        // TODO: Clean this up
-        let lit = value;
-        let (new_lit, lit_ty): (Literal<ast::Node>, Type) = match &self.hint {
-            Some(hint) => match lit {
-                Int8(v) => (Int8(v), Type::Int8),
-                Int16(v) => (Int16(v), Type::Int16),
-                Int32(v) => (Int32(v), Type::Int32),
-                Int64(v) => (Int64(v), Type::Int64),
-                UInt8(v) => (UInt8(v), Type::UInt8),
-                UInt16(v) => (UInt16(v), Type::UInt16),
-                UInt32(v) => (UInt32(v), Type::UInt32),
-                UInt64(v) => match hint {
-                    Type::Int8 => convert_num!(v, Int8, i8),
-                    Type::Int16 => convert_num!(v, Int16, i16),
-                    Type::Int32 => convert_num!(v, Int32, i32),
-                    Type::Int64 => convert_num!(v, Int64, i64),
-                    Type::UInt8 => convert_num!(v, UInt8, u8),
-                    Type::UInt16 => convert_num!(v, UInt16, u16),
-                    Type::UInt32 => convert_num!(v, UInt32, u32),
-                    Type::UInt64 => convert_num!(v, UInt64, u64),
-                    float_types!() => return Err("Literal is an integer in a float context".to_string()),
-                    Type::Bool => return Err("Literal is an integer in a bool context".to_string()),
-                    Type::Char => return Err("Literal is an integer in a char context".to_string()),
-                    Type::SArray(..) => return Err("Literal is an integer in an sarray context".to_string()),
-                    Type::Void => return Err("Literal is an integer in a void context".to_string()),
-                    Type::Comp(_) => return Err("Literal is an integer in a composite context".to_string()),
-                    Type::Ptr(_) => return Err("Literal is an integer in a pointer context".to_string()),
-                },
-                Float(v) => match hint {
-                    Type::Float => convert_num!(v, Float, f32),
-                    Type::Double => convert_num!(v, Double, f64),
-                    int_types!() => return Err("Literal is a float in an integer context".to_string()),
-                    Type::Bool => return Err("Literal is a float in a bool context".to_string()),
-                    Type::Char => return Err("Literal is a float in a char context".to_string()),
-                    Type::SArray(..) => return Err("Literal is a float in an sarray context".to_string()),
-                    _ => unreachable!("float conversion error"),
-                },
-                Double(v) => (Double(v), Type::Double),
-                Bool(v) => (Bool(v), Type::Bool),
-                Char(v) => (Char(v), Type::Char),
-                Array { .. } => self.check_lit_array(lit, Some(hint.clone()))?,
-                Comp(_) => unreachable!("composite types don't exist in the tych"),
-            },
-            None => match lit {
-                Int32(v) => (Int32(v), Type::Int32), // Only used for main's return value
-                UInt64(v) => {
-                    let v = i32::try_from(v).map_err(|_| "Numeric literal out of range")?;
-                    (Int32(v), Type::Int32)
-                },
-                Float(v) => (Float(v), Type::Float),
-                Bool(v) => (Bool(v), Type::Bool),
-                Char(v) => (Char(v), Type::Char),
-                Array { .. } => self.check_lit_array(lit, None)?,
-                x => unreachable!("numeric conversion error for {}", x),
+        let (new_lit, lit_ty): (Literal<ast::Node>, Type) = match value {
+            UInt64(v) => self.check_int_lit(v.into())?,
+            UInt128(v) if ty.is_none() => self.check_wide_int_lit(v)?,
+            Double(v) => self.check_float_lit(v)?,
+            Int8(v) => (Int8(v), Type::Int8),
+            Int16(v) => (Int16(v), Type::Int16),
+            Int32(v) => (Int32(v), Type::Int32),
+            Int64(v) => (Int64(v), Type::Int64),
+            Int128(v) => (Int128(v), Type::Int128),
+            ISize(v) => (ISize(v), Type::ISize),
+            UInt8(v) => (UInt8(v), Type::UInt8),
+            UInt16(v) => (UInt16(v), Type::UInt16),
+            UInt32(v) => (UInt32(v), Type::UInt32),
+            UInt128(v) => (UInt128(v), Type::UInt128),
+            USize(v) => (USize(v), Type::USize),
+            Float(v) => (Float(v), Type::Float),
+            Bool(v) => (Bool(v), Type::Bool),
+            Char(v) => (Char(v), Type::Char),
+            // String literals are null-terminated and can be passed to C as `*char`
+            Str(v) => {
+                unescape(&v)?;
+                (Str(v), Type::Ptr(Box::new(Type::Char)))
             },
+            Array { .. } => self.check_lit_array(value, self.hint.clone())?,
+            Tuple(_) => self.check_lit_tuple(value, self.hint.clone())?,
+            Comp(_) => unreachable!("composite types don't exist in the tych"),
         };
 
         Ok(ast::Node::new_lit(new_lit, Some(lit_ty)))
     }
 
     fn visit_ident(&mut self, name: String, _ty: Option<Type>) -> Self::Result {
-        let ident_ty =
-            self.symbol_table.get(&name).ok_or(format!("Unknown variable: `{}`", name))?.ty().clone();
+        // Variants without a payload are used like constants
+        if self.symbol_table.get(&name).is_none() {
+            if let Some((enum_sym, variant)) = self.resolve_variant_path(&name) {
+                return self.check_variant(&enum_sym, &variant, vec![]);
+            }
+        }
+
+        let sym = self
+            .symbol_table
+            .resolve_symbol(&name, &self.module)
+            .ok_or(format!("Unknown variable: `{}`", name))?;
+        if sym.kind() == "Fn" {
+            return self.check_fn_ref(sym);
+        }
+
+        let ident_ty = sym.ty().clone();
+        let sym = sym.clone();
+        self.warn_deprecated(&sym, &format!("variable `{}`", name));
+        self.capture(&name, &ident_ty);
         Ok(ast::Node::new_ident(name, Some(ident_ty)))
     }
 
-    // TODO: Check overflow on math ops
     fn visit_binop(
-        &mut self, op: Operator, lhs: ast::Node, rhs: ast::Node, _ty: Option<Type>,
+        &mut self, op: Operator, lhs: ast::Node, rhs: ast::Node, _ty: Option<Type>, loc: Loc,
     ) -> Self::Result {
         use Operator::*;
 
@@ -488,10 +1381,10 @@ impl<'a> ast::Visitor for Tych<'a> {
             rhs_ty = chkd_rhs.ty().unwrap_or_default().to_owned();
         }
 
-        // If lhs is a pointer and rhs isn't, wrap rhs type in a pointer
-        if matches!(lhs_ty, Type::Ptr(_)) && !matches!(rhs_ty, Type::Ptr(_)) {
-            rhs_ty = pointer_wrap!(rhs_ty);
-            chkd_rhs.set_ty(rhs_ty.clone());
+        // Structs assigned to trait objects are coerced
+        if op == Assign {
+            chkd_rhs = self.coerce(chkd_rhs, &lhs_ty)?;
+            rhs_ty = chkd_rhs.ty().unwrap_or_default().to_owned();
         }
 
         // Both sides must match
@@ -517,6 +1410,8 @@ impl<'a> ast::Visitor for Tych<'a> {
                         numeric_types!() | Type::Bool | Type::Char,
                         numeric_types!() | Type::Bool | Type::Char,
                     ) => (),
+                    // Structs and fixed arrays compare field-wise
+                    (Type::Comp(_) | Type::SArray(..), _) if self.is_equatable(&lhs_ty) => (),
                     _ => {
                         return Err(format!(
                             "Invalid type combination found in `{}` operation: (lhs: `{}`, rhs: `{}`)",
@@ -553,10 +1448,31 @@ impl<'a> ast::Visitor for Tych<'a> {
             _ => Type::Void,
         };
 
-        Ok(ast::Node::new_binop(op, chkd_lhs, chkd_rhs, Some(ty)))
+        Ok(ast::Node::new_binop(op, chkd_lhs, chkd_rhs, Some(ty), loc))
     }
 
     fn visit_unop(&mut self, op: Operator, rhs: ast::Node, _ty: Option<Type>) -> Self::Result {
+        // Negated literals are folded into one value so they're range checked as a whole
+        if op == Operator::Sub {
+            if let ast::node::Kind::Lit { value: Literal::UInt64(v), ty: None } = rhs.kind {
+                let (lit, lit_ty) = self.check_int_lit(-i128::from(v))?;
+                return Ok(ast::Node::new_lit(lit, Some(lit_ty)));
+            } else if let ast::node::Kind::Lit { value: Literal::UInt128(v), ty: None } = rhs.kind {
+                // Nothing is below `int128::MIN`
+                let (lit, lit_ty) = match 0i128.checked_sub_unsigned(v) {
+                    Some(v) => self.check_int_lit(v)?,
+                    None => {
+                        let ty = self.hint.clone().unwrap_or(Type::Int32);
+                        return Err(format!("Literal `-{}` is out of range for `{}`", v, ty));
+                    },
+                };
+                return Ok(ast::Node::new_lit(lit, Some(lit_ty)));
+            } else if let ast::node::Kind::Lit { value: Literal::Double(v), ty: None } = rhs.kind {
+                let (lit, lit_ty) = self.check_float_lit(-v)?;
+                return Ok(ast::Node::new_lit(lit, Some(lit_ty)));
+            }
+        }
+
         let chkd_rhs = self.check_node(rhs, None)?;
         let rhs_ty = chkd_rhs.ty().cloned().unwrap_or_default();
         match rhs_ty {
@@ -571,86 +1487,38 @@ impl<'a> ast::Visitor for Tych<'a> {
         Ok(ast::Node::new_unop(op, chkd_rhs, Some(rhs_ty)))
     }
 
-    fn visit_call(&mut self, name: String, args: Vec<ast::Node>, _ty: Option<Type>) -> Self::Result {
-        // Pull the function for the call from the table
-        let fn_entry = self
-            .symbol_table
-            .resolve_symbol(&name, &self.module)
-            .ok_or(format!("Call to undefined function: `{}`", name))?
-            .clone();
-
-        // Now that we have the FQN, use it in the AST
-        let name = fn_entry
-            .fq_name()
-            .unwrap_or_else(|| unreachable!("non-function symbol in `visit_call()`"))
-            .to_owned();
-
-        // Inject a temporary `self` into the call's arguments and the symbol table. Will
-        // be replaced in the lower. Always pass `self` as a pointer
-        let mut args = args;
-        if let Some(struct_name) = fn_entry.member_of() {
-            let self_ty = pointer_wrap!(Type::Comp(struct_name.to_owned()));
-            self.symbol_table.insert(Symbol::new_var("self", &self_ty, &self.module));
-            args.insert(0, ast::Node::new_ident(String::from("self"), Some(self_ty)));
+    fn visit_call(&mut self, name: String, args: Vec<ast::Node>, ty: Option<Type>) -> Self::Result {
+        // Variants with a payload are constructed like function calls
+        if let Some((enum_sym, variant)) = self.resolve_variant_path(&name) {
+            return self.check_variant(&enum_sym, &variant, args);
         }
 
-        // Pull out the function arg types
-        let fe_param_tys = fn_entry.param_tys().to_vec();
-
-        // Check arg length. Account for injected self if method
-        let (fe_params_len, args_len) = match fn_entry.member_of().is_some() {
-            true => (fe_param_tys.len() - 1, args.len() - 1),
-            false => (fe_param_tys.len(), args.len()),
-        };
-        if fe_param_tys.len() != args.len() {
-            return Err(format!(
-                "call to `{}()` takes {} args and {} were given",
-                name, fe_params_len, args_len
-            ));
+        // Structs are constructed with `Type(args)` and their associated functions are
+        // called as `Type::name(args)`
+        if let Some(Type::Comp(struct_name)) = self.resolve_type(&Type::Comp(name.clone())) {
+            return self.check_constructor(&struct_name, args);
+        }
+        if let Some((struct_name, fn_name)) = self.resolve_assoc_path(&name) {
+            return self.check_assoc_call(&struct_name, &fn_name, args);
         }
 
-        // Resolve the call's return type.
-        let ret_ty = match self.resolve_type(fn_entry.ret_ty()) {
-            Some(ty) => ty,
-            None => unreachable!("unknown return type in `visit_call()`"),
-        };
-
-        // Check all args and record their types. Use the function entry arg types as type
-        // hints.
-        let mut chkd_args = Vec::with_capacity(args_len);
-        let mut arg_tys = Vec::with_capacity(args_len);
-        for (idx, expr) in args.into_iter().enumerate() {
-            let mut chkd_arg = self.check_node(expr, Some(fe_param_tys[idx]))?;
-            // Send structs as pointers
-            if let Some(Type::Comp(ty)) = chkd_arg.ty() {
-                chkd_arg.set_ty(pointer_wrap!(Type::Comp(ty.to_owned())));
+        // Variables can only be called if they hold a function pointer or a closure
+        if let Some(sym) = self.symbol_table.get(&name).filter(|sym| sym.kind() == "Var") {
+            if let Type::Fn(..) = sym.ty() {
+                let callee = self.visit_ident(name.clone(), None)?;
+                return self.check_indirect_call(callee, &name, args);
             }
-            arg_tys.push((idx, chkd_arg.ty().unwrap_or_default().clone()));
-            chkd_args.push(chkd_arg);
+            return self.check_closure_call(name, args, ty);
         }
 
-        // Make sure the function params and the call args jive
-        fe_param_tys.iter().zip(arg_tys).try_for_each(|(fp_ty, (idx, ca_ty))| {
-            // Resolve param type first
-            let fp_ty = match self.resolve_type(fp_ty) {
-                Some(ty) => ty,
-                None => unreachable!("bad arg type in `visit_call()`"),
-            };
-            // TODO: remove `&& pointer_wrap!()...` when declaration order is resolved
-            if fp_ty != ca_ty && pointer_wrap!(fp_ty) != ca_ty {
-                Err(format!(
-                    "type mismatch in arg {} of call to `{}()`: `{}` != `{}`",
-                    idx + 1,
-                    name,
-                    fp_ty,
-                    ca_ty
-                ))
-            } else {
-                Ok(())
-            }
-        })?;
+        self.check_call(name, None, args)
+    }
 
-        Ok(ast::Node::new_call(name, chkd_args, Some(ret_ty)))
+    // Only made by the tych
+    fn visit_indirect_call(
+        &mut self, callee: ast::Node, args: Vec<ast::Node>, ty: Option<Type>,
+    ) -> Self::Result {
+        Ok(ast::Node::new_indirect_call(callee, args, ty))
     }
 
     fn visit_cond(
@@ -683,6 +1551,12 @@ impl<'a> ast::Visitor for Tych<'a> {
         Ok(ast::Node::new_cond(chkd_cond, chkd_then, chkd_else, Some(then_ty)))
     }
 
+    fn visit_match(
+        &mut self, scrutinee: ast::Node, arms: Vec<MatchArm<ast::Node>>, _ty: Option<Type>, loc: Loc,
+    ) -> Self::Result {
+        self.check_match(scrutinee, arms, loc)
+    }
+
     // Check the block expressions. Ensures statements always eval to void.
     fn visit_block(&mut self, list: Vec<ast::Node>, _ty: Option<Type>) -> Self::Result {
         self.symbol_table.enter_scope();
@@ -690,8 +1564,12 @@ impl<'a> ast::Visitor for Tych<'a> {
         // The block type is set to the final node's type
         let mut chkd_list = Vec::with_capacity(list.len());
         let mut list_ty = Type::Void;
-        for node in list {
+        let last = list.len().saturating_sub(1);
+        for (idx, node) in list.into_iter().enumerate() {
             let chkd_node = self.check_node(node, None)?;
+            if idx != last {
+                self.warn_unused(&chkd_node);
+            }
             list_ty = chkd_node.ty().unwrap_or_default().clone();
             chkd_list.push(chkd_node);
         }
@@ -701,26 +1579,38 @@ impl<'a> ast::Visitor for Tych<'a> {
         Ok(ast::Node::new_block(chkd_list, Some(list_ty)))
     }
 
-    fn visit_index(&mut self, binding: ast::Node, idx: ast::Node, _ty: Option<Type>) -> Self::Result {
+    fn visit_index(
+        &mut self, binding: ast::Node, idx: ast::Node, _ty: Option<Type>, loc: Loc,
+    ) -> Self::Result {
         let chkd_binding = self.check_node(binding, None)?;
         let binding_ty = match chkd_binding.ty().unwrap_or_default() {
             Type::SArray(t, _) => *t.clone(),
             t => return Err(format!("Can't index `{}`", t)),
         };
-        // TODO: Coerce into int32
+        // Any integer type can index. Codegen widens it to a pointer-sized index
         let chkd_idx = self.check_node(idx, Some(&Type::Int32))?;
         let idx_ty = chkd_idx.ty().unwrap_or_default();
         if !matches!(idx_ty, int_types!()) {
             return Err(format!("Array index must be an `int`, found `{}`", idx_ty));
-        } else if !matches!(idx_ty, Type::Int32) {
-            return Err("Index must be an int32 (for now)".to_string());
         }
 
-        Ok(ast::Node::new_index(chkd_binding, chkd_idx, Some(binding_ty)))
+        Ok(ast::Node::new_index(chkd_binding, chkd_idx, Some(binding_ty), loc))
     }
 
     fn visit_fselector(&mut self, comp: ast::Node, field: String, _ty: Option<Type>) -> Self::Result {
         let chkd_comp = self.check_node(comp, None)?;
+
+        // Tuple fields are selected by index
+        if let Some(Type::Tuple(tys)) = chkd_comp.ty() {
+            let field_ty = field
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| tys.get(idx))
+                .cloned()
+                .ok_or(format!("tuple `{}` has no field: `{}`", chkd_comp.ty().unwrap_or_default(), field))?;
+            return Ok(ast::Node::new_fselector(chkd_comp, field, Some(field_ty)));
+        }
+
         let comp_sym = self.get_composite_symbol(chkd_comp.ty())?;
         let field_ty: Type = comp_sym
             .fields()
@@ -740,21 +1630,38 @@ impl<'a> ast::Visitor for Tych<'a> {
     }
 
     fn visit_mselector(
-        &mut self, comp: ast::Node, method_name: String, args: Vec<ast::Node>, ty: Option<Type>,
+        &mut self, comp: ast::Node, method_name: String, args: Vec<ast::Node>, _ty: Option<Type>,
     ) -> Self::Result {
         let chkd_comp = self.check_node(comp, None)?;
+
+        // Methods on trait objects are checked against the trait and dispatched at runtime
+        if let Some(Type::Dyn(trait_name)) = chkd_comp.ty() {
+            let trait_name = trait_name.to_owned();
+            return self.check_dyn_call(chkd_comp, &trait_name, method_name, args);
+        }
+
         let comp_sym = self.get_composite_symbol(chkd_comp.ty())?.clone();
 
-        // Make sure the method exists
+        // Make sure the method exists. Fields holding a function pointer are called the
+        // same way
         if !comp_sym.methods().unwrap_or_default().contains(&method_name.as_str()) {
+            let field_ty = comp_sym
+                .fields()
+                .unwrap_or_default()
+                .into_iter()
+                .find(|f| f.0 == method_name)
+                .and_then(|f| self.resolve_type(&f.1.into()));
+            if let Some(field_ty @ Type::Fn(..)) = field_ty {
+                let name = format!("{}.{}", comp_sym.name, method_name);
+                let callee = ast::Node::new_fselector(chkd_comp, method_name, Some(field_ty));
+                return self.check_indirect_call(callee, &name, args);
+            }
             return Err(format!("composite `{}` has no method: `{}`", comp_sym.name, method_name));
         }
         let cooked_method_name = format!("_{}_{}", comp_sym.name, method_name);
 
-        let chkd_call = self.visit_call(cooked_method_name.clone(), args, ty).map_err(|e| {
-            // Don't use the cooked name for errors
-            e.replace(&cooked_method_name, &format!("{}.{}", comp_sym.name, method_name))
-        })?;
+        let display_name = format!("{}.{}", comp_sym.name, method_name);
+        let chkd_call = self.check_call(cooked_method_name, Some(display_name), args)?;
 
         match chkd_call.kind {
             ast::node::Kind::Call { name, args, ty } => {
@@ -763,4 +1670,55 @@ impl<'a> ast::Visitor for Tych<'a> {
             _ => unreachable!("unknown node kind in `visit_mselector()`"),
         }
     }
+
+    fn visit_closure(
+        &mut self, params: Vec<(String, Type)>, ret_ty: Type, body: ast::Node, is_move: bool,
+        _captures: Vec<(String, Type)>, _ty: Option<Type>,
+    ) -> Self::Result {
+        self.check_closure(params, ret_ty, body, is_move)
+    }
+
+    // Trait object casts are inserted by `coerce()` but can also be written out. Numeric
+    // casts convert between the integer, float, `bool`, and `char` types
+    fn visit_cast(&mut self, expr: ast::Node, ty: Option<Type>) -> Self::Result {
+        let ty = ty.unwrap_or_else(|| unreachable!("missing type in cast"));
+        let ty = self.resolve_type(&ty).ok_or(format!("Unknown type in cast: `{}`", ty))?;
+        let chkd_expr = self.check_node(expr, None)?;
+        let expr_ty = chkd_expr.ty().cloned().unwrap_or_default();
+
+        if let Type::Dyn(_) = ty {
+            return match self.coerce(chkd_expr, &ty)? {
+                node @ ast::Node { kind: ast::node::Kind::Cast { .. } } => Ok(node),
+                _ => Err(format!("invalid cast from `{}` to `{}`", expr_ty, ty)),
+            };
+        }
+
+        // Only integers can become a `char`, and nothing else can become a `bool`
+        let is_valid = matches!(
+            (&expr_ty, &ty),
+            (int_types!() | float_types!() | Type::Bool | Type::Char, int_types!())
+                | (int_types!() | float_types!(), float_types!())
+                | (int_types!() | Type::Char, Type::Char)
+                | (Type::Bool, Type::Bool)
+        );
+        if !is_valid {
+            return Err(format!("invalid cast from `{}` to `{}`", expr_ty, ty));
+        }
+
+        Ok(ast::Node::new_cast(chkd_expr, Some(ty)))
+    }
+
+    // Variants are only constructed by `visit_call()` and `visit_ident()`
+    fn visit_variant(
+        &mut self, enum_name: String, variant: String, args: Vec<ast::Node>, _ty: Option<Type>,
+    ) -> Self::Result {
+        let enum_sym = self.resolve_enum(&enum_name)?;
+        self.check_variant(&enum_sym, &variant, args)
+    }
+
+    fn visit_struct_lit(
+        &mut self, name: String, fields: Vec<(String, ast::Node)>, _ty: Option<Type>,
+    ) -> Self::Result {
+        self.check_struct_lit(name, fields)
+    }
 }
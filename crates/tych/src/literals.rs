@@ -0,0 +1,151 @@
+use super::*;
+
+// Numeric literals are parsed as a `uint64` or `double` magnitude, or a `uint128` one when
+// they don't fit a `uint64`. They take the type of their context, or `int32`/`float`
+// without one, and must fit in the range of that type. Code is generated for the host, so
+// pointer-sized types are checked against the host's pointer width.
+
+impl<'a> Tych<'a> {
+    pub(super) fn check_int_lit(&self, v: i128) -> Result<(Literal<ast::Node>, Type), String> {
+        Ok(convert_int_lit!(v, self.hint))
+    }
+
+    // Only a `uint128` holds a magnitude past `int128`
+    pub(super) fn check_wide_int_lit(&self, v: u128) -> Result<(Literal<ast::Node>, Type), String> {
+        match i128::try_from(v) {
+            Ok(v) => self.check_int_lit(v),
+            Err(_) => Ok(convert_int_lit!(v, self.hint)),
+        }
+    }
+
+    pub(super) fn check_float_lit(&self, v: f64) -> Result<(Literal<ast::Node>, Type), String> {
+        match self.hint.as_ref().unwrap_or(&Type::Float) {
+            Type::Float if v.abs() > f32::MAX.into() => Err(format!(
+                "Literal `{:e}` is out of range for `float` ({:e}..={:e})",
+                v,
+                f32::MIN,
+                f32::MAX
+            )),
+            Type::Float => Ok((Literal::Float(v as f32), Type::Float)),
+            Type::Double => Ok((Literal::Double(v), Type::Double)),
+            int_types!() => Err("Literal is a float in an integer context".to_string()),
+            Type::Bool => Err("Literal is a float in a bool context".to_string()),
+            Type::Char => Err("Literal is a float in a char context".to_string()),
+            Type::Str => Err("Literal is a float in a string context".to_string()),
+            Type::SArray(..) => Err("Literal is a float in an sarray context".to_string()),
+            Type::Void => Err("Literal is a float in a void context".to_string()),
+            Type::Comp(_) => Err("Literal is a float in a composite context".to_string()),
+            Type::Dyn(_) => Err("Literal is a float in a trait object context".to_string()),
+            Type::Enum(_) => Err("Literal is a float in an enum context".to_string()),
+            Type::Tuple(_) => Err("Literal is a float in a tuple context".to_string()),
+            Type::Fn(..) => Err("Literal is a float in a function context".to_string()),
+            Type::Ptr(_) => Err("Literal is a float in a pointer context".to_string()),
+        }
+    }
+}
+
+impl<'a> Tych<'a> {
+    // Struct literals must name every field of the struct without a default exactly once.
+    // The fields are reordered to match the declaration so lower can treat them positionally.
+    pub(super) fn check_struct_lit(
+        &mut self, name: String, fields: Vec<(String, ast::Node)>,
+    ) -> Result<ast::Node, String> {
+        let struct_ty = match self.resolve_type(&Type::Comp(name.clone())) {
+            Some(ty @ Type::Comp(_)) => ty,
+            _ => return Err(format!("Unknown struct in struct literal: `{}`", name)),
+        };
+        let struct_sym = self.get_composite_symbol(Some(&struct_ty))?.clone();
+        self.warn_deprecated(&struct_sym, &format!("struct `{}`", struct_sym.name));
+        let decl_fields: Vec<(String, Type)> = struct_sym
+            .fields()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(field, ty)| (field.to_owned(), ty.into()))
+            .collect();
+
+        let mut chkd_fields = HashMap::new();
+        for (field, value) in fields {
+            let field_ty = match decl_fields.iter().find(|(f, _)| *f == field) {
+                Some((_, ty)) => self
+                    .resolve_type(ty)
+                    .unwrap_or_else(|| unreachable!("bad field type in `check_struct_lit()`")),
+                None => return Err(format!("struct `{}` has no field: `{}`", name, field)),
+            };
+            if chkd_fields.contains_key(&field) {
+                return Err(format!("field `{}` specified more than once in `{}` literal", field, name));
+            }
+            let chkd_value = self
+                .check_var_init(&field, Some(&value), &field_ty, "struct literal")?
+                .unwrap_or_else(|| unreachable!("missing field value in `check_struct_lit()`"));
+            chkd_fields.insert(field, chkd_value);
+        }
+
+        // Fields left out of the literal take their default, if they have one
+        for (field, ty) in &decl_fields {
+            if chkd_fields.contains_key(field) {
+                continue;
+            }
+            if let Some(default) = struct_sym.field_default(field) {
+                let value = default.to_scalar().unwrap_or_else(|| unreachable!("non-constant field default"));
+                chkd_fields.insert(field.to_owned(), ast::Node::new_lit(value, Some(ty.to_owned())));
+            }
+        }
+
+        let missing: Vec<_> = decl_fields
+            .iter()
+            .filter(|(field, _)| !chkd_fields.contains_key(field))
+            .map(|(field, _)| format!("`{}`", field))
+            .collect();
+        if !missing.is_empty() {
+            return Err(format!("missing fields in `{}` literal: {}", name, missing.join(", ")));
+        }
+
+        let ordered_fields = decl_fields
+            .into_iter()
+            .map(|(field, _)| {
+                let value = chkd_fields.remove(&field).unwrap_or_else(|| unreachable!("missing field"));
+                (field, value)
+            })
+            .collect();
+        Ok(ast::Node::new_struct_lit(name, ordered_fields, Some(struct_ty)))
+    }
+
+    // Checks the fields of a struct that have a default and stores the defaults in the
+    // struct's symbol
+    pub(super) fn record_field_defaults(&mut self, name: &str, fields: &[ast::Node]) -> Result<(), String> {
+        self.current_struct = Some(name.to_owned());
+        let chkd_fields = fields
+            .iter()
+            .filter(|n| matches!(&n.kind, ast::node::Kind::Let { init: Some(_), .. }))
+            .map(|n| self.check_node(n.clone(), None))
+            .collect::<Result<Vec<_>, String>>();
+        self.current_struct = None;
+
+        let mut sym = self.symbol_table.get(name).cloned().unwrap_or_else(|| {
+            unreachable!("missing symbol table entry for `{}` in `record_field_defaults()`", name)
+        });
+        sym.set_field_defaults(&Self::field_defaults(&chkd_fields?));
+        self.symbol_table.insert(sym);
+
+        Ok(())
+    }
+
+    // Collects the defaults from checked struct fields
+    pub(super) fn field_defaults(fields: &[ast::Node]) -> Vec<(String, Literal<()>)> {
+        fields
+            .iter()
+            .filter_map(|node| match &node.kind {
+                ast::node::Kind::Let { name, init: Some(init), .. } => match &init.kind {
+                    ast::node::Kind::Lit { value, .. } => Some((
+                        name.to_owned(),
+                        value
+                            .to_scalar()
+                            .unwrap_or_else(|| unreachable!("non-constant default for `{}`", name)),
+                    )),
+                    _ => unreachable!("non-literal default for `{}`", name),
+                },
+                _ => None,
+            })
+            .collect()
+    }
+}
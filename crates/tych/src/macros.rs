@@ -1,9 +1,53 @@
-// Try to convert `$v` to `$ty`. Store result wrapped in `Literal::$variant` and
-// assign to `$lit`. Return `Type::$variant`.
+// Try to convert `$val` to `$ty`, failing with the valid range of `$ty`. Return the
+// result wrapped in `Literal::$variant`, cast to `$store` if given, along with
+// `Type::$variant`.
 #[macro_export]
 macro_rules! convert_num {
-    ($val:expr, $variant:ident, $ty:ty) => {{
-        let v = <$ty>::try_from($val).map_err(|_| "Numeric literal out of range")?;
-        (Literal::$variant(v), Type::$variant)
+    ($val:expr, $variant:ident, $ty:ty $(, $store:ty)?) => {{
+        let v = <$ty>::try_from($val).map_err(|_| {
+            format!(
+                "Literal `{}` is out of range for `{}` ({}..={})",
+                $val,
+                Type::$variant,
+                <$ty>::MIN,
+                <$ty>::MAX
+            )
+        })?;
+        (Literal::$variant(v $(as $store)?), Type::$variant)
+    }};
+}
+
+// Convert the integer literal `$val` to the type of `$hint`, or `int32` without a hint.
+// `$val` can be any integer type, so wide magnitudes are range checked like the rest.
+#[macro_export]
+macro_rules! convert_int_lit {
+    ($val:expr, $hint:expr) => {{
+        let v = $val;
+        match $hint.as_ref().unwrap_or(&Type::Int32) {
+            Type::Int8 => convert_num!(v, Int8, i8),
+            Type::Int16 => convert_num!(v, Int16, i16),
+            Type::Int32 => convert_num!(v, Int32, i32),
+            Type::Int64 => convert_num!(v, Int64, i64),
+            Type::Int128 => convert_num!(v, Int128, i128),
+            Type::ISize => convert_num!(v, ISize, isize, i64),
+            Type::UInt8 => convert_num!(v, UInt8, u8),
+            Type::UInt16 => convert_num!(v, UInt16, u16),
+            Type::UInt32 => convert_num!(v, UInt32, u32),
+            Type::UInt64 => convert_num!(v, UInt64, u64),
+            Type::UInt128 => convert_num!(v, UInt128, u128),
+            Type::USize => convert_num!(v, USize, usize, u64),
+            float_types!() => return Err("Literal is an integer in a float context".to_string()),
+            Type::Bool => return Err("Literal is an integer in a bool context".to_string()),
+            Type::Char => return Err("Literal is an integer in a char context".to_string()),
+            Type::Str => return Err("Literal is an integer in a string context".to_string()),
+            Type::SArray(..) => return Err("Literal is an integer in an sarray context".to_string()),
+            Type::Void => return Err("Literal is an integer in a void context".to_string()),
+            Type::Comp(_) => return Err("Literal is an integer in a composite context".to_string()),
+            Type::Dyn(_) => return Err("Literal is an integer in a trait object context".to_string()),
+            Type::Enum(_) => return Err("Literal is an integer in an enum context".to_string()),
+            Type::Tuple(_) => return Err("Literal is an integer in a tuple context".to_string()),
+            Type::Fn(..) => return Err("Literal is an integer in a function context".to_string()),
+            Type::Ptr(_) => return Err("Literal is an integer in a pointer context".to_string()),
+        }
     }};
 }
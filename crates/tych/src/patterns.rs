@@ -0,0 +1,294 @@
+use super::*;
+
+// Patterns are checked against the type of the match scrutinee. Literal patterns are
+// folded into constants of that type so the arms can be checked for reachability and
+// exhaustiveness:
+//   - ints, chars, and bools are covered by ranges of their values
+//   - enums are covered variant by variant, since fields can only bind or be ignored
+//   - anything else is only covered by `_` or a binding
+//
+// Guarded arms never count towards coverage.
+
+impl<'a> Tych<'a> {
+    pub(super) fn check_match(
+        &mut self, scrutinee: ast::Node, arms: Vec<MatchArm<ast::Node>>, loc: Loc,
+    ) -> Result<ast::Node, String> {
+        let chkd_scrutinee = self.check_node(scrutinee, None)?;
+        let scrutinee_ty = chkd_scrutinee.ty().cloned().unwrap_or_default();
+
+        let mut coverage = match &scrutinee_ty {
+            Type::Enum(name) => Coverage::Variants {
+                enum_name: name.to_owned(),
+                all: self.resolve_enum(name)?.variants().iter().map(|(name, ..)| name.to_owned()).collect(),
+                covered: HashSet::new(),
+            },
+            ty => match value_range(ty) {
+                Some((min, max)) => Coverage::Values { ty: ty.clone(), min, max, covered: vec![] },
+                None => Coverage::Other { covered: false },
+            },
+        };
+
+        let mut chkd_arms = Vec::with_capacity(arms.len());
+        let mut match_ty: Option<Type> = None;
+        for arm in arms {
+            // Bindings are only visible in their own arm
+            self.symbol_table.enter_scope();
+
+            let pattern = self.check_pattern(arm.pattern, &scrutinee_ty)?;
+            if coverage.covers(&pattern) {
+                return Err(format!("Unreachable pattern in match arm: `{}`", pattern));
+            }
+
+            let guard = match arm.guard {
+                Some(guard) => {
+                    let chkd_guard = self.check_node(guard, None)?;
+                    if chkd_guard.ty() != Some(&Type::Bool) {
+                        return Err("Match guard should always be a bool".to_string());
+                    }
+                    Some(chkd_guard)
+                },
+                None => None,
+            };
+
+            let body = self.check_node(arm.body, match_ty.as_ref())?;
+            let body_ty = body.ty().cloned().unwrap_or_default();
+
+            self.symbol_table.leave_scope();
+
+            match &match_ty {
+                Some(ty) if ty != &body_ty => {
+                    return Err(format!("All arms of match must be the same type: `{}` != `{}`", ty, body_ty))
+                },
+                Some(_) => (),
+                None => match_ty = Some(body_ty),
+            }
+
+            if guard.is_none() {
+                coverage.add(&pattern);
+            }
+            chkd_arms.push(MatchArm::new(pattern, guard, body));
+        }
+
+        if let Some(missing) = coverage.missing() {
+            return Err(format!("Non-exhaustive match: `{}` not covered", missing));
+        }
+
+        Ok(ast::Node::new_match(chkd_scrutinee, chkd_arms, match_ty, loc))
+    }
+
+    // Type the pattern and add its bindings to the current scope
+    fn check_pattern(
+        &mut self, pattern: Pattern<ast::Node>, ty: &Type,
+    ) -> Result<Pattern<ast::Node>, String> {
+        match pattern {
+            Pattern::Wildcard => Ok(Pattern::Wildcard),
+            Pattern::Binding(name) => {
+                self.symbol_table.insert(Symbol::new_var(&name, ty, &self.module));
+                Ok(Pattern::Binding(name))
+            },
+            Pattern::Lit(value) => Ok(Pattern::Lit(self.check_lit_pattern(value, ty)?)),
+            Pattern::Range { start, end, inclusive } => {
+                if ty == &Type::Bool {
+                    return Err("Range patterns can't match a `bool`".to_string());
+                }
+                let start = self.check_lit_pattern(start, ty)?;
+                let end = self.check_lit_pattern(end, ty)?;
+                let (lo, hi) = (pattern_value(&start), pattern_value(&end));
+                let pattern = Pattern::Range { start, end, inclusive };
+                if lo > hi || (lo == hi && !inclusive) {
+                    return Err(format!("Range pattern is empty: `{}`", pattern));
+                }
+                Ok(pattern)
+            },
+            Pattern::Variant { enum_name, variant, fields } => {
+                let enum_sym = self.resolve_enum(&enum_name)?;
+                if ty != &Type::Enum(enum_sym.name.clone()) {
+                    return Err(format!("Pattern `{}::{}` doesn't match type `{}`", enum_name, variant, ty));
+                }
+
+                let field_tys = self.resolve_variant_fields(&enum_sym, &variant)?;
+                if field_tys.len() != fields.len() {
+                    return Err(format!(
+                        "Pattern `{}::{}` has {} fields, but the variant has {}",
+                        enum_name,
+                        variant,
+                        fields.len(),
+                        field_tys.len()
+                    ));
+                }
+
+                let fields = fields
+                    .into_iter()
+                    .zip(field_tys)
+                    .map(|(field, field_ty)| self.check_pattern(field, &field_ty))
+                    .collect::<Result<Vec<_>, String>>()?;
+                Ok(Pattern::Variant { enum_name: enum_sym.name, variant, fields })
+            },
+        }
+    }
+
+    // Literal patterns become a literal of the scrutinee's type. A leading `-` is folded
+    // into the value
+    fn check_lit_pattern(&mut self, value: ast::Node, ty: &Type) -> Result<ast::Node, String> {
+        if value_range(ty).is_none() {
+            return Err(format!("Can't match a literal pattern against `{}`", ty));
+        }
+
+        // Negative patterns are checked as a `uint64` magnitude so the type's minimum fits
+        let (value, is_neg) = match value.kind {
+            ast::node::Kind::UnOp { op: Operator::Sub, rhs, .. } => (*rhs, true),
+            kind => (ast::Node { kind }, false),
+        };
+        if is_neg && !matches!(ty, signed_int_types!()) {
+            return Err(format!("Negative pattern can't match `{}`", ty));
+        }
+
+        let hint = if is_neg { &Type::UInt64 } else { ty };
+        let chkd_value = self.check_node(value, Some(hint))?;
+        if chkd_value.ty() != Some(hint) {
+            return Err(format!("Pattern `{}` doesn't match type `{}`", chkd_value, ty));
+        }
+        if !is_neg {
+            return Ok(chkd_value);
+        }
+
+        let v = -pattern_value(&chkd_value);
+        let out_of_range = || format!("Pattern `{}` is out of range for `{}`", v, ty);
+        let lit = match ty {
+            Type::Int8 => Literal::Int8(i8::try_from(v).map_err(|_| out_of_range())?),
+            Type::Int16 => Literal::Int16(i16::try_from(v).map_err(|_| out_of_range())?),
+            Type::Int32 => Literal::Int32(i32::try_from(v).map_err(|_| out_of_range())?),
+            Type::Int64 => Literal::Int64(i64::try_from(v).map_err(|_| out_of_range())?),
+            Type::ISize => Literal::ISize(i64::try_from(v).map_err(|_| out_of_range())?),
+            _ => unreachable!("negative pattern for unsigned type"),
+        };
+        Ok(ast::Node::new_lit(lit, Some(ty.clone())))
+    }
+}
+
+// The values already handled by a match's arms
+enum Coverage {
+    // Disjoint, sorted, inclusive ranges of values between `min` and `max`
+    Values { ty: Type, min: i128, max: i128, covered: Vec<(i128, i128)> },
+    Variants { enum_name: String, all: Vec<String>, covered: HashSet<String> },
+    Other { covered: bool },
+}
+
+impl Coverage {
+    fn covers(&self, pattern: &Pattern<ast::Node>) -> bool {
+        match self {
+            Coverage::Values { min, max, covered, .. } => {
+                let (lo, hi) = pattern_bounds(pattern, *min, *max);
+                covered.iter().any(|(start, end)| *start <= lo && hi <= *end)
+            },
+            Coverage::Variants { all, covered, .. } => match pattern {
+                Pattern::Variant { variant, .. } => covered.contains(variant),
+                _ => all.iter().all(|variant| covered.contains(variant)),
+            },
+            Coverage::Other { covered } => *covered,
+        }
+    }
+
+    fn add(&mut self, pattern: &Pattern<ast::Node>) {
+        match self {
+            Coverage::Values { min, max, covered, .. } => {
+                let (mut lo, mut hi) = pattern_bounds(pattern, *min, *max);
+
+                // Merge with any ranges that overlap or touch the new one
+                covered.retain(|(start, end)| {
+                    if *start > hi.saturating_add(1) || *end < lo.saturating_sub(1) {
+                        return true;
+                    }
+                    lo = lo.min(*start);
+                    hi = hi.max(*end);
+                    false
+                });
+                let idx = covered.iter().position(|(start, _)| *start > hi).unwrap_or(covered.len());
+                covered.insert(idx, (lo, hi));
+            },
+            Coverage::Variants { all, covered, .. } => match pattern {
+                Pattern::Variant { variant, .. } => {
+                    covered.insert(variant.to_owned());
+                },
+                _ => covered.extend(all.iter().cloned()),
+            },
+            Coverage::Other { covered } => *covered |= pattern.is_catch_all(),
+        }
+    }
+
+    // An example of a value that isn't covered yet
+    fn missing(&self) -> Option<String> {
+        match self {
+            Coverage::Values { ty, min, max, covered } => {
+                let mut next = *min;
+                for (start, end) in covered {
+                    if *start > next {
+                        break;
+                    }
+                    next = end + 1;
+                }
+                (next <= *max).then(|| match ty {
+                    Type::Bool => (next == 1).to_string(),
+                    Type::Char => format!("{:?}", next as u8 as char),
+                    _ => next.to_string(),
+                })
+            },
+            Coverage::Variants { enum_name, all, covered } => all
+                .iter()
+                .find(|variant| !covered.contains(*variant))
+                .map(|v| format!("{}::{}", enum_name, v)),
+            Coverage::Other { covered } => (!covered).then(|| "_".to_string()),
+        }
+    }
+}
+
+// The values of the types that can be matched by literal patterns. Pointer-sized types
+// have the host's width, like their literals, and 128-bit values don't fit the coverage
+// ranges
+fn value_range(ty: &Type) -> Option<(i128, i128)> {
+    Some(match ty {
+        Type::Int8 => (i8::MIN as i128, i8::MAX as i128),
+        Type::Int16 => (i16::MIN as i128, i16::MAX as i128),
+        Type::Int32 => (i32::MIN as i128, i32::MAX as i128),
+        Type::Int64 => (i64::MIN as i128, i64::MAX as i128),
+        Type::ISize => (isize::MIN as i128, isize::MAX as i128),
+        Type::UInt8 | Type::Char => (0, u8::MAX as i128),
+        Type::UInt16 => (0, u16::MAX as i128),
+        Type::UInt32 => (0, u32::MAX as i128),
+        Type::UInt64 => (0, u64::MAX as i128),
+        Type::USize => (0, usize::MAX as i128),
+        Type::Bool => (0, 1),
+        _ => return None,
+    })
+}
+
+// The inclusive range of values a typed pattern matches
+fn pattern_bounds(pattern: &Pattern<ast::Node>, min: i128, max: i128) -> (i128, i128) {
+    match pattern {
+        Pattern::Lit(value) => (pattern_value(value), pattern_value(value)),
+        Pattern::Range { start, end, inclusive } => {
+            (pattern_value(start), pattern_value(end) - if *inclusive { 0 } else { 1 })
+        },
+        _ => (min, max),
+    }
+}
+
+fn pattern_value(node: &ast::Node) -> i128 {
+    use Literal::*;
+
+    match &node.kind {
+        ast::node::Kind::Lit { value, .. } => match value {
+            Int8(v) => *v as i128,
+            Int16(v) => *v as i128,
+            Int32(v) => *v as i128,
+            Int64(v) | ISize(v) => *v as i128,
+            UInt8(v) | Char(v) => *v as i128,
+            UInt16(v) => *v as i128,
+            UInt32(v) => *v as i128,
+            UInt64(v) | USize(v) => *v as i128,
+            Bool(v) => *v as i128,
+            v => unreachable!("invalid literal in pattern: `{}`", v),
+        },
+        _ => unreachable!("expected literal in pattern"),
+    }
+}
@@ -2,7 +2,6 @@ use super::*;
 use lex::Lex;
 use parse::Parse;
 
-const ERR_STR: &str = "Numeric literal out of range";
 const MOD_NAME: &str = "main";
 
 macro_rules! run_insta {
@@ -19,6 +18,21 @@ macro_rules! run_insta {
     };
 }
 
+// Snapshots the warnings instead of the typed AST
+macro_rules! run_insta_warnings {
+    ($prefix:expr, $tests:expr) => {
+        insta::with_settings!({ snapshot_path => "tests/snapshots", prepend_module_to_snapshot => false }, {
+            for test in $tests {
+                let tokens = Lex::new(test[1]).scan().expect("lexing failed in `tych` tests");
+                let mut symbol_table = SymbolTable::new();
+                let (ast, _, _) = Parse::new(&tokens, &mut symbol_table).parse().expect("parsing failed in `tych` tests");
+                let res = Tych::new(MOD_NAME, &mut symbol_table).walk_with_warnings(ast).map(|(_, warnings)| warnings);
+                insta::assert_yaml_snapshot!(format!("{}_{}", $prefix, test[0]), (test[1], res));
+            }
+        })
+    };
+}
+
 #[test]
 fn test_binop() {
     let tests = [
@@ -663,7 +677,7 @@ x['c']
 "#,
         ],
         [
-            "index_bad_3",
+            "index_int8",
             r#"
 let x: [int; 3] = [1, 2, 3]
 let y: int8 = 1
@@ -929,6 +943,8 @@ fn main() {
 "#,
         ],
         ["field_init", "struct Foo { let a: int = 1 }"],
+        ["field_init_not_const", "struct Foo { let a: int = 1 + 2 }"],
+        ["field_init_mismatch", "struct Foo { let a: bool = 1 }"],
         [
             "self_type",
             r#"
@@ -941,6 +957,76 @@ struct Foo {
     run_insta!("struct", tests)
 }
 
+#[test]
+fn test_struct_cycle() {
+    let tests = [
+        ["direct", "struct Foo { let a: Foo }"],
+        [
+            "indirect",
+            r#"
+struct A {
+    let b: B
+}
+struct B {
+    let a: [A; 2]
+}
+"#,
+        ],
+        [
+            "ptr",
+            r#"
+struct Node {
+    let val: int
+    let link: *Node
+}
+"#,
+        ],
+    ];
+    run_insta!("struct_cycle", tests);
+}
+
+#[test]
+fn test_struct_eq() {
+    let tests = [
+        [
+            "array",
+            r#"
+fn main() {
+    let a: [int; 2]
+    let b: [int; 2]
+    a != b
+}
+"#,
+        ],
+        [
+            "ptr_field",
+            r#"
+struct Node {
+    let link: *Node
+}
+fn main(a: Node, b: Node) -> bool {
+    a == b
+}
+"#,
+        ],
+    ];
+    run_insta!("struct_eq", tests);
+}
+
+#[test]
+fn test_debug() {
+    let tests = [[
+        "void",
+        r#"
+fn foo() {}
+fn main() {
+    debug(foo())
+}
+"#,
+    ]];
+    run_insta!("debug", tests);
+}
+
 #[test]
 fn test_invalid_types() {
     let tests = [
@@ -978,8 +1064,17 @@ fn test_tych_int_no_hint() {
     let literals = [
         (UInt64(7), Ok(Type::Int32)),
         (UInt64(i32::MAX as u64), Ok(Type::Int32)),
-        (UInt64(i32::MAX as u64 + 1), Err(ERR_STR)),
-        (Float(7.0), Ok(Type::Float)),
+        (
+            UInt64(i32::MAX as u64 + 1),
+            Err("Literal `2147483648` is out of range for `int32` (-2147483648..=2147483647)"),
+        ),
+        (Double(7.0), Ok(Type::Float)),
+        (
+            Double(f64::MAX),
+            Err(
+                "Literal `1.7976931348623157e308` is out of range for `float` (-3.4028235e38..=3.4028235e38)",
+            ),
+        ),
     ];
 
     let mut symbol_table = SymbolTable::new();
@@ -997,29 +1092,37 @@ fn test_tych_int_with_hint() {
     let literals = [
         (UInt64(7), Type::Int8, Ok(Type::Int8)),
         (UInt64(i8::MAX as u64), Type::Int8, Ok(Type::Int8)),
-        (UInt64(i8::MAX as u64 + 1), Type::Int8, Err(ERR_STR)),
+        (UInt64(i8::MAX as u64 + 1), Type::Int8, Err("Literal `128` is out of range for `int8` (-128..=127)")),
         (UInt64(7), Type::Int16, Ok(Type::Int16)),
         (UInt64(i16::MAX as u64), Type::Int16, Ok(Type::Int16)),
-        (UInt64(i16::MAX as u64 + 1), Type::Int16, Err(ERR_STR)),
+        (UInt64(i16::MAX as u64 + 1), Type::Int16, Err("Literal `32768` is out of range for `int16` (-32768..=32767)")),
         (UInt64(7), Type::Int32, Ok(Type::Int32)),
         (UInt64(i32::MAX as u64), Type::Int32, Ok(Type::Int32)),
-        (UInt64(i32::MAX as u64 + 1), Type::Int32, Err(ERR_STR)),
+        (
+            UInt64(i32::MAX as u64 + 1),
+            Type::Int32,
+            Err("Literal `2147483648` is out of range for `int32` (-2147483648..=2147483647)"),
+        ),
         (UInt64(7), Type::Int64, Ok(Type::Int64)),
         (UInt64(i64::MAX as u64), Type::Int64, Ok(Type::Int64)),
-        (UInt64(i64::MAX as u64 + 1), Type::Int64, Err(ERR_STR)),
+        (
+            UInt64(i64::MAX as u64 + 1),
+            Type::Int64,
+            Err("Literal `9223372036854775808` is out of range for `int64` (-9223372036854775808..=9223372036854775807)"),
+        ),
         (UInt64(7), Type::UInt8, Ok(Type::UInt8)),
         (UInt64(u8::MAX as u64), Type::UInt8, Ok(Type::UInt8)),
-        (UInt64(u8::MAX as u64 + 1), Type::UInt8, Err(ERR_STR)),
+        (UInt64(u8::MAX as u64 + 1), Type::UInt8, Err("Literal `256` is out of range for `uint8` (0..=255)")),
         (UInt64(7), Type::UInt16, Ok(Type::UInt16)),
         (UInt64(u16::MAX as u64), Type::UInt16, Ok(Type::UInt16)),
-        (UInt64(u16::MAX as u64 + 1), Type::UInt16, Err(ERR_STR)),
+        (UInt64(u16::MAX as u64 + 1), Type::UInt16, Err("Literal `65536` is out of range for `uint16` (0..=65535)")),
         (UInt64(7), Type::UInt32, Ok(Type::UInt32)),
         (UInt64(u32::MAX as u64), Type::UInt32, Ok(Type::UInt32)),
-        (UInt64(u32::MAX as u64 + 1), Type::UInt32, Err(ERR_STR)),
+        (UInt64(u32::MAX as u64 + 1), Type::UInt32, Err("Literal `4294967296` is out of range for `uint32` (0..=4294967295)")),
         (UInt64(7), Type::UInt64, Ok(Type::UInt64)),
         (UInt64(u64::MAX), Type::UInt64, Ok(Type::UInt64)),
-        (Float(7.0), Type::Float, Ok(Type::Float)),
-        (Float(7.0), Type::Double, Ok(Type::Double)),
+        (Double(7.0), Type::Float, Ok(Type::Float)),
+        (Double(7.0), Type::Double, Ok(Type::Double)),
     ];
 
     let mut symbol_table = SymbolTable::new();
@@ -1040,7 +1143,7 @@ macro_rules! test_lit_hint_binop_int {
         let mut tc = Tych::new(MOD_NAME, &mut st);
         let lhs = ast::Node::new_ident(String::from("x"), None);
         let rhs = ast::Node::new_lit(Literal::UInt64(3), None);
-        let res = tc.visit_binop(Operator::Add, lhs, rhs, None).map(|e| e.ty().unwrap_or_default().clone());
+        let res = tc.visit_binop(Operator::Add, lhs, rhs, None, Loc::default()).map(|e| e.ty().unwrap_or_default().clone());
         assert_eq!(res, Ok($variant));
 
         let mut st = SymbolTable::new();
@@ -1048,7 +1151,7 @@ macro_rules! test_lit_hint_binop_int {
         let mut tc = Tych::new(MOD_NAME, &mut st);
         let lhs = ast::Node::new_lit(Literal::UInt64(3), None);
         let rhs = ast::Node::new_ident(String::from("x"), None);
-        let res = tc.visit_binop(Operator::Add, lhs, rhs, None).map(|e| e.ty().unwrap_or_default().clone());
+        let res = tc.visit_binop(Operator::Add, lhs, rhs, None, Loc::default()).map(|e| e.ty().unwrap_or_default().clone());
         assert_eq!(res, Ok($variant));
     }};
 }
@@ -1061,16 +1164,16 @@ macro_rules! test_lit_hint_binop_float {
         st.insert(Symbol::new_var("x", &$variant, MOD_NAME));
         let mut tc = Tych::new(MOD_NAME, &mut st);
         let lhs = ast::Node::new_ident(String::from("x"), None);
-        let rhs = ast::Node::new_lit(Literal::Float(3.0), None);
-        let res = tc.visit_binop(Operator::Add, lhs, rhs, None).map(|e| e.ty().unwrap_or_default().clone());
+        let rhs = ast::Node::new_lit(Literal::Double(3.0), None);
+        let res = tc.visit_binop(Operator::Add, lhs, rhs, None, Loc::default()).map(|e| e.ty().unwrap_or_default().clone());
         assert_eq!(res, Ok($variant));
 
         let mut st = SymbolTable::new();
         st.insert(Symbol::new_var("x", &$variant, MOD_NAME));
         let mut tc = Tych::new(MOD_NAME, &mut st);
-        let lhs = ast::Node::new_lit(Literal::Float(3.0), None);
+        let lhs = ast::Node::new_lit(Literal::Double(3.0), None);
         let rhs = ast::Node::new_ident(String::from("x"), None);
-        let res = tc.visit_binop(Operator::Add, lhs, rhs, None).map(|e| e.ty().unwrap_or_default().clone());
+        let res = tc.visit_binop(Operator::Add, lhs, rhs, None, Loc::default()).map(|e| e.ty().unwrap_or_default().clone());
         assert_eq!(res, Ok($variant));
     }};
 }
@@ -1090,3 +1193,1646 @@ fn test_tych_binop_lit() {
     test_lit_hint_binop_float!(Float);
     test_lit_hint_binop_float!(Double);
 }
+
+#[test]
+fn test_trait() {
+    let tests = [
+        [
+            "impl",
+            r#"
+trait Len {
+    fn len() -> int
+}
+struct Foo {
+    let size: int
+}
+impl Len for Foo {
+    fn len() -> int { self.size }
+}
+fn main() {
+    let x: Foo
+    x.len()
+}
+"#,
+        ],
+        [
+            "impl_missing_method",
+            r#"
+trait Len {
+    fn len() -> int
+    fn is_empty() -> bool
+}
+struct Foo {}
+impl Len for Foo {
+    fn len() -> int { 1 }
+}
+"#,
+        ],
+        [
+            "impl_bad_sig",
+            r#"
+trait Len {
+    fn len() -> int
+}
+struct Foo {}
+impl Len for Foo {
+    fn len() -> float { 1.0 }
+}
+"#,
+        ],
+        [
+            "impl_extra_method",
+            r#"
+trait Len {
+    fn len() -> int
+}
+struct Foo {}
+impl Len for Foo {
+    fn len() -> int { 1 }
+    fn width() -> int { 1 }
+}
+"#,
+        ],
+        [
+            "impl_unknown_trait",
+            r#"
+struct Foo {}
+impl Len for Foo {
+    fn len() -> int { 1 }
+}
+"#,
+        ],
+        [
+            "generic_call",
+            r#"
+trait Len {
+    fn len() -> int
+}
+struct Foo {
+    let size: int
+}
+impl Len for Foo {
+    fn len() -> int { self.size }
+}
+fn total<T: Len>(x: T, y: T) -> int {
+    x.len() + y.len()
+}
+fn main() {
+    let a: Foo
+    let b: Foo
+    total(a, b)
+}
+"#,
+        ],
+        [
+            "generic_unbound_method",
+            r#"
+trait Len {
+    fn len() -> int
+}
+fn total<T: Len>(x: T) -> int {
+    x.width()
+}
+"#,
+        ],
+        [
+            "generic_missing_impl",
+            r#"
+trait Len {
+    fn len() -> int
+}
+struct Foo {}
+fn total<T: Len>(x: T) -> int {
+    x.len()
+}
+fn main() {
+    let a: Foo
+    total(a)
+}
+"#,
+        ],
+        [
+            "generic_conflict",
+            r#"
+fn pick<T>(x: T, y: T) -> T {
+    x
+}
+fn main() {
+    pick(1, 2.0)
+}
+"#,
+        ],
+        [
+            "generic_ambiguous_bounds",
+            r#"
+trait Len {
+    fn len() -> int
+}
+trait Size {
+    fn len() -> int
+}
+fn total<T: Len + Size>(x: T) -> int {
+    x.len()
+}
+"#,
+        ],
+        [
+            "generic_primitive_bound",
+            r#"
+trait Len {
+    fn len() -> int
+}
+fn total<T: Len>(x: T) -> int {
+    x.len()
+}
+fn main() {
+    total(1)
+}
+"#,
+        ],
+        [
+            "generic_primitive",
+            r#"
+fn id<T>(x: T) -> T {
+    x
+}
+fn main() {
+    let a: int = id(1)
+    let b: float = id(2.0)
+}
+"#,
+        ],
+        [
+            "dyn_call",
+            r#"
+trait Shape {
+    fn area() -> float
+}
+struct Square {
+    let side: float
+}
+impl Shape for Square {
+    fn area() -> float { self.side * self.side }
+}
+fn area_of(s: dyn Shape) -> float {
+    s.area()
+}
+fn main() {
+    let sq: Square
+    let s: dyn Shape = sq
+    let shapes: [dyn Shape; 2] = [sq, s]
+    s = sq
+    area_of(sq) + shapes[1].area()
+}
+"#,
+        ],
+        [
+            "dyn_missing_impl",
+            r#"
+trait Shape {
+    fn area() -> float
+}
+struct Square {
+    let side: float
+}
+impl Shape for Square {
+    fn area() -> float { self.side * self.side }
+}
+struct Circle {
+    let radius: float
+}
+fn main() {
+    let c: Circle
+    let s: dyn Shape = c
+}
+"#,
+        ],
+        [
+            "dyn_unknown_method",
+            r#"
+trait Shape {
+    fn area() -> float
+}
+struct Square {
+    let side: float
+}
+impl Shape for Square {
+    fn area() -> float { self.side * self.side }
+}
+fn main() {
+    let sq: Square
+    let s: dyn Shape = sq
+    s.perimeter()
+}
+"#,
+        ],
+        [
+            "dyn_unknown_trait",
+            r#"
+fn main() {
+    let s: dyn Shape
+}
+"#,
+        ],
+    ];
+    run_insta!("trait", tests);
+}
+
+#[test]
+fn test_enum() {
+    let tests = [
+        [
+            "construct",
+            r#"
+enum Shape {
+    Circle(float),
+    Rect(float, float),
+    Empty
+}
+fn area(s: Shape) -> float {
+    1.0
+}
+fn main() {
+    let s: Shape = Shape::Circle(1.0)
+    s = Shape::Empty
+    area(Shape::Rect(2.0, 3.0))
+}
+"#,
+        ],
+        [
+            "struct_payload",
+            r#"
+struct Point {
+    let x: int
+}
+enum Target {
+    At(Point, Color),
+    Nowhere
+}
+enum Color { Red, Green }
+fn main() {
+    let p: Point
+    let t: Target = Target::At(p, Color::Green)
+}
+"#,
+        ],
+        [
+            "unknown_variant",
+            r#"
+enum Color { Red, Green }
+fn main() {
+    let c: Color = Color::Blue
+}
+"#,
+        ],
+        [
+            "wrong_arg_count",
+            r#"
+enum Shape { Circle(float), Empty }
+fn main() {
+    let s: Shape = Shape::Circle
+}
+"#,
+        ],
+        [
+            "arg_mismatch",
+            r#"
+enum Shape { Circle(float), Empty }
+fn main() {
+    let s: Shape = Shape::Circle(true)
+}
+"#,
+        ],
+        [
+            "type_mismatch",
+            r#"
+enum Color { Red, Green }
+fn main() {
+    let c: int = Color::Red
+}
+"#,
+        ],
+        [
+            "unknown_payload_type",
+            r#"
+enum Shape { Circle(Radius), Empty }
+"#,
+        ],
+        [
+            "recursive",
+            r#"
+enum List { Cons(int, List), Nil }
+"#,
+        ],
+    ];
+    run_insta!("enum", tests);
+}
+
+#[test]
+fn test_match() {
+    let tests = [
+        [
+            "ints",
+            r#"
+fn main() {
+    let x: int8 = 3
+    let y: int = match x {
+        -128..0 => -1,
+        0 => 0,
+        1..=9 => 1,
+        n if n > 100 => 3,
+        _ => 2,
+    }
+}
+"#,
+        ],
+        [
+            "enum",
+            r#"
+enum Shape {
+    Circle(float),
+    Rect(float, float),
+    Empty
+}
+fn area(s: Shape) -> float {
+    match s {
+        Shape::Circle(r) => 3.14 * r * r,
+        Shape::Rect(w, h) if w > 0.0 => w * h,
+        Shape::Rect(_, _) => 0.0,
+        Shape::Empty => 0.0
+    }
+}
+"#,
+        ],
+        [
+            "bool_char",
+            r#"
+fn main() {
+    let b: bool = true
+    let c: char = 'x'
+    match b {
+        true => 1,
+        false => 2
+    }
+    match c {
+        'a'..='z' => 1,
+        _ => 2
+    }
+}
+"#,
+        ],
+        [
+            "binding_float",
+            r#"
+fn main() {
+    let d: float = 1.0
+    match d {
+        x if x > 8.0 => 1,
+        x => 2
+    }
+}
+"#,
+        ],
+        [
+            "non_exhaustive_int",
+            r#"
+fn main() {
+    let x: uint8 = 3
+    match x {
+        0..10 => 1,
+        11..=255 => 2
+    }
+}
+"#,
+        ],
+        [
+            "non_exhaustive_enum",
+            r#"
+enum Color { Red, Green, Blue }
+fn main() {
+    let c: Color = Color::Red
+    match c {
+        Color::Red => 1,
+        Color::Blue => 2
+    }
+}
+"#,
+        ],
+        [
+            "non_exhaustive_guard",
+            r#"
+fn main() {
+    let b: bool = true
+    match b {
+        true => 1,
+        false if b => 2
+    }
+}
+"#,
+        ],
+        [
+            "unreachable",
+            r#"
+fn main() {
+    let x: int = 3
+    match x {
+        0..=5 => 1,
+        _ => 2,
+        3 => 3
+    }
+}
+"#,
+        ],
+        [
+            "unreachable_range",
+            r#"
+fn main() {
+    let x: int = 3
+    match x {
+        0..5 => 1,
+        5..=9 => 2,
+        2..=7 => 3,
+        _ => 4
+    }
+}
+"#,
+        ],
+        [
+            "arm_type_mismatch",
+            r#"
+fn main() {
+    let x: int = 3
+    match x {
+        0 => 1,
+        _ => true
+    }
+}
+"#,
+        ],
+        [
+            "lit_type_mismatch",
+            r#"
+fn main() {
+    let x: int = 3
+    match x {
+        'a' => 1,
+        _ => 2
+    }
+}
+"#,
+        ],
+        [
+            "negative_unsigned",
+            r#"
+fn main() {
+    let x: uint = 3
+    match x {
+        -1 => 1,
+        _ => 2
+    }
+}
+"#,
+        ],
+        [
+            "empty_range",
+            r#"
+fn main() {
+    let x: int = 3
+    match x {
+        5..5 => 1,
+        _ => 2
+    }
+}
+"#,
+        ],
+        [
+            "wrong_enum",
+            r#"
+enum Color { Red, Green }
+enum Shape { Empty }
+fn main() {
+    let c: Color = Color::Red
+    match c {
+        Shape::Empty => 1,
+        _ => 2
+    }
+}
+"#,
+        ],
+        [
+            "field_count",
+            r#"
+enum Shape { Circle(float), Empty }
+fn main() {
+    let s: Shape = Shape::Empty
+    match s {
+        Shape::Circle => 1,
+        _ => 2
+    }
+}
+"#,
+        ],
+        [
+            "float_lit",
+            r#"
+fn main() {
+    let d: float = 1.0
+    match d {
+        1.0 => 1,
+        _ => 2
+    }
+}
+"#,
+        ],
+        [
+            "binding_scope",
+            r#"
+fn main() {
+    let x: int = 3
+    match x {
+        n => n
+    }
+    n
+}
+"#,
+        ],
+    ];
+    run_insta!("match", tests);
+}
+
+#[test]
+fn test_tuple() {
+    let tests = [
+        [
+            "basic",
+            r#"
+struct Foo {
+    let x: int
+}
+fn divmod(a: int, b: int) -> (int, int) {
+    (a / b, a - a / b * b)
+}
+fn main() {
+    let foo: Foo
+    let t: (uint8, (bool, double), Foo) = (1, (true, 2.5), foo)
+    t.1.0 = false
+    let (q, _) = divmod(7, 2)
+    let (x, y): (int, int) = (q, divmod(9, 4).1)
+    let z: double = t.1.1
+}
+"#,
+        ],
+        [
+            "no_antn",
+            r#"
+fn main() {
+    let t: (int, float)
+    let (a, b) = (1, 2.0)
+}
+"#,
+        ],
+        [
+            "bad_index",
+            r#"
+fn main() {
+    let t: (int, float) = (1, 2.0)
+    let x: int = t.2
+}
+"#,
+        ],
+        [
+            "lit_mismatch",
+            r#"
+fn main() {
+    let t: (int, float) = (1, true)
+}
+"#,
+        ],
+        [
+            "destructure_count",
+            r#"
+fn main() {
+    let (a, b, c) = (1, 2)
+}
+"#,
+        ],
+        [
+            "destructure_non_tuple",
+            r#"
+fn main() {
+    let (a, b) = 1
+}
+"#,
+        ],
+        [
+            "destructure_antn_mismatch",
+            r#"
+fn main() {
+    let (a, b): (int, bool) = (1, 2)
+}
+"#,
+        ],
+        [
+            "field_on_int",
+            r#"
+fn main() {
+    let x: int = 1
+    let y: int = x.0
+}
+"#,
+        ],
+    ];
+    run_insta!("tuple", tests);
+}
+
+#[test]
+fn test_closure() {
+    let tests = [
+        [
+            "by_ref",
+            r#"
+fn main() {
+    let offset: int = 5
+    let count: int
+    let add = |x: int| -> int {
+        count += 1
+        x + offset
+    }
+    let z: int = add(1)
+}
+"#,
+        ],
+        [
+            "move",
+            r#"
+fn main() {
+    let offset: int = 5
+    let add = move |x: int| -> int { x + offset }
+    add(1)
+}
+"#,
+        ],
+        [
+            "nested",
+            r#"
+fn main() {
+    let a: int = 1
+    let outer = || -> int {
+        let b: int = 2
+        let inner = |c: int| -> int { a + b + c }
+        inner(3)
+    }
+    outer()
+}
+"#,
+        ],
+        [
+            "method_self",
+            r#"
+struct Foo {
+    let x: int
+
+    fn bump(n: int) -> int {
+        let f = |m: int| -> int { self.x + m }
+        f(n)
+    }
+}
+fn main() {}
+"#,
+        ],
+        [
+            "inferred_let",
+            r#"
+fn main() {
+    let x = 1
+    let y = 2.5
+    let t = (x, true)
+}
+"#,
+        ],
+        [
+            "infer_void",
+            r#"
+fn foo() {}
+fn main() {
+    let x = foo()
+}
+"#,
+        ],
+        [
+            "infer_array",
+            r#"
+fn main() {
+    let a = [1, 2, 3]
+}
+"#,
+        ],
+        [
+            "ret_mismatch",
+            r#"
+fn main() {
+    let f = |x: int| -> bool { x }
+}
+"#,
+        ],
+        [
+            "arg_count",
+            r#"
+fn main() {
+    let f = |x: int| -> int { x }
+    f(1, 2)
+}
+"#,
+        ],
+        [
+            "arg_mismatch",
+            r#"
+fn main() {
+    let f = |x: int| -> int { x }
+    f(true)
+}
+"#,
+        ],
+        [
+            "not_callable",
+            r#"
+fn main() {
+    let x: int = 1
+    x(2)
+}
+"#,
+        ],
+        [
+            "move_ptr_arg",
+            r#"
+struct Foo {
+    let x: int
+}
+fn bar(foo: *Foo) {
+    let f = move || { let p = foo }
+}
+fn main() {}
+"#,
+        ],
+        [
+            "param_out_of_scope",
+            r#"
+fn main() {
+    let f = |x: int| -> int { x }
+    let y: int = x
+}
+"#,
+        ],
+    ];
+    run_insta!("closure", tests);
+}
+
+#[test]
+fn test_fn_ptr() {
+    let tests = [
+        [
+            "basic",
+            r#"
+fn less(a: int, b: int) -> bool { a < b }
+fn apply(f: fn(int, int) -> bool, x: int, y: int) -> bool { f(x, y) }
+fn main() {
+    let f: fn(int, int) -> bool = less
+    let g = less
+    f(1, 2)
+    apply(g, 3, 4)
+}
+"#,
+        ],
+        [
+            "struct_field",
+            r#"
+struct Foo {
+    let x: int
+}
+struct Handler {
+    let cb: fn(Foo) -> int
+}
+fn getX(foo: Foo) -> int { foo.x }
+fn main() {
+    let foo: Foo
+    let h: Handler
+    h.cb = getX
+    h.cb(foo)
+}
+"#,
+        ],
+        [
+            "extern",
+            r#"
+extern fn atexit(cb: fn()) -> int
+fn bye() {}
+fn main() {
+    atexit(bye)
+}
+"#,
+        ],
+        [
+            "captured",
+            r#"
+fn double(x: int) -> int { x * 2 }
+fn main() {
+    let f = double
+    let g = |x: int| -> int { f(x) + 1 }
+    g(2)
+}
+"#,
+        ],
+        [
+            "mismatch",
+            r#"
+fn less(a: int, b: int) -> bool { a < b }
+fn main() {
+    let f: fn(int) -> bool = less
+}
+"#,
+        ],
+        [
+            "arg_count",
+            r#"
+fn less(a: int, b: int) -> bool { a < b }
+fn main() {
+    let f = less
+    f(1)
+}
+"#,
+        ],
+        [
+            "arg_mismatch",
+            r#"
+fn less(a: int, b: int) -> bool { a < b }
+fn main() {
+    let f = less
+    f(1, true)
+}
+"#,
+        ],
+        [
+            "field_arg_mismatch",
+            r#"
+struct Handler {
+    let cb: fn(int)
+}
+fn main() {
+    let h: Handler
+    h.cb(true)
+}
+"#,
+        ],
+        [
+            "generic",
+            r#"
+fn id<T>(x: T) -> T { x }
+fn main() {
+    let f = id
+}
+"#,
+        ],
+        [
+            "unknown_type",
+            r#"
+fn main() {
+    let f: fn(Bar)
+}
+"#,
+        ],
+    ];
+    run_insta!("fn_ptr", tests);
+}
+
+#[test]
+fn test_cast() {
+    let tests = [
+        [
+            "numeric",
+            r#"
+fn main() {
+    let i = 300
+    let a = i as uint8
+    let b = i as float + 1.5
+    let c = b as int64
+    let d = 'a' as uint8
+    let e = d as char
+    let f = true as int
+    let g = b as double
+    let h = i as int
+}
+"#,
+        ],
+        [
+            "trait_object",
+            r#"
+trait Shape {
+    fn area() -> int
+}
+struct Square {
+    let side: int
+}
+impl Shape for Square {
+    fn area() -> int { self.side * self.side }
+}
+fn main() {
+    let s: Square
+    let d = s as dyn Shape
+}
+"#,
+        ],
+        [
+            "int_to_bool",
+            r#"
+fn main() {
+    let x = 1 as bool
+}
+"#,
+        ],
+        [
+            "float_to_char",
+            r#"
+fn main() {
+    let x = 1.5 as char
+}
+"#,
+        ],
+        [
+            "struct_to_int",
+            r#"
+struct Foo {
+    let x: int
+}
+fn main() {
+    let f: Foo
+    let x = f as int
+}
+"#,
+        ],
+        [
+            "unknown_type",
+            r#"
+fn main() {
+    let x = 1 as Bar
+}
+"#,
+        ],
+    ];
+    run_insta!("cast", tests);
+}
+
+#[test]
+fn test_int_sizes() {
+    let tests = [
+        [
+            "basic",
+            r#"
+fn main() {
+    let a: isize = 1
+    let b: usize = 18446744073709551615
+    let c: int128 = 9223372036854775807
+    let d: uint128
+    let e = a as int128 + c
+}
+"#,
+        ],
+        [
+            "index",
+            r#"
+fn main() {
+    let x: [int; 3] = [1, 2, 3]
+    let i: usize = 2
+    let j: uint8 = 1
+    x[i] + x[j]
+}
+"#,
+        ],
+        [
+            "match_usize",
+            r#"
+fn main() {
+    let x: usize = 2
+    match x {
+        0 => 1,
+        1..=18446744073709551615 => 2,
+    }
+}
+"#,
+        ],
+        [
+            "isize_mismatch",
+            r#"
+fn main() {
+    let x: isize = 1
+    let y: int64 = x
+}
+"#,
+        ],
+        [
+            "match_int128",
+            r#"
+fn main() {
+    let x: int128 = 2
+    match x {
+        0 => 1,
+        _ => 2,
+    }
+}
+"#,
+        ],
+        [
+            "wide_literals",
+            r#"
+fn main() {
+    let a: uint128 = 340282366920938463463374607431768211455
+    let b: int128 = -170141183460469231731687303715884105728
+    let c: int128 = 18446744073709551616
+}
+"#,
+        ],
+        ["wide_int128_over", "let x: int128 = 170141183460469231731687303715884105728"],
+        ["wide_int64_over", "let x: int64 = 18446744073709551616"],
+        ["wide_neg_over", "let x: int128 = -170141183460469231731687303715884105729"],
+        ["wide_in_float", "let x: float = 18446744073709551616"],
+        ["isize_over", "let x: isize = 9223372036854775808"],
+    ];
+    run_insta!("int_sizes", tests);
+}
+
+#[test]
+fn test_lit_range() {
+    let tests = [
+        [
+            "in_range",
+            r#"
+fn main() {
+    let a: int8 = -128
+    let b: uint8 = 255
+    let c: [int16; 2] = [-32768, 32767]
+    let d = -2147483648
+    let e: double = -340282350000000000000000000000000000000000.0
+    let f: uint64 = 18446744073709551615
+}
+"#,
+        ],
+        ["int8_over", "let x: int8 = 128"],
+        ["int8_under", "let x: int8 = -129"],
+        ["uint8_neg", "let x: uint8 = -1"],
+        ["int32_default", "let x = 2147483648"],
+        ["array_element", "let x: [uint8; 3] = [1, 256, 3]"],
+        ["float_over", "let x: float = 340282350000000000000000000000000000000000.0"],
+        ["float_neg_over", "let x = -340282350000000000000000000000000000000000.0"],
+        [
+            "float_in_struct",
+            r#"
+struct Point {
+    let x: float
+}
+fn main() {
+    let p: Point = 1.0
+}
+"#,
+        ],
+    ];
+    run_insta!("lit_range", tests);
+}
+
+#[test]
+fn test_panic() {
+    let tests = [
+        [
+            "diverging_fn",
+            r#"
+fn foo(a: int) -> int {
+    panic("no int for you")
+}
+"#,
+        ],
+        [
+            "bad_escape",
+            r#"
+fn main() {
+    panic("no int\q")
+}
+"#,
+        ],
+    ];
+    run_insta!("panic", tests);
+}
+
+#[test]
+fn test_assert() {
+    let tests = [
+        [
+            "basic",
+            r#"
+fn foo(a: int) {
+    assert(a > 0, "positive")
+}
+"#,
+        ],
+        [
+            "non_bool",
+            r#"
+fn foo(a: int) {
+    assert(a + 1)
+}
+"#,
+        ],
+    ];
+    run_insta!("assert", tests);
+}
+
+#[test]
+fn test_struct_lit() {
+    let tests = [
+        [
+            "basic",
+            r#"
+struct Point {
+    let x: int
+    let y: int
+}
+fn main() {
+    let y = 2
+    let p = Point { y, x: 1 }
+}
+"#,
+        ],
+        [
+            "missing_field",
+            r#"
+struct Point {
+    let x: int
+    let y: int
+}
+fn main() {
+    let p = Point { x: 1 }
+}
+"#,
+        ],
+        [
+            "default_field",
+            r#"
+struct Point {
+    let x: int
+    let y: int = 2
+}
+fn main() {
+    let p = Point { x: 1 }
+}
+"#,
+        ],
+        [
+            "unknown_field",
+            r#"
+struct Point {
+    let x: int
+    let y: int
+}
+fn main() {
+    let p = Point { x: 1, y: 2, z: 3 }
+}
+"#,
+        ],
+        [
+            "duplicate_field",
+            r#"
+struct Point {
+    let x: int
+    let y: int
+}
+fn main() {
+    let p = Point { x: 1, x: 2 }
+}
+"#,
+        ],
+        [
+            "type_mismatch",
+            r#"
+struct Point {
+    let x: int
+    let y: int
+}
+fn main() {
+    let p = Point { x: 1, y: true }
+}
+"#,
+        ],
+        ["unknown_struct", "let p = Nope { x: 1 }"],
+    ];
+    run_insta!("struct_lit", tests);
+}
+
+#[test]
+fn test_assoc_fn() {
+    let tests = [
+        [
+            "basic",
+            r#"
+struct Point {
+    let x: int
+    static fn zero() -> int {
+        0
+    }
+}
+fn main() {
+    let z = Point::zero()
+}
+"#,
+        ],
+        [
+            "method",
+            r#"
+struct Point {
+    fn norm() -> int {
+        0
+    }
+}
+fn main() {
+    let n = Point::norm()
+}
+"#,
+        ],
+        [
+            "unknown",
+            r#"
+struct Point {}
+fn main() {
+    Point::nope()
+}
+"#,
+        ],
+        [
+            "no_self",
+            r#"
+struct Point {
+    let x: int
+    static fn get() -> int {
+        self.x
+    }
+}
+"#,
+        ],
+        [
+            "wrong_args",
+            r#"
+struct Point {
+    static fn zero() -> int {
+        0
+    }
+}
+fn main() {
+    let z = Point::zero(1)
+}
+"#,
+        ],
+    ];
+    run_insta!("assoc_fn", tests);
+}
+
+#[test]
+fn test_constructor() {
+    let tests = [
+        [
+            "default",
+            r#"
+struct Point {
+    let x: int
+    let y: int = 2
+}
+fn main() {
+    let p = Point()
+}
+"#,
+        ],
+        [
+            "no_init",
+            r#"
+struct Point {
+    let x: int
+}
+fn main() {
+    let p = Point(1)
+}
+"#,
+        ],
+        [
+            "init_args",
+            r#"
+struct Point {
+    let x: int
+    fn init(x: int) {
+        self.x = x
+    }
+}
+fn main() {
+    let p = Point(1, 2)
+}
+"#,
+        ],
+    ];
+    run_insta!("constructor", tests);
+}
+
+#[test]
+fn test_attrs() {
+    let tests = [
+        [
+            "fn",
+            r#"
+#[inline]
+#[must_use]
+fn foo() -> int {
+    1
+}
+"#,
+        ],
+        [
+            "struct_inline",
+            r#"
+#[inline]
+struct Foo {}
+"#,
+        ],
+        [
+            "field",
+            r#"
+struct Foo {
+    #[deprecated]
+    let x: int
+}
+"#,
+        ],
+        [
+            "inline_noinline",
+            r#"
+#[inline]
+#[noinline]
+fn foo() {}
+"#,
+        ],
+        [
+            "duplicate",
+            r#"
+#[cold]
+#[cold]
+fn foo() {}
+"#,
+        ],
+        [
+            "export_name_extern",
+            r#"
+#[export_name("c_cos")]
+extern fn cos(x: float) -> float
+"#,
+        ],
+        [
+            "must_use_let",
+            r#"
+fn foo() {
+    #[must_use]
+    let x = 1
+}
+"#,
+        ],
+    ];
+    run_insta!("attrs", tests);
+}
+
+#[test]
+fn test_attr_warnings() {
+    let tests = [
+        [
+            "deprecated_fn",
+            r#"
+#[deprecated("use bar()")]
+fn foo() {}
+fn main() {
+    foo()
+}
+"#,
+        ],
+        [
+            "deprecated_struct",
+            r#"
+#[deprecated]
+struct Foo {
+    let x: int
+}
+fn main() {
+    let a = Foo { x: 1 }
+    let b = Foo()
+}
+"#,
+        ],
+        [
+            "deprecated_var",
+            r#"
+fn main() {
+    #[deprecated("use y")]
+    let x = 1
+    x + 1
+    x + 2
+}
+"#,
+        ],
+        [
+            "must_use_fn",
+            r#"
+#[must_use]
+fn foo() -> int {
+    1
+}
+fn main() {
+    foo()
+    let x = foo()
+    foo()
+}
+"#,
+        ],
+        [
+            "must_use_method",
+            r#"
+struct Foo {
+    #[must_use]
+    fn get() -> int {
+        1
+    }
+}
+fn main() {
+    let f = Foo()
+    f.get()
+    f.get()
+}
+"#,
+        ],
+        [
+            "must_use_impl_method",
+            r#"
+trait Getter {
+    fn get() -> int
+}
+struct Foo {
+    let x: int
+}
+impl Getter for Foo {
+    #[must_use]
+    fn get() -> int {
+        self.x
+    }
+}
+fn main() {
+    let f = Foo()
+    f.get()
+    f.get()
+}
+"#,
+        ],
+        [
+            "must_use_dyn_method",
+            r#"
+trait Getter {
+    fn get() -> int
+}
+struct Foo {
+    let x: int
+}
+impl Getter for Foo {
+    fn get() -> int {
+        self.x
+    }
+}
+#[must_use]
+fn get() -> int {
+    1
+}
+fn main() {
+    let g: dyn Getter = Foo()
+    g.get()
+    g.get()
+}
+"#,
+        ],
+        [
+            "must_use_struct",
+            r#"
+#[must_use]
+struct Foo {
+    let x: int
+}
+fn make() -> Foo {
+    Foo { x: 1 }
+}
+fn main() {
+    make()
+    make()
+}
+"#,
+        ],
+    ];
+    run_insta_warnings!("attr_warnings", tests);
+}
+
+#[test]
+fn test_variadic() {
+    let tests = [
+        [
+            "promote",
+            r#"
+extern fn printf(fmt: *char, ...) -> int
+fn main() {
+    let c: int8 = 1
+    printf("%d %f\n", c, 2.5)
+}
+"#,
+        ],
+        [
+            "too_few",
+            r#"
+extern fn printf(fmt: *char, ...) -> int
+fn main() {
+    printf()
+}
+"#,
+        ],
+        [
+            "struct_arg",
+            r#"
+extern fn printf(fmt: *char, ...) -> int
+struct Foo {}
+fn main() {
+    let f: Foo
+    printf("%d", f)
+}
+"#,
+        ],
+        [
+            "fn_ref",
+            r#"
+extern fn printf(fmt: *char, ...) -> int
+fn main() {
+    let p = printf
+}
+"#,
+        ],
+        [
+            "bad_escape",
+            r#"
+extern fn printf(fmt: *char, ...) -> int
+fn main() {
+    printf("%d\q", 1)
+}
+"#,
+        ],
+    ];
+    run_insta!("variadic", tests);
+}
+
+#[test]
+fn test_print() {
+    let tests = [
+        [
+            "basic",
+            r#"
+fn foo(a: int, b: bool) {
+    println("a = {}, b = {}", a, b)
+}
+"#,
+        ],
+        [
+            "count",
+            r#"
+fn foo(a: int) {
+    println("{} {}", a)
+}
+"#,
+        ],
+        [
+            "bad_placeholder",
+            r#"
+fn foo(a: int) {
+    print("{a}", a)
+}
+"#,
+        ],
+        [
+            "void",
+            r#"
+fn foo() {}
+fn main() {
+    println("{}", foo())
+}
+"#,
+        ],
+        [
+            "bad_escape",
+            r#"
+fn main() {
+    print("tab\q")
+}
+"#,
+        ],
+    ];
+    run_insta!("print", tests);
+}
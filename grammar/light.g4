@@ -10,28 +10,58 @@ stmt                 : let_stmt
                      | fn_decl
                      | extern_decl
                      | struct_decl
+                     | trait_decl
+                     | enum_decl
+                     | impl_decl
                      | use_stmt
                      | break_stmt
                      | next_stmt
+                     | panic_stmt
+                     | assert_stmt
+                     | debug_stmt
+                     | print_stmt
                      | expr;
 mod_decl             : 'module' IDENT ';';
 block                : '{' stmt_list? '}';
-fn_decl              : proto block;
-extern_decl          : 'extern' proto;
-struct_decl          : 'struct' IDENT '{' (let_stmt ';' | fn_decl ';')* '}' ;
-proto                : 'fn' IDENT '(' (typed_decl (',' typed_decl)*)* ')' ('->' type_antn)?;
+fn_decl              : attr* proto block;
+extern_decl          : attr* 'extern' proto;
+struct_decl          : attr* 'struct' IDENT '{' (let_stmt ';' | 'static'? fn_decl ';')* '}' ;
+attr                 : '#' '[' IDENT ('(' attr_arg (',' attr_arg)* ')')? ']' ;
+attr_arg             : IDENT | NUMBER | STRING ;
+trait_decl           : 'trait' IDENT '{' (proto ';')* '}' ;
+enum_decl            : 'enum' IDENT '{' (variant (',' variant)* ','?)? '}' ;
+variant              : IDENT ('(' type_antn (',' type_antn)* ')')? ('=' '-'? NUMBER)?;
+impl_decl            : 'impl' IDENT 'for' IDENT '{' (fn_decl ';')* '}' ;
+proto                : 'fn' IDENT generics? '(' (typed_decl (',' typed_decl)* (',' '...')?)? ')' ('->' type_antn)?;
+generics             : '<' generic_param (',' generic_param)* '>';
+generic_param        : IDENT (':' IDENT ('+' IDENT)*)?;
 for_stmt             : 'for' var_init ';' expr ';' NUMBER? block;
 loop_stmt            : 'loop' block;
 while_stmt           : 'while' expr block;
-let_stmt             : 'let' var_init;
+let_stmt             : attr* 'let' (var_init | IDENT '=' expr | destructure);
+destructure          : '(' destruct_name (',' destruct_name)+ ')' (':' type_antn)? '=' expr;
+destruct_name        : '_' | IDENT;
 var_init             : typed_decl ('=' expr)?;
 typed_decl           : IDENT ':' type_antn;
 type_antn            : TYPE
-                     | '[' TYPE ']';
+                     | dyn_type
+                     | tuple_type
+                     | fn_type
+                     | ptr_type
+                     | '[' (TYPE | dyn_type) ']';
+ptr_type             : '*' type_antn;
+tuple_type           : '(' type_antn (',' type_antn)+ ')';
+dyn_type             : 'dyn' IDENT;
+fn_type              : 'fn' '(' (type_antn (',' type_antn)*)? ')' ('->' type_antn)?;
 use_stmt             : 'use' IDENT ;
 break_stmt           : 'break' ;
 next_stmt            : 'next' ;
+panic_stmt           : 'panic' '(' STRING ')' ;
+assert_stmt          : ('assert' | 'debug_assert') '(' expr (',' STRING)? ')' ;
+debug_stmt           : 'debug' '(' expr ')' ;
+print_stmt           : ('print' | 'println') '(' STRING (',' expr)* ')' ;
 expr                 : primary_expr
+                     | expr 'as' type_antn
                      | expr ('*' | '/') expr
                      | expr ('+' | '-') expr
                      | expr ('>' | '>=' | '<' | '<=') expr
@@ -41,24 +71,30 @@ expr                 : primary_expr
                      | expr '||' expr
                      | assignable_expr ('=' | '+=' | '-=') expr;
 primary_expr         : cond_expr
+                     | match_expr
                      | self_expr
                      | lit_expr
                      | ident_expr
                      | call_expr
+                     | struct_lit_expr
                      | block
                      | paren_expr
+                     | tuple_expr
+                     | closure_expr
                      | unop_expr
                      | primary_expr '[' expr ']'
-                     | primary_expr '.' (ident_expr | call_expr);
+                     | primary_expr '.' (ident_expr | call_expr | NUMBER);
 // ANTLR doesn't do mutual left recursion, so some expressions are defined as directly
 // recursive above. The Light parser handles this properly.
 //                   | index_expr
 //                   | field_selector_expr;
+//                   | tuple_field_expr;
 //                   | method_selector_expr;
 index_expr           : primary_expr '[' expr ']';
 field_selector_expr  : primary_expr '.' ident_expr;
+tuple_field_expr     : primary_expr '.' NUMBER;
 self_expr            : 'self' '.' (ident_expr | call_expr);
-assignable_expr      : ident_expr | index_expr | self_expr | field_selector_expr;
+assignable_expr      : ident_expr | index_expr | self_expr | field_selector_expr | tuple_field_expr;
 unop_expr            : ('-' | '!') expr;
 lit_expr             : NUMBER
                      | BOOL
@@ -66,8 +102,20 @@ lit_expr             : NUMBER
                      | string_lit
                      | array_lit;
 call_expr            : IDENT '(' expr_list? ')';
+struct_lit_expr      : IDENT '{' (field_init (',' field_init)* ','?)? '}';
+field_init           : IDENT (':' expr)?;
 paren_expr           : '(' expr ')';
+tuple_expr           : '(' expr (',' expr)+ ','? ')';
+closure_expr         : 'move'? ('||' | '|' (typed_decl (',' typed_decl)* ','?)? '|') ('->' type_antn)? block;
 cond_expr            : 'if' expr block ('else' (cond_expr | block))?;
+match_expr           : 'match' expr '{' (match_arm (',' match_arm)* ','?)? '}';
+match_arm            : pattern ('if' expr)? '=>' expr;
+pattern              : '_'
+                     | IDENT
+                     | lit_pattern (('..' | '..=') lit_pattern)?
+                     | IDENT '(' field_pattern (',' field_pattern)* ')';
+lit_pattern          : '-'? NUMBER | BOOL | char_lit;
+field_pattern        : '_' | IDENT;
 ident_expr           : IDENT;
 array_lit            : '[' expr_list? ']';
 char_lit             : CHAR;
@@ -79,11 +127,15 @@ TYPE                 : 'int'
                      | 'int16'
             	     | 'int32'
             	     | 'int64'
+            	     | 'int128'
+            	     | 'isize'
             	     | 'uint'
             	     | 'uint8'
             	     | 'uint16'
             	     | 'uint32'
             	     | 'uint64'
+            	     | 'uint128'
+            	     | 'usize'
             	     | 'float'
                      | 'double'
                      | 'bool'